@@ -1,9 +1,11 @@
-use std::io::{stderr, Write};
 use std::mem;
 
 use peg_parser::{Parser, ParserData};
 
-use crate::laze_parser::matcher::extract_ast;
+use crate::{
+    laze_parser::matcher::extract_ast,
+    util::diagnostic::{report, Diagnostic, DiagnosticCode},
+};
 
 use super::{
     dec::{self, ClassMemberList, Dec, DecData, DecList, Dec_},
@@ -48,7 +50,11 @@ impl ASTNode {
         if let ASTNode::Var(var) = self {
             var
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a declaration.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a declaration."),
+            ));
             Box::new(Var_ {
                 pos,
                 data: VarData::None,
@@ -67,7 +73,11 @@ impl ASTNode {
                     mem::swap(&mut declist[0], &mut temp_dec);
                     temp_dec
                 } else {
-                    let _ = writeln!(stderr(), "{name} in {rule} is not a declaration.");
+                    report(Diagnostic::error(
+                        DiagnosticCode::UnexpectedNode,
+                        pos,
+                        format!("{name} in {rule} is not a declaration."),
+                    ));
                     Box::new(Dec_ {
                         pos,
                         data: DecData::None,
@@ -75,7 +85,11 @@ impl ASTNode {
                 }
             }
             _ => {
-                let _ = writeln!(stderr(), "{name} in {rule} is not a declaration.");
+                report(Diagnostic::error(
+                    DiagnosticCode::UnexpectedNode,
+                    pos,
+                    format!("{name} in {rule} is not a declaration."),
+                ));
                 Box::new(Dec_ {
                     pos,
                     data: DecData::None,
@@ -83,11 +97,15 @@ impl ASTNode {
             }
         }
     }
-    pub fn get_declist_data(self, _pos: (usize, usize), name: &str, rule: &str) -> DecList {
+    pub fn get_declist_data(self, pos: (usize, usize), name: &str, rule: &str) -> DecList {
         if let ASTNode::DecList(declist) = self {
             declist
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a declaration list.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a declaration list."),
+            ));
             vec![]
         }
     }
@@ -103,7 +121,11 @@ impl ASTNode {
                     mem::swap(&mut stmlist[0], &mut temp_stm);
                     temp_stm
                 } else {
-                    let _ = writeln!(stderr(), "{name} in {rule} is not a statement.");
+                    report(Diagnostic::error(
+                        DiagnosticCode::UnexpectedNode,
+                        pos,
+                        format!("{name} in {rule} is not a statement."),
+                    ));
                     Box::new(Stm_ {
                         pos,
                         data: StmData::None,
@@ -111,7 +133,11 @@ impl ASTNode {
                 }
             }
             _ => {
-                let _ = writeln!(stderr(), "{name} in {rule} is not a statement.");
+                report(Diagnostic::error(
+                    DiagnosticCode::UnexpectedNode,
+                    pos,
+                    format!("{name} in {rule} is not a statement."),
+                ));
                 Box::new(Stm_ {
                     pos,
                     data: StmData::None,
@@ -119,11 +145,15 @@ impl ASTNode {
             }
         }
     }
-    pub fn get_stmlist_data(self, _pos: (usize, usize), name: &str, rule: &str) -> StmList {
+    pub fn get_stmlist_data(self, pos: (usize, usize), name: &str, rule: &str) -> StmList {
         if let ASTNode::StmList(stmlist) = self {
             stmlist
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a statement list.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a statement list."),
+            ));
             vec![]
         }
     }
@@ -145,7 +175,11 @@ impl ASTNode {
                 temp_exp
             }
             _ => {
-                let _ = writeln!(stderr(), "{name} in {rule} is not an expression.");
+                report(Diagnostic::error(
+                    DiagnosticCode::UnexpectedNode,
+                    pos,
+                    format!("{name} in {rule} is not an expression."),
+                ));
                 return Box::new(ASTExp_ {
                     pos,
                     data: ASTExpData::None,
@@ -153,24 +187,32 @@ impl ASTNode {
             }
         }
     }
-    pub fn get_explist_data(self, _pos: (usize, usize), name: &str, rule: &str) -> ASTExpList {
+    pub fn get_explist_data(self, pos: (usize, usize), name: &str, rule: &str) -> ASTExpList {
         if let ASTNode::ExpList(explist) = self {
             explist
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not an expression list.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not an expression list."),
+            ));
             vec![]
         }
     }
     pub fn get_classmembers_data(
         self,
-        _pos: (usize, usize),
+        pos: (usize, usize),
         name: &str,
         rule: &str,
     ) -> ClassMemberList {
         if let ASTNode::ClassMemberList(members) = self {
             members
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not an class members list.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a class member list."),
+            ));
             vec![]
         }
     }
@@ -178,18 +220,26 @@ impl ASTNode {
         if let ASTNode::Type(ty) = self {
             ty
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a type.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a type."),
+            ));
             Box::new(Type_ {
                 pos,
                 data: TypeData::None,
             })
         }
     }
-    pub fn get_tylist_data(self, _pos: (usize, usize), name: &str, rule: &str) -> TypeList {
+    pub fn get_tylist_data(self, pos: (usize, usize), name: &str, rule: &str) -> TypeList {
         if let ASTNode::TypeList(tylist) = self {
             tylist
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a type.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a type."),
+            ));
             vec![]
         }
     }
@@ -197,73 +247,105 @@ impl ASTNode {
         if let ASTNode::Field(field) = self {
             field
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a field.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a field."),
+            ));
             Box::new(Field_ {
                 pos,
                 data: FieldData::None,
             })
         }
     }
-    pub fn get_oper_data(self, _pos: (usize, usize), name: &str, rule: &str) -> Oper {
+    pub fn get_oper_data(self, pos: (usize, usize), name: &str, rule: &str) -> Oper {
         if let ASTNode::Op(oper) = self {
             oper
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a field.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a field."),
+            ));
             Oper::None
         }
     }
-    pub fn get_fieldlist_data(self, _pos: (usize, usize), name: &str, rule: &str) -> FieldList {
+    pub fn get_fieldlist_data(self, pos: (usize, usize), name: &str, rule: &str) -> FieldList {
         if let ASTNode::FieldList(fieldlist) = self {
             fieldlist
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a fieldlist.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a fieldlist."),
+            ));
             vec![]
         }
     }
-    pub fn get_string_data(self, _pos: (usize, usize), name: &str, rule: &str) -> String {
+    pub fn get_string_data(self, pos: (usize, usize), name: &str, rule: &str) -> String {
         if let ASTNode::String(str) = self {
             str
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a string.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a string."),
+            ));
             "".to_string()
         }
     }
-    pub fn get_stringlist_data(self, _pos: (usize, usize), name: &str, rule: &str) -> Vec<String> {
+    pub fn get_stringlist_data(self, pos: (usize, usize), name: &str, rule: &str) -> Vec<String> {
         match self {
             ASTNode::StringList(strlist) => strlist,
             ASTNode::String(str) => vec![str],
             _ => {
-                let _ = writeln!(stderr(), "{name} in {rule} is not a string list.");
+                report(Diagnostic::error(
+                    DiagnosticCode::UnexpectedNode,
+                    pos,
+                    format!("{name} in {rule} is not a string list."),
+                ));
                 vec![]
             }
         }
     }
     pub fn get_suffixlist_data(
         self,
-        _pos: (usize, usize),
+        pos: (usize, usize),
         name: &str,
         rule: &str,
     ) -> ASTExpSuffixList {
         if let ASTNode::ExpSuffixList(suffixlist) = self {
             suffixlist
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a string list.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a string list."),
+            ));
             vec![]
         }
     }
-    pub fn get_ifelselist_data(self, _pos: (usize, usize), name: &str, rule: &str) -> IfElseList {
+    pub fn get_ifelselist_data(self, pos: (usize, usize), name: &str, rule: &str) -> IfElseList {
         if let ASTNode::IfElseList(ifelselist) = self {
             ifelselist
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a string list.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a string list."),
+            ));
             vec![]
         }
     }
-    pub fn get_operlist_data(self, _pos: (usize, usize), name: &str, rule: &str) -> OperList {
+    pub fn get_operlist_data(self, pos: (usize, usize), name: &str, rule: &str) -> OperList {
         if let ASTNode::OperList(oplist) = self {
             oplist
         } else {
-            let _ = writeln!(stderr(), "{name} in {rule} is not a string list.");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("{name} in {rule} is not a string list."),
+            ));
             vec![]
         }
     }
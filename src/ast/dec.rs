@@ -18,7 +18,12 @@ pub enum DecData {
 
     Var(Var, ty::Type, exp::ASTExp),
     Class(String, ClassMemberList, Vec<String>),
-    Template(Dec, Vec<String>),
+    // dec: Dec, type_params: Vec<String>, constraints: FieldList
+    // a constraint is written as <制約>: <type parameter>
+    Template(Dec, Vec<String>, field::FieldList),
+    // name: String, requirements: DecList
+    // the requirements are functions and operators without a body
+    Constraint(String, DecList),
     None,
 }
 
@@ -83,10 +88,21 @@ impl Dec_ {
             data: DecData::Class(name, class_members, inheritance),
         })
     }
-    pub fn template_dec(pos: (usize, usize), dec: Dec, ty_params: Vec<String>) -> Dec {
+    pub fn template_dec(
+        pos: (usize, usize),
+        dec: Dec,
+        ty_params: Vec<String>,
+        constraints: field::FieldList,
+    ) -> Dec {
+        Box::new(Dec_ {
+            pos,
+            data: DecData::Template(dec, ty_params, constraints),
+        })
+    }
+    pub fn constraint_dec(pos: (usize, usize), name: String, requirements: DecList) -> Dec {
         Box::new(Dec_ {
             pos,
-            data: DecData::Template(dec, ty_params),
+            data: DecData::Constraint(name, requirements),
         })
     }
 }
@@ -94,12 +110,16 @@ impl Dec_ {
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MemberSpecifier {
     Public,
+    // only in the methods of the class and its derived classes
+    Protected,
     Private,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ClassMember_ {
     pub specifier: MemberSpecifier,
+    // methods declared in a 仮想 section can be overridden by derived classes
+    pub is_virtual: bool,
     pub dec: Dec,
 }
 
@@ -107,15 +127,24 @@ pub type ClassMember = Box<ClassMember_>;
 pub type ClassMemberList = Vec<ClassMember>;
 
 pub trait MemberList {
-    fn new_list(declist: Vec<Dec>, specifier: MemberSpecifier) -> ClassMemberList;
+    fn new_list(declist: Vec<Dec>, specifier: MemberSpecifier, is_virtual: bool)
+        -> ClassMemberList;
     fn append_list(&mut self, declist: ClassMemberList);
 }
 
 impl MemberList for Vec<ClassMember> {
-    fn new_list(declist: Vec<Dec>, specifier: MemberSpecifier) -> ClassMemberList {
+    fn new_list(
+        declist: Vec<Dec>,
+        specifier: MemberSpecifier,
+        is_virtual: bool,
+    ) -> ClassMemberList {
         let mut new_list: ClassMemberList = vec![];
         for dec in declist {
-            new_list.push(Box::new(ClassMember_ { specifier, dec }));
+            new_list.push(Box::new(ClassMember_ {
+                specifier,
+                is_virtual,
+                dec,
+            }));
         }
         new_list
     }
@@ -1,4 +1,4 @@
-use super::{field, op, stm, suffix::ASTExpSuffixList, var::Var};
+use super::{field, op, stm, suffix::ASTExpSuffixList, ty::Type, var::Var};
 
 pub type ASTExp = Box<ASTExp_>;
 pub type ASTExpList = Vec<ASTExp>;
@@ -26,6 +26,8 @@ pub enum ASTExpData {
     Field(ASTExp, String),
     Array(ASTExpList),
     SizeOf(ASTExp),
+    // an object made on the heap, and the arguments of its constructor
+    New(Type, ASTExpList),
     Paren(ASTExp),
     Suffix(ASTExp, ASTExpSuffixList),
 
@@ -123,6 +125,12 @@ impl ASTExp_ {
             data: ASTExpData::SizeOf(var),
         })
     }
+    pub fn new_exp(pos: (usize, usize), ty: Type, args: ASTExpList) -> ASTExp {
+        Box::new(ASTExp_ {
+            pos,
+            data: ASTExpData::New(ty, args),
+        })
+    }
     pub fn paren_exp(pos: (usize, usize), exp: ASTExp) -> ASTExp {
         Box::new(ASTExp_ {
             pos,
@@ -46,3 +46,23 @@ pub fn string_to_oper(name: &str) -> Oper {
         _ => Oper::None,
     }
 }
+
+// The name of a 演算子 declaration is the name of the operator rule without "Op".
+pub fn oper_to_name(oper: &Oper) -> Option<&'static str> {
+    match oper {
+        Oper::Plus => Some("Add"),
+        Oper::Minus => Some("Sub"),
+        Oper::Times => Some("Mul"),
+        Oper::Divide => Some("Div"),
+        Oper::Eq => Some("Eq"),
+        Oper::Neq => Some("Ne"),
+        Oper::Lt => Some("Lt"),
+        Oper::Le => Some("Le"),
+        Oper::Gt => Some("Gt"),
+        Oper::Ge => Some("Ge"),
+        Oper::And => Some("And"),
+        Oper::Or => Some("Or"),
+        Oper::Not => Some("Not"),
+        _ => None,
+    }
+}
@@ -32,6 +32,7 @@ pub enum StmData {
     Exp(exp::ASTExp),
     IfElse(ifelse::IfElseList),
     While(exp::ASTExp, Stm),
+    Until(exp::ASTExp, Stm),
     For(Stm, exp::ASTExp, Stm, Stm),
     Call(exp::ASTExp, exp::ASTExpList),
     Return(exp::ASTExp),
@@ -85,6 +86,12 @@ impl Stm_ {
             data: StmData::While(test, body),
         })
     }
+    pub fn until_stm(pos: (usize, usize), test: exp::ASTExp, body: Stm) -> Stm {
+        Box::new(Stm_ {
+            pos,
+            data: StmData::Until(test, body),
+        })
+    }
     pub fn for_stm(pos: (usize, usize), init: Stm, test: exp::ASTExp, incr: Stm, body: Stm) -> Stm {
         Box::new(Stm_ {
             pos,
@@ -1,4 +1,4 @@
-use super::suffix::ASTExpSuffixList;
+use super::{suffix::ASTExpSuffixList, ty::TypeList};
 
 pub type Var = Box<Var_>;
 
@@ -11,6 +11,8 @@ pub struct Var_ {
 #[derive(Clone, Debug, PartialEq)]
 pub enum VarData {
     Simple(String),
+    // a generic function with explicit type parameters
+    Template(String, TypeList),
     SuffixVar(Var, ASTExpSuffixList),
     Pointer(Var),
     None,
@@ -23,6 +25,12 @@ impl Var_ {
             data: VarData::Simple(name),
         })
     }
+    pub fn template_var(pos: (usize, usize), name: String, type_params: TypeList) -> Var {
+        Box::new(Var_ {
+            pos,
+            data: VarData::Template(name, type_params),
+        })
+    }
     pub fn suffix_var(pos: (usize, usize), var: Var, suffixlist: ASTExpSuffixList) -> Var {
         Box::new(Var_ {
             pos,
@@ -9,9 +9,15 @@ pub enum CompilerMode {
     Compile,
     Convert,
 }
+pub enum EmitType {
+    Wat,
+    Wasm,
+}
 pub struct OptionCompilerInfo {
     pub mode: Option<CompilerMode>,
+    pub emit: Option<EmitType>,
     pub parser: Option<LazeParser>,
+    pub target_parser_file_path: Option<String>,
     pub program_file_path: Option<String>,
     pub dist_file_path: Option<String>,
 }
@@ -20,7 +26,9 @@ impl OptionCompilerInfo {
     pub fn new() -> Self {
         OptionCompilerInfo {
             mode: None,
+            emit: None,
             parser: None,
+            target_parser_file_path: None,
             program_file_path: None,
             dist_file_path: None,
         }
@@ -29,24 +37,39 @@ impl OptionCompilerInfo {
 
 pub struct CompilerInfo {
     pub mode: CompilerMode,
+    pub emit: EmitType,
     pub parser: LazeParser,
+    pub target_parser_file_path: String,
     pub program_file_path: String,
     pub dist_file_path: String,
 }
 
 impl CompilerInfo {
     pub fn from_option(info: OptionCompilerInfo) -> Self {
+        let mode = match info.mode {
+            Some(mode) => mode,
+            None => {
+                let _ = writeln!(
+                    stderr(),
+                    "Please select a mode with the option: --compile / --convert"
+                );
+                exit(1);
+            }
+        };
         CompilerInfo {
-            mode: match info.mode {
-                Some(mode) => mode,
-                None => {
+            target_parser_file_path: match (&mode, info.target_parser_file_path) {
+                (_, Some(path)) => path,
+                (CompilerMode::Convert, None) => {
                     let _ = writeln!(
                         stderr(),
-                        "Please select a mode with the option: --compile / --convert"
+                        "Please give a parser file to convert into with: --target-parser=[PATH]"
                     );
                     exit(1);
                 }
+                (CompilerMode::Compile, None) => "".to_string(),
             },
+            mode,
+            emit: info.emit.unwrap_or(EmitType::Wat),
             parser: match info.parser {
                 Some(parser) => parser,
                 None => {
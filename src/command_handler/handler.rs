@@ -1,6 +1,14 @@
-use std::{collections::VecDeque, path::Path, process::exit};
+use std::{
+    collections::VecDeque,
+    io::{stderr, Write},
+    path::Path,
+    process::exit,
+};
 
-use crate::{command_handler::compiler_info::CompilerMode, laze_parser::parser::LazeParser};
+use crate::{
+    command_handler::compiler_info::{CompilerMode, EmitType},
+    laze_parser::parser::LazeParser,
+};
 
 use super::compiler_info::{CompilerInfo, OptionCompilerInfo};
 
@@ -40,11 +48,23 @@ fn handle_double_dash(arguments: &mut VecDeque<String>, info: &mut OptionCompile
             if str.starts_with("parser=") {
                 let (_, path) = param.split_at(7);
                 info.parser = Some(LazeParser::new(Path::new(path)));
+            } else if str.starts_with("target-parser=") {
+                let (_, path) = param.split_at(14);
+                info.target_parser_file_path = Some(path.to_string());
+            } else if str.starts_with("emit=") {
+                let (_, emit_type) = param.split_at(5);
+                match emit_type {
+                    "wat" => info.emit = Some(EmitType::Wat),
+                    "wasm" => info.emit = Some(EmitType::Wasm),
+                    _ => {
+                        let _ = writeln!(stderr(), "Unknown output type: {emit_type} (wat / wasm)");
+                    }
+                }
             } else if str.starts_with("dist=") {
                 let (_, path) = param.split_at(5);
                 info.dist_file_path = Some(path.to_string());
             } else {
-                println!("Unknown option: --{param}");
+                let _ = writeln!(stderr(), "Unknown option: --{param}");
             }
         }
     }
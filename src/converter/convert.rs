@@ -0,0 +1,46 @@
+use std::path::Path;
+
+use peg_parser::parse_grammar_tree;
+
+use crate::{
+    ast::ast::ASTNode,
+    util::{file_opener::open_file, source_map::SourceMap},
+};
+
+use super::{formatter::format_tokens, syntax::ast_to_syntax, unparser::Unparser};
+
+pub struct Converter {
+    unparser: Unparser,
+}
+
+impl Converter {
+    pub fn new(target_parser_file_path: &Path) -> Result<Self, String> {
+        let parser_rules = open_file(target_parser_file_path);
+        let rules = parse_grammar_tree(parser_rules.as_str()).map_err(|(error_pos, mes)| {
+            let source_map =
+                SourceMap::new(&target_parser_file_path.to_string_lossy(), &parser_rules);
+            format!("{}: {}", source_map.location((error_pos, error_pos)), mes)
+        })?;
+        Ok(Converter {
+            unparser: Unparser::new(rules),
+        })
+    }
+    // The program is written from the AST, which keeps no comments,
+    // so the converted program has none of the comments of the source.
+    pub fn convert(&mut self, ast: &ASTNode) -> Result<String, String> {
+        match self.unparser.unparse(&ast_to_syntax(ast)) {
+            Some(tokens) => Ok(format_tokens(&tokens)),
+            None => {
+                let mut mes =
+                    "Could not write the program with the rules of the target grammar.".to_string();
+                if !self.unparser.undefined_rules.is_empty() {
+                    mes += &format!(
+                        " These rules are not defined: {}",
+                        self.unparser.undefined_rules.join(", ")
+                    );
+                }
+                Err(mes)
+            }
+        }
+    }
+}
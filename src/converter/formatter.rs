@@ -0,0 +1,185 @@
+use super::unparser::Token;
+
+const INDENT: &str = "    ";
+
+fn is_symbol(literal: &str) -> bool {
+    !literal.is_empty() && literal.chars().all(|c| c.is_ascii_punctuation())
+}
+
+fn is_open(literal: &str) -> bool {
+    matches!(literal, "(" | "[")
+}
+
+fn is_close(literal: &str) -> bool {
+    matches!(literal, ")" | "]")
+}
+
+fn is_separator(literal: &str) -> bool {
+    matches!(literal, ";" | "," | ":" | "." | "->")
+}
+
+// An operator written right after another symbol (or at the start) is a prefix: "-a", "*p".
+fn is_prefix(prev: Option<&Token>, literal: &str) -> bool {
+    if !is_symbol(literal) || is_open(literal) || is_close(literal) || is_separator(literal) {
+        return false;
+    }
+    match prev {
+        None => true,
+        Some(Token::Literal(prev)) => {
+            is_symbol(prev) && !is_close(prev) && prev != "}" && prev != ";"
+        }
+        Some(Token::Text(..)) => false,
+    }
+}
+
+// An operator written after an operand and before a separator is a postfix: "図形*: s".
+fn is_postfix(prev: Option<&Token>, literal: &str, next: Option<&Token>) -> bool {
+    if !is_symbol(literal) || is_open(literal) || is_close(literal) || is_separator(literal) {
+        return false;
+    }
+    let after_operand = match prev {
+        Some(Token::Literal(prev)) => is_close(prev),
+        Some(Token::Text(..)) => true,
+        None => false,
+    };
+    let before_separator = match next {
+        Some(Token::Literal(next)) => is_separator(next) || is_close(next) || next == ">",
+        Some(Token::Text(..)) => false,
+        None => true,
+    };
+    after_operand && before_separator
+}
+
+// "<" and ">" around a type list are brackets: "箱<整数>", "型<T>".
+// Only names, type keywords, commas, pointers and nested brackets can be between them, otherwise they are comparisons.
+fn angle_brackets(tokens: &[Token]) -> Vec<bool> {
+    let mut brackets = vec![false; tokens.len()];
+    for (index, token) in tokens.iter().enumerate() {
+        let opens = matches!(token, Token::Literal(literal) if literal == "<")
+            && index > 0
+            && match &tokens[index - 1] {
+                Token::Literal(prev) => !is_symbol(prev),
+                Token::Text(..) => true,
+            };
+        if !opens || brackets[index] {
+            continue;
+        }
+        let mut depth = 0;
+        for (close_index, token) in tokens.iter().enumerate().skip(index) {
+            match token {
+                Token::Literal(literal) if literal == "<" => depth += 1,
+                Token::Literal(literal) if literal == ">" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        brackets[index] = true;
+                        brackets[close_index] = true;
+                        break;
+                    }
+                }
+                Token::Literal(literal)
+                    if !is_symbol(literal) || matches!(literal.as_str(), "," | "*") => {}
+                Token::Text(..) => {}
+                _ => break,
+            }
+        }
+    }
+    brackets
+}
+
+// Writes the tokens with spaces and line breaks.
+// Line breaks are only put after literals, which always skip the whitespace after them.
+pub fn format_tokens(tokens: &[Token]) -> String {
+    let mut output = String::new();
+    let mut indent: usize = 0;
+    let mut paren_depth = 0;
+    let mut outer_paren_depths = vec![];
+    let mut prefixes = vec![];
+    let mut postfixes = vec![];
+    for (index, token) in tokens.iter().enumerate() {
+        let prev = if index > 0 {
+            tokens.get(index - 1)
+        } else {
+            None
+        };
+        let (prefix, postfix) = match token {
+            Token::Literal(literal) => (
+                is_prefix(prev, literal),
+                is_postfix(prev, literal, tokens.get(index + 1)),
+            ),
+            Token::Text(..) => (false, false),
+        };
+        prefixes.push(prefix);
+        postfixes.push(postfix);
+    }
+    let brackets = angle_brackets(tokens);
+    let mut newline = false;
+    for (index, token) in tokens.iter().enumerate() {
+        let text = match token {
+            Token::Literal(literal) | Token::Text(literal, _) => literal.as_str(),
+        };
+        if text == "}" {
+            indent = indent.saturating_sub(1);
+        }
+        if index > 0 {
+            let prev = &tokens[index - 1];
+            let can_space = match prev {
+                Token::Literal(_) => true,
+                Token::Text(_, spaced) => *spaced,
+            };
+            if newline && can_space {
+                output.push('\n');
+                output.push_str(&INDENT.repeat(indent));
+            } else if can_space {
+                let prev_text = match prev {
+                    Token::Literal(literal) | Token::Text(literal, _) => literal.as_str(),
+                };
+                // a call and a keyword taking arguments are written without a space: "js書き出し(",
+                // but not an operator before a parenthesis: "=> ("
+                let no_space = is_open(prev_text)
+                    || prefixes[index - 1]
+                    || matches!(prev_text, "." | "->")
+                    || is_close(text)
+                    || is_separator(text)
+                    || postfixes[index]
+                    || brackets[index]
+                    || (brackets[index - 1] && (prev_text == "<" || is_open(text)))
+                    || (is_open(text)
+                        && (matches!(prev, Token::Text(..))
+                            || is_close(prev_text)
+                            || !is_symbol(prev_text)));
+                if !no_space {
+                    output.push(' ');
+                }
+            }
+        }
+        output.push_str(text);
+        newline = false;
+        if let Token::Literal(literal) = token {
+            match literal.as_str() {
+                "(" | "[" => paren_depth += 1,
+                ")" | "]" => paren_depth -= 1,
+                "{" => {
+                    indent += 1;
+                    outer_paren_depths.push(paren_depth);
+                    paren_depth = 0;
+                    newline = true;
+                }
+                "}" => paren_depth = outer_paren_depths.pop().unwrap_or(0),
+                _ => {}
+            }
+            if paren_depth == 0 && matches!(literal.as_str(), ";" | "}") {
+                newline = !matches!(
+                    tokens.get(index + 1),
+                    Some(Token::Literal(next)) if matches!(next.as_str(), ";" | ")" | ",")
+                );
+            }
+        }
+        if let Some(Token::Literal(next)) = tokens.get(index + 1) {
+            if next == "}" && !newline {
+                newline = true;
+            }
+        }
+    }
+    output.push('\n');
+    output
+}
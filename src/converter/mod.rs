@@ -0,0 +1,6 @@
+pub mod convert;
+pub mod formatter;
+pub mod syntax;
+#[cfg(test)]
+pub mod tests;
+pub mod unparser;
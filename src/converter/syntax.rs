@@ -0,0 +1,595 @@
+use std::collections::HashMap;
+
+use crate::ast::{
+    ast::ASTNode,
+    dec::{ClassMemberList, Dec, DecData, MemberSpecifier},
+    exp::{ASTExp, ASTExpData, ASTExpList},
+    field::{FieldData, FieldList},
+    ifelse::{IfElseData, IfElseList},
+    op::Oper,
+    stm::{AssignType, Stm, StmData, StmList},
+    suffix::{ASTExpSuffixList, SuffixData},
+    ty::{Type, TypeData},
+    var::{Var, VarData},
+};
+
+// The AST rewritten with the rule names of the grammar.
+// A node holds its children under the names they are saved with in a rule,
+// which is what the unparser looks up when it walks the target grammar.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Syntax {
+    // rule_name: String, fields: Fields
+    Node(String, Fields),
+    Text(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fields {
+    pub items: HashMap<String, Vec<Syntax>>,
+    // How many times a repeated literal (e.g. "*" in PointerVar) is written.
+    pub repeats: HashMap<String, usize>,
+    // Whether the alternative of the rule writing the fewest tokens is chosen,
+    // e.g. a public section at the start of a class is written without "公開:".
+    pub shortest: bool,
+}
+
+impl Fields {
+    fn with(mut self, name: &str, item: Syntax) -> Self {
+        self.items.entry(name.to_string()).or_default().push(item);
+        self
+    }
+    fn with_list(mut self, name: &str, list: Vec<Syntax>) -> Self {
+        self.items.entry(name.to_string()).or_default().extend(list);
+        self
+    }
+    fn with_repeat(mut self, literal: &str, count: usize) -> Self {
+        self.repeats.insert(literal.to_string(), count);
+        self
+    }
+    fn shortest(mut self) -> Self {
+        self.shortest = true;
+        self
+    }
+}
+
+fn node(rule: &str, fields: Fields) -> Syntax {
+    Syntax::Node(rule.to_string(), fields)
+}
+
+fn leaf(rule: &str) -> Syntax {
+    node(rule, Fields::default())
+}
+
+fn text_node(rule: &str, capture: &str, text: String) -> Syntax {
+    node(
+        rule,
+        Fields::default().with(capture, Syntax::Text(text.trim().to_string())),
+    )
+}
+
+fn id(name: &str) -> Syntax {
+    text_node("ID", "id", name.to_string())
+}
+
+fn string(content: &str) -> Syntax {
+    text_node("String", "string", format!("\"{}\"", content))
+}
+
+pub fn ast_to_syntax(ast: &ASTNode) -> Syntax {
+    match ast {
+        ASTNode::DecList(declist) => node(
+            "Start",
+            Fields::default().with(
+                "DecList",
+                node(
+                    "DecList",
+                    Fields::default().with_list("Dec", declist.iter().map(dec_to_syntax).collect()),
+                ),
+            ),
+        ),
+        _ => leaf("Start"),
+    }
+}
+
+fn dec_to_syntax(dec: &Dec) -> Syntax {
+    match &dec.data {
+        DecData::Func(name, _, _, body) if name.starts_with('~') => node(
+            "DestructorDec",
+            Fields::default()
+                .with("ID", id(&name[1..]))
+                .with("StmList", stmlist_to_syntax(body)),
+        ),
+        DecData::Func(name, params, result, body) | DecData::Oper(name, params, result, body) => {
+            node(
+                if let DecData::Func(..) = dec.data {
+                    "FuncDec"
+                } else {
+                    "OperDec"
+                },
+                Fields::default()
+                    .with("ID", id(name))
+                    .with("params", fieldlist_to_syntax(params))
+                    .with("result", fieldlist_to_syntax(result))
+                    .with("StmList", stmlist_to_syntax(body)),
+            )
+        }
+        DecData::JsImport(name, params, result, module, import_name) => node(
+            "JsImportDec",
+            Fields::default()
+                .with("ID", id(name))
+                .with("params", fieldlist_to_syntax(params))
+                .with("result", fieldlist_to_syntax(result))
+                .with("module", string(module))
+                .with("name", string(import_name)),
+        ),
+        DecData::JsExport(name, export_name) => node(
+            "JsExportDec",
+            Fields::default()
+                .with("ID", id(name))
+                .with("String", string(export_name)),
+        ),
+        DecData::Var(var, ty, init) => {
+            let fields = Fields::default()
+                .with("Type", ty_to_syntax(ty))
+                .with("Var", var_to_syntax(var));
+            if let ASTExpData::None = init.data {
+                node("VarDecNoInit", fields)
+            } else {
+                node("VarDecInit", fields.with("Exp", exp_to_syntax(init)))
+            }
+        }
+        DecData::Class(name, members, inheritance) => {
+            let fields = Fields::default()
+                .with("ID", id(name))
+                .with("ClassMemberList", classmembers_to_syntax(members));
+            if inheritance.is_empty() {
+                node("ClassDec", fields)
+            } else {
+                node(
+                    "ClassDec",
+                    fields.with("IDList", idlist_to_syntax(inheritance)),
+                )
+            }
+        }
+        DecData::Template(dec, ty_params, constraints) => {
+            let fields = Fields::default()
+                .with("IDList", idlist_to_syntax(ty_params))
+                .with("Dec", dec_to_syntax(dec));
+            if constraints.is_empty() {
+                node("TemplateDec", fields)
+            } else {
+                node(
+                    "TemplateDec",
+                    fields.with("constraints", fieldlist_to_syntax(constraints)),
+                )
+            }
+        }
+        DecData::Constraint(name, requirements) => node(
+            "ConstraintDec",
+            Fields::default().with("ID", id(name)).with_list(
+                "Requirement",
+                requirements.iter().map(requirement_to_syntax).collect(),
+            ),
+        ),
+        DecData::None => leaf("NoneDec"),
+    }
+}
+
+// A requirement is a function or an operator written without a body.
+fn requirement_to_syntax(dec: &Dec) -> Syntax {
+    match &dec.data {
+        DecData::Func(name, params, result, _) | DecData::Oper(name, params, result, _) => node(
+            if let DecData::Func(..) = dec.data {
+                "FuncRequirement"
+            } else {
+                "OperRequirement"
+            },
+            Fields::default()
+                .with("ID", id(name))
+                .with("params", fieldlist_to_syntax(params))
+                .with("result", fieldlist_to_syntax(result)),
+        ),
+        _ => leaf("NoneDec"),
+    }
+}
+
+fn classmembers_to_syntax(members: &ClassMemberList) -> Syntax {
+    // Consecutive members with the same specifier are written in one section.
+    let mut sections: Vec<(MemberSpecifier, bool, Vec<Syntax>)> = vec![];
+    for member in members {
+        match sections.last_mut() {
+            Some((specifier, is_virtual, decs))
+                if *specifier == member.specifier && *is_virtual == member.is_virtual =>
+            {
+                decs.push(dec_to_syntax(&member.dec));
+            }
+            _ => sections.push((
+                member.specifier,
+                member.is_virtual,
+                vec![dec_to_syntax(&member.dec)],
+            )),
+        }
+    }
+    node(
+        "ClassMemberList",
+        Fields::default().with_list(
+            "members",
+            sections
+                .into_iter()
+                .enumerate()
+                .map(|(index, (specifier, is_virtual, decs))| {
+                    let fields = Fields::default().with(
+                        "DecList",
+                        node("DecList", Fields::default().with_list("Dec", decs)),
+                    );
+                    match (specifier, is_virtual) {
+                        (_, true) => node("VirtualMembers", fields),
+                        // only the first section can leave out its specifier,
+                        // since a later one would be joined to the section before it
+                        (MemberSpecifier::Public, false) if index == 0 => {
+                            node("PublicMembers", fields.shortest())
+                        }
+                        (MemberSpecifier::Public, false) => node("PublicMembers", fields),
+                        (MemberSpecifier::Protected, false) => node("ProtectedMembers", fields),
+                        (MemberSpecifier::Private, false) => node("PrivateMembers", fields),
+                    }
+                })
+                .collect(),
+        ),
+    )
+}
+
+fn idlist_to_syntax(idlist: &[String]) -> Syntax {
+    node(
+        "IDList",
+        Fields::default().with_list("ID", idlist.iter().map(|name| id(name)).collect()),
+    )
+}
+
+fn fieldlist_to_syntax(fieldlist: &FieldList) -> Syntax {
+    node(
+        "FieldList",
+        Fields::default().with_list(
+            "Field",
+            fieldlist
+                .iter()
+                .map(|field| match &field.data {
+                    FieldData::Field(var, ty) => node(
+                        "Field",
+                        Fields::default()
+                            .with("Type", ty_to_syntax(ty))
+                            .with("Var", var_to_syntax(var)),
+                    ),
+                    FieldData::None => leaf("NoneField"),
+                })
+                .collect(),
+        ),
+    )
+}
+
+fn stmlist_to_syntax(stmlist: &StmList) -> Syntax {
+    node(
+        "StmList",
+        Fields::default().with_list("Stm", stmlist.iter().map(stm_to_syntax).collect()),
+    )
+}
+
+fn stm_to_syntax(stm: &Stm) -> Syntax {
+    match &stm.data {
+        StmData::Compound(stmlist) => node(
+            "CompoundStm",
+            Fields::default().with("StmList", stmlist_to_syntax(stmlist)),
+        ),
+        StmData::Assign(var, exp, assign_type) => node(
+            match assign_type {
+                AssignType::Normal => "NormalAssign",
+                AssignType::Add => "AddAssign",
+                AssignType::Sub => "SubAssign",
+                AssignType::Mul => "MulAssign",
+                AssignType::Div => "DivAssign",
+            },
+            Fields::default()
+                .with("Var", var_to_syntax(var))
+                .with("Exp", exp_to_syntax(exp)),
+        ),
+        StmData::Dec(dec) => node("DecStm", Fields::default().with("Dec", dec_to_syntax(dec))),
+        StmData::Exp(exp) => node("ExpStm", Fields::default().with("Exp", exp_to_syntax(exp))),
+        StmData::IfElse(ifelselist) => node(
+            "IfStm",
+            Fields::default().with("IfElseList", ifelselist_to_syntax(ifelselist)),
+        ),
+        StmData::While(test, body) => node(
+            "WhileStm",
+            Fields::default()
+                .with("Exp", exp_to_syntax(test))
+                .with("Stm", stm_to_syntax(body)),
+        ),
+        StmData::Until(test, body) => node(
+            "UntilStm",
+            Fields::default()
+                .with("Exp", exp_to_syntax(test))
+                .with("Stm", stm_to_syntax(body)),
+        ),
+        StmData::For(init, test, incr, body) => node(
+            "ForStm",
+            Fields::default()
+                .with("init", stm_to_syntax(init))
+                .with("Exp", exp_to_syntax(test))
+                .with("incr", stm_to_syntax(incr))
+                .with("Stm", stm_to_syntax(body)),
+        ),
+        StmData::Return(exp) => {
+            if let ASTExpData::None = exp.data {
+                leaf("ReturnStm")
+            } else {
+                node(
+                    "ReturnStm",
+                    Fields::default().with("Exp", exp_to_syntax(exp)),
+                )
+            }
+        }
+        StmData::Loop(body) => node(
+            "LoopStm",
+            Fields::default().with("Stm", stm_to_syntax(body)),
+        ),
+        StmData::Repeat(count, body) => node(
+            "RepeatStm",
+            Fields::default()
+                .with("Exp", exp_to_syntax(count))
+                .with("Stm", stm_to_syntax(body)),
+        ),
+        StmData::Continue => leaf("ContinueStm"),
+        StmData::Break => leaf("BreakStm"),
+        StmData::Call(..) => leaf("CallStm"),
+        StmData::None => leaf("NoneStm"),
+    }
+}
+
+fn ifelselist_to_syntax(ifelselist: &IfElseList) -> Syntax {
+    node(
+        "IfElseList",
+        Fields::default().with_list(
+            "ifelse",
+            ifelselist
+                .iter()
+                .map(|ifelse| match &ifelse.data {
+                    IfElseData::If(test, body) | IfElseData::ElseIf(test, body) => node(
+                        if let IfElseData::If(..) = ifelse.data {
+                            "If"
+                        } else {
+                            "ElseIf"
+                        },
+                        Fields::default()
+                            .with("Exp", exp_to_syntax(test))
+                            .with("Stm", stm_to_syntax(body)),
+                    ),
+                    IfElseData::Else(body) => {
+                        node("Else", Fields::default().with("Stm", stm_to_syntax(body)))
+                    }
+                })
+                .collect(),
+        ),
+    )
+}
+
+fn oper_to_syntax(oper: &Oper) -> Syntax {
+    leaf(match oper {
+        Oper::Plus => "AddOp",
+        Oper::Minus | Oper::UMinus => "SubOp",
+        Oper::Times => "MulOp",
+        Oper::Divide => "DivOp",
+        Oper::Eq => "EqOp",
+        Oper::Neq => "NeOp",
+        Oper::Lt => "LtOp",
+        Oper::Le => "LeOp",
+        Oper::Gt => "GtOp",
+        Oper::Ge => "GeOp",
+        Oper::And => "AndOp",
+        Oper::Or => "OrOp",
+        Oper::Deref => "DerefOp",
+        Oper::Address => "AddressOp",
+        Oper::Not => "NotOp",
+        _ => "NoneOp",
+    })
+}
+
+fn binop_rule(oper: Option<&Oper>) -> &'static str {
+    match oper {
+        Some(Oper::And) | Some(Oper::Or) => "BinOpExp",
+        Some(Oper::Eq) | Some(Oper::Neq) | Some(Oper::Lt) | Some(Oper::Le) | Some(Oper::Gt)
+        | Some(Oper::Ge) => "CompOpExp",
+        Some(Oper::Plus) | Some(Oper::Minus) => "SumExp",
+        _ => "ProdExp",
+    }
+}
+
+fn explist_to_syntax(explist: &ASTExpList) -> Syntax {
+    node(
+        "ExpList",
+        Fields::default().with_list("exp", explist.iter().map(exp_to_syntax).collect()),
+    )
+}
+
+fn exp_to_syntax(exp: &ASTExp) -> Syntax {
+    match &exp.data {
+        ASTExpData::Int(num) => node(
+            "IntExp",
+            Fields::default().with("Integer", text_node("Integer", "int", num.clone())),
+        ),
+        ASTExpData::Real(num) => node(
+            "RealExp",
+            Fields::default().with("Real", text_node("Real", "real", num.clone())),
+        ),
+        ASTExpData::String(content) => node(
+            "StringExp",
+            Fields::default().with("String", string(content)),
+        ),
+        ASTExpData::Bool(value) => node(
+            "BoolExp",
+            Fields::default().with("bool", leaf(if *value { "True" } else { "False" })),
+        ),
+        ASTExpData::Var(var) => node("VarExp", Fields::default().with("Var", var_to_syntax(var))),
+        ASTExpData::BinOp(oplist, explist) => node(
+            binop_rule(oplist.first()),
+            Fields::default()
+                .with_list("op", oplist.iter().map(oper_to_syntax).collect())
+                .with_list("exp", explist.iter().map(exp_to_syntax).collect()),
+        ),
+        ASTExpData::UnaryOp(oplist, exp) => node(
+            "UnaryOpExp",
+            Fields::default()
+                .with_list("op", oplist.iter().map(oper_to_syntax).collect())
+                .with("exp", exp_to_syntax(exp)),
+        ),
+        ASTExpData::Func(params, result, body) => node(
+            "FuncExp",
+            Fields::default()
+                .with("params", fieldlist_to_syntax(params))
+                .with("result", fieldlist_to_syntax(result))
+                .with("Stm", stm_to_syntax(body)),
+        ),
+        ASTExpData::Array(explist) => node(
+            "ArrayExp",
+            Fields::default().with("ExpList", explist_to_syntax(explist)),
+        ),
+        ASTExpData::SizeOf(exp) => node(
+            "SizeOfExp",
+            Fields::default().with("exp", exp_to_syntax(exp)),
+        ),
+        ASTExpData::New(ty, args) => node(
+            "NewExp",
+            Fields::default()
+                .with("Type", ty_to_syntax(ty))
+                .with("ExpList", explist_to_syntax(args)),
+        ),
+        ASTExpData::Paren(exp) => node(
+            "ParenExp",
+            Fields::default().with("exp", exp_to_syntax(exp)),
+        ),
+        ASTExpData::Short(_) => leaf("ShortExp"),
+        ASTExpData::Char(_) => leaf("CharExp"),
+        ASTExpData::Call(..) => leaf("CallExp"),
+        ASTExpData::Field(..) => leaf("FieldExp"),
+        ASTExpData::Suffix(..) => leaf("SuffixExp"),
+        ASTExpData::None => leaf("NoneExp"),
+    }
+}
+
+fn suffixlist_to_syntax(suffixlist: &ASTExpSuffixList) -> Vec<Syntax> {
+    suffixlist
+        .iter()
+        .map(|suffix| match &suffix.data {
+            SuffixData::Call(explist) => node(
+                "CallSuffix",
+                Fields::default().with("explist", explist_to_syntax(explist)),
+            ),
+            SuffixData::Dot(member) => node("DotSuffix", Fields::default().with("ID", id(member))),
+            SuffixData::Arrow(member) => {
+                node("ArrowSuffix", Fields::default().with("ID", id(member)))
+            }
+            SuffixData::Subscript(exp) => node(
+                "SubscriptSuffix",
+                Fields::default().with("exp", exp_to_syntax(exp)),
+            ),
+        })
+        .collect()
+}
+
+fn var_to_syntax(var: &Var) -> Syntax {
+    match &var.data {
+        VarData::Simple(name) => node("SimpleVar", Fields::default().with("ID", id(name))),
+        VarData::Template(name, type_params) => node(
+            "GenericsVar",
+            Fields::default()
+                .with("ID", id(name))
+                .with("TypeList", tylist_to_syntax(type_params)),
+        ),
+        VarData::SuffixVar(primary, suffixlist) => node(
+            "SuffixVar",
+            Fields::default()
+                .with(
+                    "PrimaryVar",
+                    match &primary.data {
+                        VarData::Pointer(_) | VarData::SuffixVar(..) => node(
+                            "ParenVar",
+                            Fields::default().with("Var", var_to_syntax(primary)),
+                        ),
+                        _ => var_to_syntax(primary),
+                    },
+                )
+                .with_list("suffix", suffixlist_to_syntax(suffixlist)),
+        ),
+        VarData::Pointer(_) => {
+            let mut count = 0;
+            let mut pointee = var;
+            while let VarData::Pointer(inner) = &pointee.data {
+                count += 1;
+                pointee = inner;
+            }
+            node(
+                "PointerVar",
+                Fields::default()
+                    .with("SuffixVar", var_to_syntax(pointee))
+                    .with_repeat("*", count),
+            )
+        }
+        VarData::None => leaf("NoneVar"),
+    }
+}
+
+fn tylist_to_syntax(tylist: &[Type]) -> Syntax {
+    node(
+        "TypeList",
+        Fields::default().with_list("Type", tylist.iter().map(ty_to_syntax).collect()),
+    )
+}
+
+fn ty_to_syntax(ty: &Type) -> Syntax {
+    match &ty.data {
+        TypeData::Int => leaf("IntType"),
+        TypeData::Short => leaf("ShortType"),
+        TypeData::Real => leaf("RealType"),
+        TypeData::Char => leaf("CharType"),
+        TypeData::Bool => leaf("BoolType"),
+        TypeData::Name(name) => node("NameType", Fields::default().with("ID", id(name))),
+        TypeData::Pointer(pointee) => node(
+            "PointerType",
+            Fields::default().with("PrimaryType", ty_to_syntax(pointee)),
+        ),
+        TypeData::Array(_, _) => {
+            let mut sizes = vec![];
+            let mut elem = ty;
+            while let TypeData::Array(inner, size) = &elem.data {
+                sizes.push(exp_to_syntax(size));
+                elem = inner;
+            }
+            sizes.reverse();
+            node(
+                "ArrayType",
+                Fields::default()
+                    .with("PrimaryType", ty_to_syntax(elem))
+                    .with_list("exp", sizes),
+            )
+        }
+        TypeData::Template(name, tylist) => node(
+            "GenericsType",
+            Fields::default()
+                .with("ID", id(name))
+                .with("TypeList", tylist_to_syntax(tylist)),
+        ),
+        TypeData::Void => leaf("VoidType"),
+        TypeData::Func(params, result) => node(
+            "FuncType",
+            Fields::default()
+                .with("params", fieldlist_to_syntax(params))
+                .with_list(
+                    "result",
+                    match result.data {
+                        TypeData::Void => vec![],
+                        _ => vec![ty_to_syntax(result)],
+                    },
+                ),
+        ),
+        TypeData::None => leaf("NoneType"),
+    }
+}
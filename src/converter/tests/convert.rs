@@ -0,0 +1,116 @@
+use std::{fs, path::Path};
+
+use regex::Regex;
+
+use crate::{
+    converter::{convert::Converter, formatter::format_tokens, unparser::Token},
+    laze_parser::parser::LazeParser,
+};
+
+fn without_pos(ast_string: String) -> String {
+    Regex::new(r"pos: \(\d+, \d+\), ")
+        .unwrap()
+        .replace_all(&ast_string, "")
+        .to_string()
+}
+
+fn convert(program_path: &str, parser_path: &str, target_parser_path: &str) -> String {
+    let mut parser = LazeParser::new(Path::new(parser_path));
+    let ast = parser.parse(Path::new(program_path));
+    let mut converter = Converter::new(Path::new(target_parser_path)).unwrap();
+    converter.convert(&ast).unwrap()
+}
+
+// Converting into another grammar must give a program with the same AST in that grammar.
+fn into_en(program_path: &str) {
+    let converted = convert(
+        program_path,
+        "./parser_files/ja.peg",
+        "./parser_files/en.peg",
+    );
+    let converted_path = std::env::temp_dir().join(format!(
+        "{}.en.laze",
+        Path::new(program_path)
+            .file_stem()
+            .expect("Getting file name")
+            .to_string_lossy()
+    ));
+    fs::write(&converted_path, converted).expect("Writing converted program");
+    let ast = LazeParser::new(Path::new("./parser_files/ja.peg")).parse(Path::new(program_path));
+    let converted_ast = LazeParser::new(Path::new("./parser_files/en.peg")).parse(&converted_path);
+    assert_eq!(
+        without_pos(format!("{:?}", converted_ast)),
+        without_pos(format!("{:?}", ast))
+    );
+}
+
+#[test]
+fn class_virtual_text() {
+    assert_eq!(
+        convert(
+            "./laze_tests/dec/class_virtual.laze",
+            "./parser_files/ja.peg",
+            "./parser_files/en.peg"
+        ),
+        fs::read_to_string("./laze_tests/dec/class_virtual.en.laze").unwrap()
+    );
+}
+
+#[test]
+fn class_virtual_back() {
+    let converted = convert(
+        "./laze_tests/dec/class_virtual.en.laze",
+        "./parser_files/en.peg",
+        "./parser_files/ja.peg",
+    );
+    assert!(converted.contains("関数: 測る(図形*: s) => (整数: r) {"));
+    assert!(converted.contains("js書き出し(取得, \"get\");"));
+    assert!(!converted.contains("公開"));
+}
+
+#[test]
+fn class_access() {
+    into_en("./laze_tests/dec/class_access.laze");
+}
+
+#[test]
+fn func_template() {
+    into_en("./laze_tests/dec/func_template.laze");
+}
+
+#[test]
+fn if_elseif_else() {
+    into_en("./laze_tests/stm/if_stm/if_elseif_else.laze");
+}
+
+#[test]
+fn for_continue() {
+    into_en("./laze_tests/stm/for_stm/for_continue.laze");
+}
+
+#[test]
+fn loop_nested_break() {
+    into_en("./laze_tests/stm/while_stm/loop_nested_break.laze");
+}
+
+#[test]
+fn logical_exp() {
+    into_en("./laze_tests/exp/logical_exp.laze");
+}
+
+#[test]
+fn comments_are_dropped() {
+    // func_recursion.laze has a line comment
+    into_en("./laze_tests/dec/func_recursion.laze");
+    let converted = convert(
+        "./laze_tests/dec/func_recursion.laze",
+        "./parser_files/ja.peg",
+        "./parser_files/en.peg",
+    );
+    assert!(!converted.contains("//"));
+}
+
+#[test]
+fn unbalanced_brace() {
+    assert_eq!(format_tokens(&[Token::Literal("}".to_string())]), "}\n");
+}
@@ -0,0 +1,142 @@
+use std::{fs, path::Path};
+
+use regex::Regex;
+
+use crate::{converter::convert::Converter, laze_parser::parser::LazeParser};
+
+fn without_pos(ast_string: String) -> String {
+    Regex::new(r"pos: \(\d+, \d+\), ")
+        .unwrap()
+        .replace_all(&ast_string, "")
+        .to_string()
+}
+
+// Converting into the same grammar must give back a program with the same AST.
+fn roundtrip(program_path: &str) {
+    let mut test_parser = LazeParser::new(Path::new("./parser_files/ja.peg"));
+    let ast = test_parser.parse(Path::new(program_path));
+    let mut converter = Converter::new(Path::new("./parser_files/ja.peg")).unwrap();
+    let converted = converter.convert(&ast).unwrap();
+
+    let converted_path = std::env::temp_dir().join(
+        Path::new(program_path)
+            .file_name()
+            .expect("Getting file name"),
+    );
+    fs::write(&converted_path, converted).expect("Writing converted program");
+    let converted_ast = test_parser.parse(&converted_path);
+    assert_eq!(
+        without_pos(format!("{:?}", converted_ast)),
+        without_pos(format!("{:?}", ast))
+    );
+}
+
+#[test]
+fn class_dec() {
+    roundtrip("./laze_tests/dec/class_dec.laze");
+}
+
+#[test]
+fn class_inheritance() {
+    roundtrip("./laze_tests/dec/class_inheritance.laze");
+}
+
+#[test]
+fn class_virtual() {
+    roundtrip("./laze_tests/dec/class_virtual.laze");
+}
+
+#[test]
+fn class_access() {
+    roundtrip("./laze_tests/dec/class_access.laze");
+}
+
+#[test]
+fn func_overload() {
+    roundtrip("./laze_tests/dec/func_overload.laze");
+}
+
+#[test]
+fn oper_overload() {
+    roundtrip("./laze_tests/dec/oper_overload.laze");
+}
+
+#[test]
+fn func_template() {
+    roundtrip("./laze_tests/dec/func_template.laze");
+}
+
+#[test]
+fn template_constraint() {
+    roundtrip("./laze_tests/dec/template_constraint.laze");
+}
+
+#[test]
+fn heap_alloc() {
+    roundtrip("./laze_tests/dec/heap_alloc.laze");
+}
+
+#[test]
+fn class_constructor() {
+    roundtrip("./laze_tests/dec/class_constructor.laze");
+}
+
+#[test]
+fn method_call() {
+    roundtrip("./laze_tests/dec/method_call.laze");
+}
+
+#[test]
+fn global_var() {
+    roundtrip("./laze_tests/dec/global_var.laze");
+}
+
+#[test]
+fn all_operators() {
+    roundtrip("./laze_tests/exp/all_operators_exp.laze");
+}
+
+#[test]
+fn logical_exp() {
+    roundtrip("./laze_tests/exp/logical_exp.laze");
+}
+
+#[test]
+fn if_elseif_else() {
+    roundtrip("./laze_tests/stm/if_stm/if_elseif_else.laze");
+}
+
+#[test]
+fn while_nested() {
+    roundtrip("./laze_tests/stm/while_stm/while_nested.laze");
+}
+
+#[test]
+fn loop_nested_break() {
+    roundtrip("./laze_tests/stm/while_stm/loop_nested_break.laze");
+}
+
+#[test]
+fn loop_break() {
+    roundtrip("./laze_tests/stm/loop_stm/loop_break.laze");
+}
+
+#[test]
+fn for_continue() {
+    roundtrip("./laze_tests/stm/for_stm/for_continue.laze");
+}
+
+#[test]
+fn until_simple() {
+    roundtrip("./laze_tests/stm/until_stm/until_simple.laze");
+}
+
+#[test]
+fn var_pointer() {
+    roundtrip("./laze_tests/var/var_pointer.laze");
+}
+
+#[test]
+fn func_exp() {
+    roundtrip("./laze_tests/exp/func_exp.laze");
+}
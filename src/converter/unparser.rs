@@ -0,0 +1,410 @@
+use std::collections::{HashMap, HashSet};
+
+use peg_parser::PegTree;
+
+use super::syntax::{Fields, Syntax};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Literal(String),
+    // text: String, spaced: bool (the grammar skips spaces after it)
+    Text(String, bool),
+}
+
+// Where the names in a rule are looked up.
+// A node of the same rule gives its own fields. When the item belongs to another rule,
+// the referenced rule is only a wrapper around it (e.g. Exp = BinOpExp::exp),
+// so the item is passed down until a rule of the same name is found.
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    Fields(&'a Fields),
+    Forward(&'a Syntax),
+}
+
+#[derive(Clone)]
+struct Scope<'a> {
+    source: Source<'a>,
+    cursors: HashMap<String, usize>,
+    repeats_taken: HashSet<String>,
+    consumed: usize,
+    forwarded_rules: Vec<String>,
+}
+
+impl<'a> Scope<'a> {
+    fn new(source: Source<'a>, forwarded_rules: Vec<String>) -> Self {
+        Scope {
+            source,
+            cursors: HashMap::new(),
+            repeats_taken: HashSet::new(),
+            consumed: 0,
+            forwarded_rules,
+        }
+    }
+    fn peek(&self, name: &str) -> Option<&'a Syntax> {
+        match self.source {
+            Source::Fields(fields) => fields
+                .items
+                .get(name)
+                .and_then(|items| items.get(*self.cursors.get(name).unwrap_or(&0))),
+            Source::Forward(item) => {
+                if self.consumed == 0 {
+                    Some(item)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+    fn take(&mut self, name: &str) -> Option<&'a Syntax> {
+        let item = self.peek(name)?;
+        *self.cursors.entry(name.to_string()).or_insert(0) += 1;
+        self.consumed += 1;
+        Some(item)
+    }
+    fn take_repeat(&mut self, literal: &str) -> usize {
+        match self.source {
+            Source::Fields(fields) => {
+                self.repeats_taken.insert(literal.to_string());
+                *fields.repeats.get(literal).unwrap_or(&0)
+            }
+            Source::Forward(_) => 0,
+        }
+    }
+    fn is_finished(&self) -> bool {
+        match self.source {
+            Source::Fields(fields) => {
+                fields
+                    .items
+                    .iter()
+                    .all(|(name, items)| *self.cursors.get(name).unwrap_or(&0) == items.len())
+                    && fields
+                        .repeats
+                        .iter()
+                        .all(|(literal, count)| *count == 0 || self.repeats_taken.contains(literal))
+            }
+            Source::Forward(_) => self.consumed == 1,
+        }
+    }
+}
+
+pub struct Unparser {
+    rules: HashMap<String, PegTree>,
+    // Whether the grammar skips the spaces after the text captured by a rule.
+    spaced_captures: HashSet<String>,
+    pub undefined_rules: Vec<String>,
+}
+
+impl Unparser {
+    pub fn new(rules: Vec<(String, PegTree)>) -> Self {
+        let mut spaced_captures = HashSet::new();
+        for (_, tree) in &rules {
+            collect_spaced_captures(tree, &mut spaced_captures);
+        }
+        Unparser {
+            rules: rules.into_iter().collect(),
+            spaced_captures,
+            undefined_rules: vec![],
+        }
+    }
+
+    pub fn unparse(&mut self, syntax: &Syntax) -> Option<Vec<Token>> {
+        let mut tokens = vec![];
+        let mut scope = Scope::new(Source::Forward(syntax), vec![]);
+        if self.emit_ref("Start", "Start", &mut scope, &mut tokens) {
+            Some(tokens)
+        } else {
+            None
+        }
+    }
+
+    fn emit_ref(
+        &mut self,
+        rule: &str,
+        name: &str,
+        scope: &mut Scope,
+        tokens: &mut Vec<Token>,
+    ) -> bool {
+        let tree = match self.rules.get(rule) {
+            Some(tree) => tree.clone(),
+            None => return false,
+        };
+        if scope
+            .forwarded_rules
+            .iter()
+            .any(|forwarded| forwarded == rule)
+        {
+            return false;
+        }
+        let item = match scope.take(name) {
+            Some(item) => item,
+            None => return false,
+        };
+        let inner_scope = match item {
+            Syntax::Node(item_rule, fields) if item_rule == rule => {
+                Scope::new(Source::Fields(fields), vec![])
+            }
+            Syntax::Node(item_rule, _) => {
+                if !self.rules.contains_key(item_rule) && !self.undefined_rules.contains(item_rule)
+                {
+                    self.undefined_rules.push(item_rule.clone());
+                }
+                let mut forwarded_rules = match scope.source {
+                    Source::Forward(_) => scope.forwarded_rules.clone(),
+                    Source::Fields(_) => vec![],
+                };
+                forwarded_rules.push(rule.to_string());
+                Scope::new(Source::Forward(item), forwarded_rules)
+            }
+            Syntax::Text(_) => Scope::new(Source::Forward(item), vec![rule.to_string()]),
+        };
+        let len = tokens.len();
+        // an alternative of the whole rule is only chosen when it writes all the fields,
+        // since a shorter alternative can be a prefix of a longer one
+        // (e.g. a ClassDec with and without "<-" IDList)
+        let alternatives = match (&tree, inner_scope.source) {
+            (PegTree::Or(trees), Source::Fields(_)) => trees.clone(),
+            _ => vec![tree],
+        };
+        let shortest = matches!(inner_scope.source, Source::Fields(fields) if fields.shortest);
+        let mut chosen: Option<Vec<Token>> = None;
+        for alternative in &alternatives {
+            let mut alt_scope = inner_scope.clone();
+            if self.emit(alternative, &mut alt_scope, tokens) && alt_scope.is_finished() {
+                if !shortest {
+                    return true;
+                }
+                let alt_tokens = tokens.split_off(len);
+                if chosen
+                    .as_ref()
+                    .is_none_or(|chosen_tokens| alt_tokens.len() < chosen_tokens.len())
+                {
+                    chosen = Some(alt_tokens);
+                }
+            }
+            tokens.truncate(len);
+        }
+        match chosen {
+            Some(chosen_tokens) => {
+                tokens.extend(chosen_tokens);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn emit(&mut self, tree: &PegTree, scope: &mut Scope, tokens: &mut Vec<Token>) -> bool {
+        match tree {
+            PegTree::Str(literal) => {
+                tokens.push(Token::Literal(literal.clone()));
+                true
+            }
+            // the formatter writes the spacing between tokens
+            PegTree::Ref(rule, _) if rule == "Spacing" => true,
+            PegTree::Ref(rule, save_name) => {
+                let name = save_name.as_ref().unwrap_or(rule).clone();
+                self.emit_ref(rule, &name, scope, tokens)
+            }
+            PegTree::Capture(name, inner) => match scope.peek(name) {
+                Some(Syntax::Text(text)) => {
+                    scope.take(name);
+                    tokens.push(Token::Text(
+                        text.clone(),
+                        self.spaced_captures.contains(name),
+                    ));
+                    true
+                }
+                _ => self.emit(inner, scope, tokens),
+            },
+            PegTree::Seq(trees) => {
+                for (index, tree) in trees.iter().enumerate() {
+                    // (Field)* ("," Field)*: the first repetition writes only one item
+                    // so that the rest are written with the separators.
+                    let at_most_one = match tree {
+                        PegTree::Many(body) | PegTree::MoreThanOne(body) => {
+                            let mut later_names = HashSet::new();
+                            for later in &trees[index + 1..] {
+                                collect_names(later, &mut later_names);
+                            }
+                            let mut names = HashSet::new();
+                            collect_names(body, &mut names);
+                            !names.is_disjoint(&later_names)
+                        }
+                        _ => false,
+                    };
+                    if !self.emit_repeat(tree, at_most_one, scope, tokens) {
+                        return false;
+                    }
+                }
+                true
+            }
+            PegTree::Or(trees) => {
+                if let Source::Forward(_) = scope.source {
+                    return self.emit_shortest(trees, scope, tokens);
+                }
+                for tree in trees {
+                    let saved_scope = scope.clone();
+                    let len = tokens.len();
+                    if self.emit(tree, scope, tokens) {
+                        return true;
+                    }
+                    *scope = saved_scope;
+                    tokens.truncate(len);
+                }
+                false
+            }
+            PegTree::Many(_) | PegTree::MoreThanOne(_) => {
+                self.emit_repeat(tree, false, scope, tokens)
+            }
+            PegTree::Not(_) => true,
+            PegTree::Range(_)
+            | PegTree::Any
+            | PegTree::Rules(_)
+            | PegTree::Trees(_)
+            | PegTree::Text(_)
+            | PegTree::None => false,
+        }
+    }
+
+    // A wrapper rule can reach the item through several alternatives
+    // (e.g. Type = PointerType::type / ArrayType::type), and the one writing the fewest tokens
+    // is the one that does not add anything around it.
+    fn emit_shortest<'a>(
+        &mut self,
+        trees: &[PegTree],
+        scope: &mut Scope<'a>,
+        tokens: &mut Vec<Token>,
+    ) -> bool {
+        let mut shortest: Option<(Scope<'a>, Vec<Token>)> = None;
+        for tree in trees {
+            let mut alt_scope = scope.clone();
+            let mut alt_tokens = vec![];
+            if self.emit(tree, &mut alt_scope, &mut alt_tokens)
+                && alt_scope.consumed > scope.consumed
+                && shortest
+                    .as_ref()
+                    .is_none_or(|(_, shortest_tokens)| alt_tokens.len() < shortest_tokens.len())
+            {
+                shortest = Some((alt_scope, alt_tokens));
+            }
+        }
+        match shortest {
+            Some((alt_scope, alt_tokens)) => {
+                *scope = alt_scope;
+                tokens.extend(alt_tokens);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn emit_repeat(
+        &mut self,
+        tree: &PegTree,
+        at_most_one: bool,
+        scope: &mut Scope,
+        tokens: &mut Vec<Token>,
+    ) -> bool {
+        let (body, min) = match tree {
+            PegTree::Many(body) => (body, 0),
+            PegTree::MoreThanOne(body) => (body, 1),
+            tree => return self.emit(tree, scope, tokens),
+        };
+        if let Some(literal) = literal_of(body) {
+            let count = scope.take_repeat(&literal).max(min);
+            for _ in 0..count {
+                tokens.push(Token::Literal(literal.clone()));
+            }
+            return true;
+        }
+        let mut count = 0;
+        loop {
+            let saved_scope = scope.clone();
+            let len = tokens.len();
+            if !self.emit(body, scope, tokens) || scope.consumed == saved_scope.consumed {
+                *scope = saved_scope;
+                tokens.truncate(len);
+                break;
+            }
+            count += 1;
+            if at_most_one {
+                break;
+            }
+        }
+        count >= min
+    }
+}
+
+fn literal_of(tree: &PegTree) -> Option<String> {
+    match tree {
+        PegTree::Str(literal) => Some(literal.clone()),
+        PegTree::Seq(trees) => trees
+            .iter()
+            .map(literal_of)
+            .collect::<Option<Vec<String>>>()
+            .map(|literals| literals.concat()),
+        _ => None,
+    }
+}
+
+// (rule_name, save_name) of the references in a tree
+fn collect_names(tree: &PegTree, names: &mut HashSet<(String, String)>) {
+    match tree {
+        PegTree::Ref(rule, save_name) => {
+            names.insert((rule.clone(), save_name.as_ref().unwrap_or(rule).clone()));
+        }
+        PegTree::Capture(name, inner) => {
+            names.insert((name.clone(), name.clone()));
+            collect_names(inner, names);
+        }
+        PegTree::Seq(trees) | PegTree::Or(trees) => {
+            for tree in trees {
+                collect_names(tree, names);
+            }
+        }
+        PegTree::Many(inner) | PegTree::MoreThanOne(inner) => collect_names(inner, names),
+        _ => {}
+    }
+}
+
+fn is_space_skip(tree: &PegTree) -> bool {
+    match tree {
+        PegTree::Many(inner) => match inner.as_ref() {
+            PegTree::Str(str) => str.trim().is_empty(),
+            PegTree::Or(trees) => trees
+                .iter()
+                .all(|tree| matches!(tree, PegTree::Str(str) if str.trim().is_empty())),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn collect_spaced_captures(tree: &PegTree, captures: &mut HashSet<String>) {
+    match tree {
+        PegTree::Capture(name, inner) => {
+            if let PegTree::Seq(trees) = inner.as_ref() {
+                if trees.last().is_some_and(is_space_skip) {
+                    captures.insert(name.clone());
+                }
+            }
+            collect_spaced_captures(inner, captures);
+        }
+        PegTree::Seq(trees) | PegTree::Or(trees) => {
+            // { ... : id } Spacing
+            for pair in trees.windows(2) {
+                if let [PegTree::Capture(name, _), PegTree::Ref(rule, _)] = pair {
+                    if rule == "Spacing" {
+                        captures.insert(name.clone());
+                    }
+                }
+            }
+            for tree in trees {
+                collect_spaced_captures(tree, captures);
+            }
+        }
+        PegTree::Many(inner) | PegTree::MoreThanOne(inner) | PegTree::Not(inner) => {
+            collect_spaced_captures(inner, captures)
+        }
+        _ => {}
+    }
+}
@@ -1,17 +1,43 @@
-use std::path::Path;
+use std::{
+    io::{stderr, Write},
+    path::Path,
+    process::exit,
+};
 
 use peg_parser::{Parser, PegParser};
 
-use crate::{ast::ast::ASTNode, util::file_opener::open_file};
+use crate::{
+    ast::ast::ASTNode,
+    util::{file_opener::open_file, source_map::SourceMap},
+};
 
 pub fn init_laze_parser(parser_file_path: &Path) -> Parser<ASTNode> {
     let parser_rules = open_file(parser_file_path);
-    init_laze_parser_direct(parser_rules.as_str())
+    build_laze_parser(&parser_file_path.to_string_lossy(), parser_rules.as_str())
 }
 
 pub fn init_laze_parser_direct(parser_rules: &str) -> Parser<ASTNode> {
+    build_laze_parser("parser rules", parser_rules)
+}
+
+fn build_laze_parser(file_name: &str, parser_rules: &str) -> Parser<ASTNode> {
     let mut laze_parser = PegParser::<ASTNode>::new();
-    laze_parser
+    laze_parser.set_memoize(true);
+    let result = laze_parser
         .parse_parser(parser_rules.to_string())
-        .expect("Parsing parser: ")
+        .map_err(|_| ());
+    match result {
+        Ok(parser) => parser,
+        Err(()) => {
+            let source_map = SourceMap::new(file_name, parser_rules);
+            let error_pos = laze_parser.get_error_pos();
+            let _ = writeln!(
+                stderr(),
+                "{}: Could not parse the grammar: {}",
+                source_map.location((error_pos, error_pos)),
+                laze_parser.get_expected_message()
+            );
+            exit(1);
+        }
+    }
 }
@@ -1,5 +1,3 @@
-use std::io::{stderr, Write};
-
 use peg_parser::Parser;
 
 use crate::ast::{
@@ -8,12 +6,13 @@ use crate::ast::{
     exp::ASTExp_,
     field::Field_,
     ifelse::IfElse_,
-    op::{string_to_oper, Oper},
+    op::string_to_oper,
     stm::{AssignType, Stm_},
     suffix::ASTExpSuffix_,
     ty::Type_,
     var::Var_,
 };
+use crate::util::diagnostic::{report, Diagnostic, DiagnosticCode};
 
 use super::extracter::*;
 
@@ -75,6 +74,11 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
             pos,
             extract_string_data(pos, parser.get_data("ID"), "ID", name),
         )),
+        "GenericsVar" => ASTNode::Var(Var_::template_var(
+            pos,
+            extract_string_data(pos, parser.get_data("ID"), "ID", name),
+            extract_tylist_data(pos, parser.get_data("TypeList"), "TypeList", name),
+        )),
         "ParenVar" => parser
             .get_data("Var")
             .expect("Could not find Var in ParenVar."),
@@ -103,13 +107,8 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
                             pos,
                             extract_var_data(pos, parser.get_data("SuffixVar"), "SuffixVar", name),
                         );
-                        for index in 1..str.len() {
-                            let slice = &str[index..index + 1];
-                            if slice == "*" {
-                                var = Var_::pointer_var(pos, var);
-                            } else {
-                                break;
-                            }
+                        for _ in str.chars().skip(1).take_while(|c| *c == '*') {
+                            var = Var_::pointer_var(pos, var);
                         }
                         ASTNode::Var(var)
                     } else {
@@ -125,6 +124,14 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
             pos,
             extract_ty_data(pos, parser.get_data("PrimaryType"), "ParenType", name),
         )),
+        "FuncType" => ASTNode::Type(Type_::func_type(
+            pos,
+            extract_fieldlist_data(pos, parser.get_data("params"), "params", name),
+            match parser.get_data("result") {
+                Some(result) => extract_ty_data(pos, Some(result), "result", name),
+                None => Type_::void_type(pos),
+            },
+        )),
         "ParenType" => parser.get_data("Type").expect("ParenType"),
         "IntType" => ASTNode::Type(Type_::int_type(pos)),
         "ShortType" => ASTNode::Type(Type_::short_type(pos)),
@@ -138,7 +145,7 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
         "GenericsType" => ASTNode::Type(Type_::template_type(
             pos,
             extract_string_data(pos, parser.get_data("ID"), "ID", name),
-            extract_tylist_data(pos, parser.get_data("IDList"), "IDList", name),
+            extract_tylist_data(pos, parser.get_data("TypeList"), "TypeList", name),
         )),
         "ArrayType" => match parser.get_data("exp") {
             Some(exp) => ASTNode::Type(Type_::array_type(
@@ -151,7 +158,25 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
                 .expect("PrimaryType in ArrayType"),
         },
         "PrimaryType" => parser.get_data("type").expect("PrimaryType"),
-        "Type" => parser.get_data("type").expect("Type"),
+        "Type" => {
+            let new_ty = extract_ty_data(pos, parser.get_data("type"), "type", name);
+            match parser.get_data_from_parent_scope("Type") {
+                Some(node) => match node {
+                    ASTNode::Type(ty) => ASTNode::TypeList(vec![ty, new_ty]),
+                    ASTNode::TypeList(mut tylist) => {
+                        tylist.push(new_ty);
+                        ASTNode::TypeList(tylist)
+                    }
+                    _ => ASTNode::Type(new_ty),
+                },
+                None => ASTNode::Type(new_ty),
+            }
+        }
+        "TypeList" => match parser.get_data("Type") {
+            Some(ASTNode::Type(ty)) => ASTNode::TypeList(vec![ty]),
+            Some(tylist) => tylist,
+            None => ASTNode::TypeList(vec![]),
+        },
         "If" | "ElseIf" => {
             let test = extract_exp_data(pos, parser.get_data("Exp"), "Exp", name);
             let body = extract_stm_data(pos, parser.get_data("Stm"), "Stm", name);
@@ -195,13 +220,7 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
         )),
         "ReturnStm" => ASTNode::Stm(Stm_::return_stm(
             pos,
-            match parser.get_data("Exp") {
-                Some(exp) => match exp {
-                    ASTNode::Exp(e) => e,
-                    _ => ASTExp_::none_exp(pos),
-                },
-                None => ASTExp_::none_exp(pos),
-            },
+            extract_exp_data(pos, parser.get_data("Exp"), "Exp", name),
         )),
         "ContinueStm" => ASTNode::Stm(Stm_::continue_stm(pos)),
         "BreakStm" => ASTNode::Stm(Stm_::break_stm(pos)),
@@ -210,13 +229,16 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
             extract_exp_data(pos, parser.get_data("Exp"), "Exp", name),
             extract_stm_data(pos, parser.get_data("Stm"), "Stm", name),
         )),
-        "UntilStm" => ASTNode::Stm(Stm_::while_stm(
+        "UntilStm" => ASTNode::Stm(Stm_::until_stm(
             pos,
-            ASTExp_::unaryop_exp(
-                pos,
-                vec![Oper::Not],
-                extract_exp_data(pos, parser.get_data("Exp"), "Exp", name),
-            ),
+            extract_exp_data(pos, parser.get_data("Exp"), "Exp", name),
+            extract_stm_data(pos, parser.get_data("Stm"), "Stm", name),
+        )),
+        "ForStm" => ASTNode::Stm(Stm_::for_stm(
+            pos,
+            extract_stm_data(pos, parser.get_data("init"), "init", name),
+            extract_exp_data(pos, parser.get_data("Exp"), "Exp", name),
+            extract_stm_data(pos, parser.get_data("incr"), "incr", name),
             extract_stm_data(pos, parser.get_data("Stm"), "Stm", name),
         )),
         "WhileStm" => ASTNode::Stm(Stm_::while_stm(
@@ -229,6 +251,7 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
             extract_ifelselist_data(pos, parser.get_data("IfElseList"), "IfElseList", name),
         )),
         "AssignStm" => parser.get_data("stm").expect("stm in AssignStm"),
+        "Assign" => parser.get_data("stm").expect("stm in Assign"),
         "NormalAssign" | "AddAssign" | "SubAssign" | "MulAssign" | "DivAssign" => {
             ASTNode::Stm(Stm_::assign_stm(
                 pos,
@@ -263,7 +286,11 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
                     ASTNode::StmList(stmlist)
                 }
                 _ => {
-                    let _ = writeln!(stderr(), "stm is not a statement or a statement list.");
+                    report(Diagnostic::error(
+                        DiagnosticCode::UnexpectedNode,
+                        pos,
+                        "stm is not a statement or a statement list.".to_string(),
+                    ));
                     ASTNode::None
                 }
             },
@@ -317,6 +344,11 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
             pos,
             extract_exp_data(pos, parser.get_data("exp"), "exp", name),
         )),
+        "NewExp" => ASTNode::Exp(ASTExp_::new_exp(
+            pos,
+            extract_ty_data(pos, parser.get_data("Type"), "Type", name),
+            extract_explist_data(pos, parser.get_data("ExpList"), "ExpList", name),
+        )),
         "ArrayExp" => ASTNode::Exp(ASTExp_::array_exp(
             pos,
             extract_explist_data(pos, parser.get_data("ExpList"), "ExpList", name),
@@ -367,7 +399,11 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
                         ASTNode::ExpSuffixList(list)
                     }
                     _ => {
-                        let _ = writeln!(stderr(), "suffix is not ExpSuffixList.");
+                        report(Diagnostic::error(
+                            DiagnosticCode::UnexpectedNode,
+                            pos,
+                            "suffix is not ExpSuffixList.".to_string(),
+                        ));
                         ASTNode::ExpSuffixList(vec![data])
                     }
                 },
@@ -383,7 +419,11 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
                         ASTNode::OperList(list)
                     }
                     _ => {
-                        let _ = writeln!(stderr(), "\"op\" is not an operator list.");
+                        report(Diagnostic::error(
+                            DiagnosticCode::UnexpectedNode,
+                            pos,
+                            "\"op\" is not an operator list.".to_string(),
+                        ));
                         ASTNode::None
                     }
                 },
@@ -434,7 +474,11 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
                     }
                     ASTNode::Exp(exp) => ASTNode::ExpList(vec![exp, handled_exp]),
                     _ => {
-                        let _ = writeln!(stderr(), "exp is not an explist. {:?}", node);
+                        report(Diagnostic::error(
+                            DiagnosticCode::UnexpectedNode,
+                            pos,
+                            format!("exp is not an explist. {:?}", node),
+                        ));
                         ASTNode::None
                     }
                 },
@@ -458,7 +502,11 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
                         ASTNode::FieldList(list)
                     }
                     _ => {
-                        let _ = writeln!(stderr(), "Field is not a Fieldlist");
+                        report(Diagnostic::error(
+                            DiagnosticCode::UnexpectedNode,
+                            pos,
+                            "Field is not a Fieldlist".to_string(),
+                        ));
                         ASTNode::None
                     }
                 },
@@ -469,14 +517,15 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
             Some(fieldlist) => fieldlist,
             None => ASTNode::FieldList(vec![]),
         },
-        "PublicMembers" | "PrivateMembers" => {
+        "PublicMembers" | "ProtectedMembers" | "PrivateMembers" | "VirtualMembers" => {
             let new_list = ClassMemberList::new_list(
                 extract_declist_data(pos, parser.get_data("DecList"), "DecList", name),
-                if name == "PublicMembers" {
-                    MemberSpecifier::Public
-                } else {
-                    MemberSpecifier::Private
+                match name {
+                    "PrivateMembers" => MemberSpecifier::Private,
+                    "ProtectedMembers" => MemberSpecifier::Protected,
+                    _ => MemberSpecifier::Public,
                 },
+                name == "VirtualMembers",
             );
             match parser.get_data_from_parent_scope("members") {
                 Some(node) => match node {
@@ -485,7 +534,11 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
                         ASTNode::ClassMemberList(list)
                     }
                     _ => {
-                        let _ = writeln!(stderr(), "members is not a class member list.");
+                        report(Diagnostic::error(
+                            DiagnosticCode::UnexpectedNode,
+                            pos,
+                            "members is not a class member list.".to_string(),
+                        ));
                         ASTNode::ClassMemberList(new_list)
                     }
                 },
@@ -504,12 +557,16 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
             ),
             extract_stringlist_data(pos, parser.get_data("IDList"), "IDList", name),
         )),
-        "OperDec" | "FuncDec" | "JsImportDec" => {
+        "OperDec" | "FuncDec" | "JsImportDec" | "OperRequirement" | "FuncRequirement" => {
             let id = extract_string_data(pos, parser.get_data("ID"), "ID", name);
             let params = extract_fieldlist_data(pos, parser.get_data("params"), "params", name);
             let result = extract_fieldlist_data(pos, parser.get_data("result"), "result", name);
 
-            if name == "OperDec" || name == "FuncDec" {
+            if name == "OperRequirement" {
+                ASTNode::Dec(Dec_::oper_dec(pos, id, params, result, vec![]))
+            } else if name == "FuncRequirement" {
+                ASTNode::Dec(Dec_::func_dec(pos, id, params, result, vec![]))
+            } else if name == "OperDec" || name == "FuncDec" {
                 let body = extract_stmlist_data(pos, parser.get_data("StmList"), "StmList", name);
                 if name == "OperDec" {
                     ASTNode::Dec(Dec_::oper_dec(pos, id, params, result, body))
@@ -522,6 +579,17 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
                 ASTNode::Dec(Dec_::js_import_dec(pos, id, params, result, module, name))
             }
         }
+        // the destructor is entered as a method named ~<class>
+        "DestructorDec" => ASTNode::Dec(Dec_::func_dec(
+            pos,
+            format!(
+                "~{}",
+                extract_string_data(pos, parser.get_data("ID"), "ID", name)
+            ),
+            vec![],
+            vec![],
+            extract_stmlist_data(pos, parser.get_data("StmList"), "StmList", name),
+        )),
         "JsExportDec" => ASTNode::Dec(Dec_::js_export_dec(
             pos,
             extract_string_data(pos, parser.get_data("ID"), "ID", name),
@@ -531,6 +599,12 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
             pos,
             extract_dec_data(pos, parser.get_data("Dec"), "Dec", name),
             extract_stringlist_data(pos, parser.get_data("IDList"), "IDList", name),
+            extract_fieldlist_data(pos, parser.get_data("constraints"), "constraints", name),
+        )),
+        "ConstraintDec" => ASTNode::Dec(Dec_::constraint_dec(
+            pos,
+            extract_string_data(pos, parser.get_data("ID"), "ID", name),
+            extract_declist_data(pos, parser.get_data("Requirement"), "Requirement", name),
         )),
         "VarDecInit" | "VarDecNoInit" => {
             let var = extract_var_data(pos, parser.get_data("Var"), "Var", name);
@@ -556,17 +630,35 @@ pub fn extract_ast(pos: (usize, usize), name: &str, parser: &mut Parser<ASTNode>
                         ASTNode::DecList(list)
                     }
                     _ => {
-                        let _ = writeln!(stderr(), "dec is not a declaration list.");
+                        report(Diagnostic::error(
+                            DiagnosticCode::UnexpectedNode,
+                            pos,
+                            "dec is not a declaration list.".to_string(),
+                        ));
                         ASTNode::DecList(vec![new_dec])
                     }
                 },
                 None => ASTNode::DecList(vec![new_dec]),
             }
         }
+        "Requirement" => {
+            let new_dec = extract_dec_data(pos, parser.get_data("dec"), "dec", name);
+            match parser.get_data_from_parent_scope("Requirement") {
+                Some(ASTNode::DecList(mut list)) => {
+                    list.push(new_dec);
+                    ASTNode::DecList(list)
+                }
+                _ => ASTNode::DecList(vec![new_dec]),
+            }
+        }
         "DecList" => parser.get_data("Dec").expect("DecList"),
         "Start" => parser.get_data("DecList").expect("Start"),
         _ => {
-            let _ = writeln!(stderr(), "What is this token: {name}");
+            report(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                format!("What is this token: {name}"),
+            ));
             ASTNode::None
         }
     }
@@ -6,33 +6,58 @@ use std::{
 
 use peg_parser::Parser;
 
-use crate::{ast::ast::ASTNode, util::file_opener::open_file};
+use crate::{
+    ast::ast::ASTNode,
+    util::{
+        diagnostic::{take_reported, Diagnostic, DiagnosticCode, DiagnosticList},
+        file_opener::open_file,
+        source_map::SourceMap,
+    },
+};
 
 use super::init::{init_laze_parser, init_laze_parser_direct};
 
 pub struct LazeParser {
     parser: Parser<ASTNode>,
+    // problems found while building the AST in the last parse
+    pub diagnostics: DiagnosticList,
 }
 
 impl LazeParser {
     pub fn new(parser_file_path: &Path) -> Self {
         Self {
             parser: init_laze_parser(parser_file_path),
+            diagnostics: vec![],
         }
     }
     pub fn new_direct(parser_rules: &str) -> Self {
         Self {
             parser: init_laze_parser_direct(parser_rules),
+            diagnostics: vec![],
         }
     }
     pub fn parse(&mut self, program_path: &Path) -> ASTNode {
         let content = open_file(program_path);
-        match self.parser.parse(content.as_str()) {
+        match self.parse_source(&content) {
             Ok(node) => node,
-            Err(mes) => {
-                let _ = writeln!(stderr(), "{mes}");
+            Err(diagnostic) => {
+                let source_map = SourceMap::new(&program_path.to_string_lossy(), &content);
+                let _ = write!(stderr(), "{}", diagnostic.render(&source_map));
                 exit(1);
             }
         }
     }
+    // The syntax error points at the furthest char the grammar could reach.
+    pub fn parse_source(&mut self, content: &str) -> Result<ASTNode, Diagnostic> {
+        let result = self.parser.parse(content).map_err(|_| ());
+        self.diagnostics = take_reported();
+        result.map_err(|()| {
+            let error_pos = self.parser.error_pos;
+            Diagnostic::error(
+                DiagnosticCode::SyntaxError,
+                (error_pos, error_pos + 1),
+                self.parser.expected_message(),
+            )
+        })
+    }
 }
@@ -0,0 +1,25 @@
+use std::path::Path;
+
+use crate::laze_parser::parser::LazeParser;
+
+#[test]
+fn line_and_block_comments() {
+    let mut test_parser = LazeParser::new(Path::new("./parser_files/ja.peg"));
+    let ast = test_parser
+        .parse_source(
+            "// 先頭\n関数: 実行() => () { // 行末\n    /* 複数\n       行 */\n    整数: a = /* 値 */ 1;\n}\n",
+        )
+        .expect("the program should parse");
+    let ast_string = format!("{:?}", ast);
+    assert!(ast_string.contains(r#"data: Simple("a")"#));
+    assert!(ast_string.contains(r#"ASTExp_ { pos: (71, 72), data: Int("1") }"#));
+}
+
+#[test]
+fn comments_in_strings_are_kept() {
+    let mut test_parser = LazeParser::new(Path::new("./parser_files/ja.peg"));
+    let ast = test_parser
+        .parse_source("関数: 実行() => () {\n    文字: s = \"// /* */\";\n}\n")
+        .expect("the program should parse");
+    assert!(format!("{:?}", ast).contains(r#""// /* */""#));
+}
@@ -0,0 +1,21 @@
+use std::path::Path;
+
+use peg_parser::PegParser;
+
+use crate::{ast::ast::ASTNode, laze_parser::parser::LazeParser, util::file_opener::open_file};
+
+#[test]
+fn same_ast_as_without_memo() {
+    let program = open_file(Path::new("./laze_tests/exp/all_operators_exp.laze"));
+    let mut plain_parser = PegParser::<ASTNode>::new()
+        .parse_parser(open_file(Path::new("./parser_files/ja.peg")))
+        .expect("ja.peg should parse");
+    let plain_ast = plain_parser
+        .parse(&program)
+        .expect("the program should parse");
+    let mut memo_parser = LazeParser::new(Path::new("./parser_files/ja.peg"));
+    let memo_ast = memo_parser
+        .parse_source(&program)
+        .expect("the program should parse");
+    assert_eq!(format!("{:?}", memo_ast), format!("{:?}", plain_ast));
+}
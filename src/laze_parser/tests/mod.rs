@@ -1,3 +1,6 @@
 pub mod assign_statement;
+pub mod comment;
 pub mod if_statement;
+pub mod memoize;
+pub mod syntax_error;
 pub mod while_statement;
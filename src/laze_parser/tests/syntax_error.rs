@@ -0,0 +1,30 @@
+use std::path::Path;
+
+use crate::{laze_parser::parser::LazeParser, util::diagnostic::DiagnosticCode};
+
+#[test]
+fn expected_tokens() {
+    let mut test_parser = LazeParser::new(Path::new("./parser_files/ja.peg"));
+    let diagnostic = test_parser
+        .parse_source("関数: 実行() => () {\n    もし (1) 抜ける;\n}\n")
+        .expect_err("the program should not parse");
+    assert_eq!(diagnostic.code, DiagnosticCode::SyntaxError);
+    assert_eq!(diagnostic.span, (28, 29));
+    assert!(diagnostic.message.starts_with("expected "));
+    assert!(diagnostic.message.contains("`ならば`"));
+    assert!(!diagnostic.message.contains("` `"));
+}
+
+// the program parsed up to the stray brace is not taken as the whole program
+#[test]
+fn unparsed_rest() {
+    let mut test_parser = LazeParser::new(Path::new("./parser_files/ja.peg"));
+    let source = "関数: 実行() => () {\n}\n}\n";
+    let diagnostic = test_parser
+        .parse_source(source)
+        .expect_err("the program should not parse");
+    assert_eq!(diagnostic.code, DiagnosticCode::SyntaxError);
+    let brace = source.chars().count() - 2;
+    assert_eq!(diagnostic.span, (brace, brace + 1));
+    assert!(diagnostic.message.starts_with("expected "));
+}
@@ -1,32 +1,119 @@
-use std::{env, path::Path, time::Instant};
+use std::{
+    env,
+    fs::File,
+    io::{stderr, Write},
+    path::Path,
+    process::exit,
+};
 
-use command_handler::handler::handle_args;
+use command_handler::{
+    compiler_info::{CompilerInfo, CompilerMode, EmitType},
+    handler::handle_args,
+};
 
-use crate::wasm::{print_tree::print_module::fwrite_tree, semantic::trans_ast::trans_ast};
+use crate::{
+    converter::convert::Converter,
+    util::{
+        diagnostic::{has_error, DiagnosticList},
+        file_opener::open_file,
+        source_map::SourceMap,
+    },
+    wasm::{
+        encode::encode_module::fwrite_wasm, print_tree::print_module::fwrite_tree,
+        semantic::trans_ast::trans_ast,
+    },
+};
 
 // use crate::wasm::semantic::trans_ast::trans_ast;
 
 pub mod ast;
 pub mod command_handler;
+pub mod converter;
 pub mod laze_parser;
 pub mod util;
 pub mod wasm;
 
 fn main() {
     env::set_var("RUST_BACKTRACE", "1");
-    let start = Instant::now();
     let mut info = handle_args(std::env::args());
+    if let CompilerMode::Convert = info.mode {
+        convert(&mut info);
+        return;
+    }
     let ast = info.parser.parse(Path::new(&info.program_file_path));
-    println!("{}ms", start.elapsed().as_millis());
-    println!("{:?}", ast);
-    let (module_list, mem_size) = trans_ast(ast);
-    println!("{}ms", start.elapsed().as_millis());
-    println!("{:?}", module_list);
-    fwrite_tree(
-        &module_list,
-        mem_size,
-        &Path::new(&info.program_file_path),
-        Some(&Path::new(&info.dist_file_path)),
+    let source_map = SourceMap::new(
+        &info.program_file_path,
+        &open_file(Path::new(&info.program_file_path)),
     );
-    println!("{}ms", start.elapsed().as_millis());
+    let (module_list, mem_size, mut semantic_diagnostics) = trans_ast(ast, source_map.clone());
+    let mut diagnostics = std::mem::take(&mut info.parser.diagnostics);
+    diagnostics.append(&mut semantic_diagnostics);
+    report_diagnostics(&diagnostics, &source_map);
+    let dist_path = if info.dist_file_path.is_empty() {
+        None
+    } else {
+        Some(Path::new(&info.dist_file_path))
+    };
+    match info.emit {
+        EmitType::Wat => fwrite_tree(
+            &module_list,
+            mem_size,
+            Path::new(&info.program_file_path),
+            dist_path,
+        ),
+        EmitType::Wasm => fwrite_wasm(
+            &module_list,
+            mem_size,
+            Path::new(&info.program_file_path),
+            dist_path,
+        ),
+    }
+}
+
+fn convert(info: &mut CompilerInfo) {
+    let ast = info.parser.parse(Path::new(&info.program_file_path));
+    let source_map = SourceMap::new(
+        &info.program_file_path,
+        &open_file(Path::new(&info.program_file_path)),
+    );
+    report_diagnostics(&info.parser.diagnostics, &source_map);
+    let mut converter = match Converter::new(Path::new(&info.target_parser_file_path)) {
+        Ok(converter) => converter,
+        Err(mes) => {
+            let _ = writeln!(stderr(), "{mes}");
+            exit(1);
+        }
+    };
+    let program = match converter.convert(&ast) {
+        Ok(program) => program,
+        Err(mes) => {
+            let _ = writeln!(stderr(), "{mes}");
+            exit(1);
+        }
+    };
+    if info.dist_file_path.is_empty() {
+        print!("{program}");
+    } else {
+        let result = File::create(&info.dist_file_path)
+            .and_then(|mut file| file.write_all(program.as_bytes()));
+        if let Err(error) = result {
+            let _ = writeln!(
+                stderr(),
+                "Could not write to \"{}\" because of: {}",
+                info.dist_file_path,
+                error
+            );
+            exit(1);
+        }
+    }
+}
+
+// Prints the diagnostics and exits if one of them is an error.
+fn report_diagnostics(diagnostics: &DiagnosticList, source_map: &SourceMap) {
+    for diagnostic in diagnostics {
+        let _ = write!(stderr(), "{}", diagnostic.render(source_map));
+    }
+    if has_error(diagnostics) {
+        exit(1);
+    }
 }
@@ -0,0 +1,164 @@
+use std::cell::RefCell;
+
+use super::source_map::{display_width, SourceMap};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiagnosticCode {
+    // the grammar or a translation produced a node that its caller did not expect
+    UnexpectedNode,
+    SyntaxError,
+    UndefinedVariable,
+    NotAVariable,
+    NotAFunction,
+    NotAPointer,
+    NotAClass,
+    UndefinedMember,
+    PrivateMember,
+    TypeMismatch,
+    InvalidOperator,
+    NotAddressable,
+    NotConstant,
+    TemplateParams,
+    InvalidType,
+    MultipleReturnValues,
+    InvalidDeclaration,
+    NotInLoop,
+    // a type given to a template does not have what a 制約 on its type parameter requires
+    UnsatisfiedConstraint,
+    // trans_stm does not have a translation for the kind of the statement
+    UnsupportedStatement,
+    MultipleUnaryOperators,
+    MultiDimensionalArray,
+    // 無限ループ without 抜ける or 終了 in it
+    EndlessLoop,
+    // an operator between a char and a short, which are converted without a check
+    CharShortConversion,
+}
+
+impl DiagnosticCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UnexpectedNode => "E0001",
+            Self::SyntaxError => "E0002",
+            Self::UndefinedVariable => "E0101",
+            Self::NotAVariable => "E0102",
+            Self::NotAFunction => "E0103",
+            Self::NotAPointer => "E0104",
+            Self::NotAClass => "E0105",
+            Self::UndefinedMember => "E0106",
+            Self::PrivateMember => "E0107",
+            Self::TypeMismatch => "E0108",
+            Self::InvalidOperator => "E0109",
+            Self::NotAddressable => "E0110",
+            Self::NotConstant => "E0111",
+            Self::TemplateParams => "E0112",
+            Self::InvalidType => "E0113",
+            Self::MultipleReturnValues => "E0114",
+            Self::InvalidDeclaration => "E0115",
+            Self::NotInLoop => "E0116",
+            Self::UnsatisfiedConstraint => "E0117",
+            Self::UnsupportedStatement => "E0118",
+            Self::MultipleUnaryOperators => "W0101",
+            Self::MultiDimensionalArray => "W0102",
+            Self::EndlessLoop => "W0103",
+            Self::CharShortConversion => "W0104",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+    pub message: String,
+    // start and end char offsets, same as the pos of AST nodes
+    pub span: (usize, usize),
+}
+
+pub type DiagnosticList = Vec<Diagnostic>;
+
+impl Diagnostic {
+    pub fn error(code: DiagnosticCode, span: (usize, usize), message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code,
+            message,
+            span,
+        }
+    }
+    pub fn warning(code: DiagnosticCode, span: (usize, usize), message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            code,
+            message,
+            span,
+        }
+    }
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+    // error[E0101]: message
+    //  --> file:line:column
+    //   |
+    // 3 |     source line
+    //   |     ^^^^
+    pub fn render(&self, source_map: &SourceMap) -> String {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let (line, column) = source_map.line_column(self.span.0);
+        let line_content = source_map.line(line);
+        let line_chars: Vec<char> = line_content.chars().collect();
+        let caret_start = (column - 1).min(line_chars.len());
+        // spans over multiple lines are underlined until the end of the first line
+        let caret_end = (caret_start + self.span.1.saturating_sub(self.span.0))
+            .min(line_chars.len())
+            .max(caret_start + 1);
+        // tabs are copied, so that the carets line up however wide the terminal shows them
+        let padding: String = line_chars[..caret_start]
+            .iter()
+            .map(|c| match c {
+                '\t' => "\t".to_string(),
+                c => " ".repeat(display_width(&c.to_string())),
+            })
+            .collect();
+        let under_caret: String = line_chars[caret_start..caret_end.min(line_chars.len())]
+            .iter()
+            .collect();
+        let line_number = line.to_string();
+        let margin = " ".repeat(line_number.len());
+        format!(
+            "{severity}[{}]: {}\n{margin}--> {}\n{margin} |\n{line_number} | {line_content}\n{margin} | {}{}\n",
+            self.code.as_str(),
+            self.message,
+            source_map.location(self.span),
+            padding,
+            "^".repeat(display_width(&under_caret).max(1)),
+        )
+    }
+}
+
+pub fn has_error(diagnostics: &DiagnosticList) -> bool {
+    diagnostics.iter().any(|diagnostic| diagnostic.is_error())
+}
+
+// The AST extractors are called from inside the PEG parser,
+// so they cannot hold a list and report here instead.
+thread_local! {
+    static REPORTED: RefCell<DiagnosticList> = const { RefCell::new(vec![]) };
+}
+
+pub fn report(diagnostic: Diagnostic) {
+    REPORTED.with(|reported| reported.borrow_mut().push(diagnostic));
+}
+
+pub fn take_reported() -> DiagnosticList {
+    REPORTED.with(|reported| reported.take())
+}
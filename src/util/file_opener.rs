@@ -25,3 +25,36 @@ pub fn open_file(path: &Path) -> String {
         }
     }
 }
+
+// Writes the output of the compiler to dist_path,
+// or to dist/<file name>.<extension> when no path is given.
+pub fn write_output(file_path: &Path, dist_path: Option<&Path>, extension: &str, content: &[u8]) {
+    let file_name_only = file_path.file_name();
+    if let Some(file_name) = file_name_only {
+        let dist_file = if let Some(dist) = dist_path {
+            format_args!("{}", dist.to_str().unwrap(),).to_string()
+        } else {
+            format_args!("dist/{}.{}", file_name.to_str().unwrap(), extension).to_string()
+        };
+        let file_to_write = File::create(&dist_file);
+        if let Ok(mut file) = file_to_write {
+            let result = file.write_all(content);
+            if let Err(error) = result {
+                let _ = writeln!(
+                    stderr(),
+                    "Error while writing to file {}: {}",
+                    dist_file,
+                    error
+                );
+            }
+        } else if let Err(error) = file_to_write {
+            let _ = writeln!(stderr(), "Could not open file {}: {}", dist_file, error);
+        }
+    } else {
+        let _ = writeln!(
+            stderr(),
+            "Could not get file name of {}",
+            file_path.to_str().unwrap()
+        );
+    }
+}
@@ -1 +1,5 @@
+pub mod diagnostic;
 pub mod file_opener;
+pub mod source_map;
+#[cfg(test)]
+pub mod tests;
@@ -0,0 +1,65 @@
+// Converts the char offsets in the pos of AST nodes into lines and columns.
+// Offsets count chars, not bytes, so Japanese text is handled like ASCII.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceMap {
+    pub file_name: String,
+    lines: Vec<String>,
+    // char offset of the first char of each line
+    line_starts: Vec<usize>,
+}
+
+impl SourceMap {
+    pub fn new(file_name: &str, source: &str) -> Self {
+        let mut lines = vec![];
+        let mut line_starts = vec![];
+        let mut offset = 0;
+        for line in source.split('\n') {
+            line_starts.push(offset);
+            offset += line.chars().count() + 1;
+            lines.push(line.trim_end_matches('\r').to_string());
+        }
+        SourceMap {
+            file_name: file_name.to_string(),
+            lines,
+            line_starts,
+        }
+    }
+    // line and column of a char offset, both starting from 1
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let line_index = match self.line_starts.binary_search(&offset) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        };
+        (line_index + 1, offset - self.line_starts[line_index] + 1)
+    }
+    pub fn line(&self, line: usize) -> &str {
+        self.lines.get(line - 1).map_or("", |line| line.as_str())
+    }
+    // file:line:column, the format that editors can jump to
+    pub fn location(&self, pos: (usize, usize)) -> String {
+        let (line, column) = self.line_column(pos.0);
+        format!("{}:{}:{}", self.file_name, line, column)
+    }
+}
+
+// Width of the text in a terminal.
+// Full-width characters such as kanji and kana take two cells.
+pub fn display_width(str: &str) -> usize {
+    str.chars().map(|c| if is_wide(c) { 2 } else { 1 }).sum()
+}
+
+fn is_wide(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x20000..=0x3FFFD)
+}
@@ -0,0 +1,57 @@
+use crate::util::{
+    diagnostic::{has_error, Diagnostic, DiagnosticCode},
+    source_map::SourceMap,
+};
+
+#[test]
+fn render_error() {
+    let source = "関数: 実行() => () {\n    b = 1;\n}\n";
+    let diagnostic = Diagnostic::error(
+        DiagnosticCode::NotAVariable,
+        (21, 22),
+        "\"b\" is not a variable.".to_string(),
+    );
+    assert_eq!(
+        diagnostic.render(&SourceMap::new("test.laze", source)),
+        "error[E0102]: \"b\" is not a variable.\n --> test.laze:2:5\n  |\n2 |     b = 1;\n  |     ^\n"
+    );
+}
+
+#[test]
+fn warnings_are_not_errors() {
+    let warning = Diagnostic::warning(
+        DiagnosticCode::MultiDimensionalArray,
+        (0, 1),
+        "Avoid using N-Dimensional arrays.".to_string(),
+    );
+    assert!(!has_error(&vec![warning.clone()]));
+    assert!(warning
+        .render(&SourceMap::new("test.laze", "a"))
+        .starts_with("warning[W0102]"));
+}
+
+#[test]
+fn render_caret_under_wide_chars() {
+    let source = "関数: 実行() => () {\n    変数 = 1;\n}\n";
+    let diagnostic = Diagnostic::error(
+        DiagnosticCode::NotAVariable,
+        (21, 23),
+        "\"変数\" is not a variable.".to_string(),
+    );
+    assert!(diagnostic
+        .render(&SourceMap::new("test.laze", source))
+        .ends_with("2 |     変数 = 1;\n  |     ^^^^\n"));
+}
+
+#[test]
+fn render_caret_after_tabs() {
+    let source = "関数: 実行() => () {\n\t\t変数 = 1;\n}\n";
+    let diagnostic = Diagnostic::error(
+        DiagnosticCode::NotAVariable,
+        (19, 21),
+        "\"変数\" is not a variable.".to_string(),
+    );
+    assert!(diagnostic
+        .render(&SourceMap::new("test.laze", source))
+        .ends_with("2 | \t\t変数 = 1;\n  | \t\t^^^^\n"));
+}
@@ -0,0 +1,18 @@
+use crate::util::source_map::{display_width, SourceMap};
+
+#[test]
+fn line_column_counts_chars() {
+    let source_map = SourceMap::new("test.laze", "関数: 実行() => () {\r\n    b = 1;\r\n}\r\n");
+    assert_eq!(source_map.line_column(0), (1, 1));
+    assert_eq!(source_map.line_column(4), (1, 5));
+    assert_eq!(source_map.line_column(22), (2, 5));
+    assert_eq!(source_map.line(2), "    b = 1;");
+    assert_eq!(source_map.location((22, 23)), "test.laze:2:5");
+}
+
+#[test]
+fn wide_chars_take_two_cells() {
+    assert_eq!(display_width("ab"), 2);
+    assert_eq!(display_width("関数"), 4);
+    assert_eq!(display_width("実行()"), 6);
+}
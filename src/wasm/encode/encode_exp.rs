@@ -0,0 +1,266 @@
+use std::io::{stderr, Write};
+
+use crate::wasm::il::{
+    exp::{ConstData, Exp, ExpData, ExpList},
+    util::{BinOper, UniOper, WasmType},
+};
+
+use super::{
+    encode_stm::encode_stmlist,
+    utils::{encode_i32, encode_i64, encode_index, encode_u32, encode_valtype},
+};
+
+fn binop_opcode(oper: &BinOper, ty: &WasmType) -> Option<u8> {
+    match ty {
+        WasmType::I32 => match oper {
+            BinOper::Eq => Some(0x46),
+            BinOper::Ne => Some(0x47),
+            BinOper::LtSigned => Some(0x48),
+            BinOper::LtUnsigned => Some(0x49),
+            BinOper::GtSigned => Some(0x4a),
+            BinOper::GtUnsigned => Some(0x4b),
+            BinOper::LeSigned => Some(0x4c),
+            BinOper::LeUnsigned => Some(0x4d),
+            BinOper::GeSigned => Some(0x4e),
+            BinOper::GeUnsigned => Some(0x4f),
+            BinOper::Add => Some(0x6a),
+            BinOper::Sub => Some(0x6b),
+            BinOper::Mul => Some(0x6c),
+            BinOper::DivSigned => Some(0x6d),
+            BinOper::DivUnsigned => Some(0x6e),
+            BinOper::RemSigned => Some(0x6f),
+            BinOper::RemUnsigned => Some(0x70),
+            BinOper::And => Some(0x71),
+            BinOper::Or => Some(0x72),
+            BinOper::Shl => Some(0x74),
+            BinOper::None => None,
+        },
+        WasmType::I64 => match oper {
+            BinOper::Eq => Some(0x51),
+            BinOper::Ne => Some(0x52),
+            BinOper::LtSigned => Some(0x53),
+            BinOper::LtUnsigned => Some(0x54),
+            BinOper::GtSigned => Some(0x55),
+            BinOper::GtUnsigned => Some(0x56),
+            BinOper::LeSigned => Some(0x57),
+            BinOper::LeUnsigned => Some(0x58),
+            BinOper::GeSigned => Some(0x59),
+            BinOper::GeUnsigned => Some(0x5a),
+            BinOper::Add => Some(0x7c),
+            BinOper::Sub => Some(0x7d),
+            BinOper::Mul => Some(0x7e),
+            BinOper::DivSigned => Some(0x7f),
+            BinOper::DivUnsigned => Some(0x80),
+            BinOper::RemSigned => Some(0x81),
+            BinOper::RemUnsigned => Some(0x82),
+            BinOper::And => Some(0x83),
+            BinOper::Or => Some(0x84),
+            BinOper::Shl => Some(0x86),
+            BinOper::None => None,
+        },
+        WasmType::F32 => match oper {
+            BinOper::Eq => Some(0x5b),
+            BinOper::Ne => Some(0x5c),
+            BinOper::LtSigned => Some(0x5d),
+            BinOper::GtSigned => Some(0x5e),
+            BinOper::LeSigned => Some(0x5f),
+            BinOper::GeSigned => Some(0x60),
+            BinOper::Add => Some(0x92),
+            BinOper::Sub => Some(0x93),
+            BinOper::Mul => Some(0x94),
+            BinOper::DivSigned => Some(0x95),
+            _ => None,
+        },
+        WasmType::F64 => match oper {
+            BinOper::Eq => Some(0x61),
+            BinOper::Ne => Some(0x62),
+            BinOper::LtSigned => Some(0x63),
+            BinOper::GtSigned => Some(0x64),
+            BinOper::LeSigned => Some(0x65),
+            BinOper::GeSigned => Some(0x66),
+            BinOper::Add => Some(0xa0),
+            BinOper::Sub => Some(0xa1),
+            BinOper::Mul => Some(0xa2),
+            BinOper::DivSigned => Some(0xa3),
+            _ => None,
+        },
+        WasmType::None => None,
+    }
+}
+
+fn unaryop_opcode(oper: &UniOper, ty: &WasmType) -> Option<u8> {
+    if let UniOper::Clz = oper {
+        return match ty {
+            WasmType::I32 => Some(0x67),
+            WasmType::I64 => Some(0x79),
+            _ => None,
+        };
+    }
+    let base = match ty {
+        WasmType::F32 => 0x8b,
+        WasmType::F64 => 0x99,
+        _ => return None,
+    };
+    Some(
+        base + match oper {
+            UniOper::Abs => 0,
+            UniOper::Neg => 1,
+            UniOper::Ceil => 2,
+            UniOper::Floor => 3,
+            UniOper::Trunc => 4,
+            UniOper::Nearest => 5,
+            UniOper::Sqrt => 6,
+            UniOper::Clz => return None,
+        },
+    )
+}
+
+fn convert_opcode(to: &WasmType, from: &WasmType) -> Option<u8> {
+    match (to, from) {
+        (WasmType::I32, WasmType::I64) => Some(0xa7),
+        (WasmType::I32, WasmType::F32) => Some(0xa8),
+        (WasmType::I32, WasmType::F64) => Some(0xaa),
+        (WasmType::I64, WasmType::I32) => Some(0xac),
+        (WasmType::I64, WasmType::F32) => Some(0xae),
+        (WasmType::I64, WasmType::F64) => Some(0xb0),
+        (WasmType::F32, WasmType::I32) => Some(0xb2),
+        (WasmType::F32, WasmType::I64) => Some(0xb4),
+        (WasmType::F32, WasmType::F64) => Some(0xb6),
+        (WasmType::F64, WasmType::I32) => Some(0xb7),
+        (WasmType::F64, WasmType::I64) => Some(0xb9),
+        (WasmType::F64, WasmType::F32) => Some(0xbb),
+        _ => None,
+    }
+}
+
+// (opcode, alignment) of the load / store instruction for the type
+pub fn memory_opcode(ty: &WasmType, is_store: bool) -> Option<(u8, u32)> {
+    let (opcode, align) = match ty {
+        WasmType::I32 => (0x28, 2),
+        WasmType::I64 => (0x29, 3),
+        WasmType::F32 => (0x2a, 2),
+        WasmType::F64 => (0x2b, 3),
+        WasmType::None => return None,
+    };
+    Some((if is_store { opcode + 0x0e } else { opcode }, align))
+}
+
+pub fn encode_exp(exp: &Exp, bytes: &mut Vec<u8>) {
+    match &exp.data {
+        ExpData::BinOp(oper, lhs, rhs) => {
+            encode_exp(lhs, bytes);
+            encode_exp(rhs, bytes);
+            match binop_opcode(oper, &exp.ty) {
+                Some(opcode) => bytes.push(opcode),
+                None => {
+                    let _ = writeln!(
+                        stderr(),
+                        "{}.{} does not exist in WebAssembly.",
+                        exp.ty.to_string(),
+                        oper.to_string(&exp.ty)
+                    );
+                }
+            }
+        }
+        ExpData::CallExp(index, _label, args) => {
+            encode_explist(args, bytes);
+            bytes.push(0x10);
+            encode_index(*index, bytes);
+        }
+        ExpData::CallIndirect(index, args, type_index) => {
+            encode_explist(args, bytes);
+            encode_exp(index, bytes);
+            bytes.push(0x11);
+            encode_index(*type_index, bytes);
+            bytes.push(0x00);
+        }
+        ExpData::Const(const_data) => match const_data {
+            ConstData::I32(data) => {
+                bytes.push(0x41);
+                encode_i32(*data, bytes);
+            }
+            ConstData::I64(data) => {
+                bytes.push(0x42);
+                encode_i64(*data, bytes);
+            }
+            ConstData::F32(data) => {
+                bytes.push(0x43);
+                bytes.extend_from_slice(&data.to_le_bytes());
+            }
+            ConstData::F64(data) => {
+                bytes.push(0x44);
+                bytes.extend_from_slice(&data.to_le_bytes());
+            }
+        },
+        ExpData::Convert(convert_exp) => {
+            encode_exp(convert_exp, bytes);
+            if let Some(opcode) = convert_opcode(&exp.ty, &convert_exp.ty) {
+                bytes.push(opcode);
+            }
+        }
+        ExpData::GetGlobal(index) => {
+            bytes.push(0x23);
+            encode_index(*index, bytes);
+        }
+        ExpData::GetLocal(index) => {
+            bytes.push(0x20);
+            encode_index(*index, bytes);
+        }
+        ExpData::TeeLocal(index, value) => {
+            encode_exp(value, bytes);
+            bytes.push(0x22);
+            encode_index(*index, bytes);
+        }
+        ExpData::IfExp(test_exp, then_exp, else_exp) => {
+            encode_exp(test_exp, bytes);
+            bytes.push(0x04);
+            bytes.push(encode_valtype(&exp.ty).unwrap_or(0x40));
+            encode_exp(then_exp, bytes);
+            bytes.push(0x05);
+            encode_exp(else_exp, bytes);
+            bytes.push(0x0b);
+        }
+        ExpData::Block(stmlist, value) => {
+            bytes.push(0x02);
+            bytes.push(encode_valtype(&exp.ty).unwrap_or(0x40));
+            // the statements that an expression needs only branch to the blocks in them
+            encode_stmlist(stmlist, &mut vec![None], bytes);
+            encode_exp(value, bytes);
+            bytes.push(0x0b);
+        }
+        ExpData::Load(addr) => {
+            encode_exp(addr, bytes);
+            if let Some((opcode, align)) = memory_opcode(&exp.ty, false) {
+                bytes.push(opcode);
+                encode_u32(align, bytes);
+                encode_u32(0, bytes);
+            }
+        }
+        ExpData::MemorySize => bytes.extend_from_slice(&[0x3f, 0x00]),
+        ExpData::MemoryGrow(pages) => {
+            encode_exp(pages, bytes);
+            bytes.extend_from_slice(&[0x40, 0x00]);
+        }
+        ExpData::None => {}
+        ExpData::UnaryOp(oper, op_exp) => {
+            encode_exp(op_exp, bytes);
+            match unaryop_opcode(oper, &exp.ty) {
+                Some(opcode) => bytes.push(opcode),
+                None => {
+                    let _ = writeln!(
+                        stderr(),
+                        "{}.{} does not exist in WebAssembly.",
+                        exp.ty.to_string(),
+                        oper.to_string()
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub fn encode_explist(explist: &ExpList, bytes: &mut Vec<u8>) {
+    for exp in explist {
+        encode_exp(exp, bytes);
+    }
+}
@@ -0,0 +1,237 @@
+use std::path::Path;
+
+use crate::{
+    util::file_opener::write_output,
+    wasm::il::{
+        exp::Exp,
+        module::{ModuleList, Module_},
+        stm::Stm_,
+        util::{WasmType, WasmTypeList},
+    },
+};
+
+use super::{
+    encode_exp::encode_exp,
+    encode_stm::encode_stm,
+    utils::{
+        encode_functype, encode_i32, encode_index, encode_locals, encode_name, encode_section,
+        encode_u32, encode_valtype,
+    },
+};
+
+// Function types are shared by their encoding.
+// Explicit type modules come first so that their indices are the ones used by call_indirect.
+struct TypeSection {
+    types: Vec<Vec<u8>>,
+}
+
+impl TypeSection {
+    fn push(&mut self, params: &WasmTypeList, result: &WasmType) {
+        let mut functype = vec![];
+        encode_functype(params, result, &mut functype);
+        self.types.push(functype);
+    }
+    fn index_of(&mut self, params: &WasmTypeList, result: &WasmType) -> u32 {
+        let mut functype = vec![];
+        encode_functype(params, result, &mut functype);
+        match self.types.iter().position(|ty| *ty == functype) {
+            Some(index) => index as u32,
+            None => {
+                self.types.push(functype);
+                self.types.len() as u32 - 1
+            }
+        }
+    }
+}
+
+fn encode_const_exp(exp: &Exp, bytes: &mut Vec<u8>) {
+    encode_exp(exp, bytes);
+    bytes.push(0x0b);
+}
+
+pub fn encode_tree(modulelist: &ModuleList, mem_size: i32) -> Vec<u8> {
+    let mut types = TypeSection { types: vec![] };
+    for module in modulelist {
+        if let Module_::Type(params, result) = &**module {
+            types.push(params, result);
+        }
+    }
+
+    let mut imports = (0, vec![]);
+    let mut functions = (0, vec![]);
+    let mut tables = (0, vec![]);
+    let mut memories = (0, vec![]);
+    let mut globals = (0, vec![]);
+    let mut exports = (0, vec![]);
+    let mut elems = (0, vec![]);
+    let mut codes = (0, vec![]);
+    let mut start = None;
+    let mut datas = (0, vec![]);
+
+    let import_func_num = modulelist
+        .iter()
+        .filter(|module| {
+            matches!(&***module, Module_::JsImport(_, _, import) if matches!(**import, Module_::Func(..)))
+        })
+        .count() as u32;
+    let mut func_index = import_func_num;
+
+    for module in modulelist {
+        match &**module {
+            Module_::JsImport(name, module_name, import) => match &**import {
+                Module_::Func(_, params, _, result, _, _) => {
+                    encode_name(module_name, &mut imports.1);
+                    encode_name(name, &mut imports.1);
+                    imports.1.push(0x00);
+                    encode_u32(types.index_of(params, result), &mut imports.1);
+                    imports.0 += 1;
+                }
+                Module_::Memory(page_size) => {
+                    encode_name(module_name, &mut imports.1);
+                    encode_name(name, &mut imports.1);
+                    imports.1.push(0x02);
+                    imports.1.push(0x00);
+                    encode_index(*page_size, &mut imports.1);
+                    imports.0 += 1;
+                }
+                // the semantic pass imports only functions and the memory
+                _ => {}
+            },
+            Module_::Func(_, params, locals, result, body, export_name) => {
+                encode_u32(types.index_of(params, result), &mut functions.1);
+                functions.0 += 1;
+                if let Some(name) = export_name {
+                    encode_name(name, &mut exports.1);
+                    exports.1.push(0x00);
+                    encode_u32(func_index, &mut exports.1);
+                    exports.0 += 1;
+                }
+                let mut code = vec![];
+                encode_locals(locals, &mut code);
+                // the body of a function is already a block
+                let mut labels = vec![];
+                if let Stm_::Block(stmlist) = &**body {
+                    for stm in stmlist {
+                        encode_stm(stm, &mut labels, &mut code);
+                    }
+                } else {
+                    encode_stm(body, &mut labels, &mut code);
+                }
+                code.push(0x0b);
+                encode_u32(code.len() as u32, &mut codes.1);
+                codes.1.extend(code);
+                codes.0 += 1;
+                func_index += 1;
+            }
+            Module_::JsExport(export_name, index) => {
+                encode_name(export_name, &mut exports.1);
+                exports.1.push(0x00);
+                encode_index(*index, &mut exports.1);
+                exports.0 += 1;
+            }
+            Module_::Global(var_ty, mutable, init_val) => {
+                globals.1.extend(encode_valtype(var_ty));
+                globals.1.push(*mutable as u8);
+                encode_const_exp(init_val, &mut globals.1);
+                globals.0 += 1;
+            }
+            Module_::Data(data, offset) => {
+                datas.1.push(0x00);
+                encode_const_exp(offset, &mut datas.1);
+                encode_name(data, &mut datas.1);
+                datas.0 += 1;
+            }
+            Module_::Elem(offset, modlist) => {
+                elems.1.push(0x00);
+                encode_const_exp(offset, &mut elems.1);
+                let indices: Vec<i32> = modlist
+                    .iter()
+                    .filter_map(|module| match **module {
+                        Module_::Func(index, ..) => Some(index),
+                        _ => None,
+                    })
+                    .collect();
+                encode_u32(indices.len() as u32, &mut elems.1);
+                for index in indices {
+                    encode_index(index, &mut elems.1);
+                }
+                elems.0 += 1;
+            }
+            Module_::Table(table_size) => {
+                tables.1.push(0x70);
+                tables.1.push(0x00);
+                encode_index(*table_size, &mut tables.1);
+                tables.0 += 1;
+            }
+            Module_::Memory(page_size) => {
+                memories.1.push(0x00);
+                encode_index(*page_size, &mut memories.1);
+                memories.0 += 1;
+            }
+            Module_::Start(index) => start = Some(*index),
+            Module_::Type(..) | Module_::None => {}
+        }
+    }
+
+    // memorySize and clearMemory, which are also added to the text format
+    let mut memory_size_code = vec![0x00, 0x41];
+    encode_i32(mem_size, &mut memory_size_code);
+    memory_size_code.extend_from_slice(&[0x0f, 0x0b]);
+    let mut clear_memory_code = vec![0x00, 0x41, 0x00, 0x41, 0x00, 0x41];
+    encode_i32(1114112, &mut clear_memory_code);
+    clear_memory_code.extend_from_slice(&[0xfc, 0x0b, 0x00, 0x0b]);
+    for (name, result, code) in [
+        ("memorySize", WasmType::I32, memory_size_code),
+        ("clearMemory", WasmType::None, clear_memory_code),
+    ] {
+        encode_u32(types.index_of(&vec![], &result), &mut functions.1);
+        functions.0 += 1;
+        encode_name(name, &mut exports.1);
+        exports.1.push(0x00);
+        encode_u32(func_index, &mut exports.1);
+        exports.0 += 1;
+        encode_u32(code.len() as u32, &mut codes.1);
+        codes.1.extend(code);
+        codes.0 += 1;
+        func_index += 1;
+    }
+
+    let mut bytes = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+    encode_section(1, types.types.len(), types.types.concat(), &mut bytes);
+    for (id, (count, content)) in [
+        (2, imports),
+        (3, functions),
+        (4, tables),
+        (5, memories),
+        (6, globals),
+        (7, exports),
+    ] {
+        encode_section(id, count, content, &mut bytes);
+    }
+    // the start section is only the index of the function
+    if let Some(index) = start {
+        let mut section = vec![];
+        encode_index(index, &mut section);
+        bytes.push(8);
+        encode_u32(section.len() as u32, &mut bytes);
+        bytes.extend(section);
+    }
+    for (id, (count, content)) in [(9, elems), (10, codes), (11, datas)] {
+        encode_section(id, count, content, &mut bytes);
+    }
+    bytes
+}
+
+pub fn fwrite_wasm(
+    modulelist: &ModuleList,
+    mem_size: i32,
+    file_path: &Path,
+    dist_path: Option<&Path>,
+) {
+    write_output(
+        file_path,
+        dist_path,
+        "wasm",
+        &encode_tree(modulelist, mem_size),
+    );
+}
@@ -0,0 +1,123 @@
+use crate::wasm::il::stm::{Stm, StmList, Stm_};
+
+use super::{
+    encode_exp::{encode_exp, encode_explist, memory_opcode},
+    utils::{encode_index, encode_u32},
+};
+
+// The labels of the blocks, the loops and the ifs that enclose the statement, the innermost last.
+// Only the ones that can be the target of a branch have a label.
+pub type LabelStack = Vec<Option<i32>>;
+
+// the relative depth of the block or the loop that a branch targets
+fn label_depth(label: i32, labels: &LabelStack) -> i32 {
+    match labels.iter().rev().position(|l| *l == Some(label)) {
+        Some(depth) => depth as i32,
+        None => panic!("Branch to the label {} that does not enclose it.", label),
+    }
+}
+
+pub fn encode_stm(stm: &Stm, labels: &mut LabelStack, bytes: &mut Vec<u8>) {
+    match &**stm {
+        Stm_::Block(stmlist) => {
+            bytes.push(0x02);
+            bytes.push(0x40);
+            labels.push(None);
+            encode_stmlist(stmlist, labels, bytes);
+            labels.pop();
+            bytes.push(0x0b);
+        }
+        Stm_::LabeledBlock(label, stmlist) => {
+            bytes.push(0x02);
+            bytes.push(0x40);
+            labels.push(Some(*label));
+            encode_stmlist(stmlist, labels, bytes);
+            labels.pop();
+            bytes.push(0x0b);
+        }
+        Stm_::Break(label) => {
+            bytes.push(0x0c);
+            encode_index(label_depth(*label, labels), bytes);
+        }
+        Stm_::BreakIf(label, test_exp) => {
+            encode_exp(test_exp, bytes);
+            bytes.push(0x0d);
+            encode_index(label_depth(*label, labels), bytes);
+        }
+        Stm_::Call(index, args, _label) => {
+            encode_explist(args, bytes);
+            bytes.push(0x10);
+            encode_index(*index, bytes);
+        }
+        Stm_::CallIndirect(func_index_exp, args, type_index) => {
+            encode_explist(args, bytes);
+            encode_exp(func_index_exp, bytes);
+            bytes.push(0x11);
+            encode_index(*type_index, bytes);
+            bytes.push(0x00);
+        }
+        Stm_::Copy(dest, src, size) => {
+            encode_exp(dest, bytes);
+            encode_exp(src, bytes);
+            encode_exp(size, bytes);
+            bytes.extend_from_slice(&[0xfc, 0x0a, 0x00, 0x00]);
+        }
+        Stm_::Drop(exp) => {
+            encode_exp(exp, bytes);
+            bytes.push(0x1a);
+        }
+        Stm_::If(test_exp, then_stm, else_stm) => {
+            encode_exp(test_exp, bytes);
+            bytes.push(0x04);
+            bytes.push(0x40);
+            labels.push(None);
+            encode_stm(then_stm, labels, bytes);
+            if let Stm_::None = **else_stm {
+            } else {
+                bytes.push(0x05);
+                encode_stm(else_stm, labels, bytes);
+            }
+            labels.pop();
+            bytes.push(0x0b);
+        }
+        Stm_::Loop(label, stmlist) => {
+            bytes.push(0x03);
+            bytes.push(0x40);
+            labels.push(Some(*label));
+            encode_stmlist(stmlist, labels, bytes);
+            labels.pop();
+            bytes.push(0x0b);
+        }
+        Stm_::None => {}
+        Stm_::Unreachable => bytes.push(0x00),
+        Stm_::Return(return_exp) => {
+            encode_exp(return_exp, bytes);
+            bytes.push(0x0f);
+        }
+        Stm_::SetGlobal(index, exp) => {
+            encode_exp(exp, bytes);
+            bytes.push(0x24);
+            encode_index(*index, bytes);
+        }
+        Stm_::SetLocal(index, exp) => {
+            encode_exp(exp, bytes);
+            bytes.push(0x21);
+            encode_index(*index, bytes);
+        }
+        Stm_::Store(addr, value) => {
+            encode_exp(addr, bytes);
+            encode_exp(value, bytes);
+            if let Some((opcode, align)) = memory_opcode(&value.ty, true) {
+                bytes.push(opcode);
+                encode_u32(align, bytes);
+                encode_u32(0, bytes);
+            }
+        }
+    }
+}
+
+pub fn encode_stmlist(stmlist: &StmList, labels: &mut LabelStack, bytes: &mut Vec<u8>) {
+    for stm in stmlist {
+        encode_stm(stm, labels, bytes);
+    }
+}
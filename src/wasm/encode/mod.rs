@@ -0,0 +1,6 @@
+pub mod encode_exp;
+pub mod encode_module;
+pub mod encode_stm;
+#[cfg(test)]
+pub mod tests;
+pub mod utils;
@@ -0,0 +1,28 @@
+use crate::wasm::encode::utils::{encode_i32, encode_i64, encode_u32};
+
+#[test]
+fn unsigned() {
+    let mut bytes = vec![];
+    encode_u32(0, &mut bytes);
+    encode_u32(127, &mut bytes);
+    encode_u32(128, &mut bytes);
+    encode_u32(624485, &mut bytes);
+    assert_eq!(bytes, vec![0x00, 0x7f, 0x80, 0x01, 0xe5, 0x8e, 0x26]);
+}
+
+#[test]
+fn signed() {
+    let mut bytes = vec![];
+    encode_i32(-1, &mut bytes);
+    encode_i32(63, &mut bytes);
+    encode_i32(64, &mut bytes);
+    encode_i32(-123456, &mut bytes);
+    encode_i64(i64::MIN, &mut bytes);
+    assert_eq!(
+        bytes,
+        vec![
+            0x7f, 0x3f, 0xc0, 0x00, 0xc0, 0xbb, 0x78, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80,
+            0x80, 0x80, 0x7f
+        ]
+    );
+}
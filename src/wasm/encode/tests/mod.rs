@@ -0,0 +1,2 @@
+pub mod leb128;
+pub mod module;
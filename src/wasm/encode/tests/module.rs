@@ -0,0 +1,164 @@
+use std::{fs, path::Path};
+
+use crate::{
+    util::diagnostic::has_error,
+    wasm::{
+        encode::encode_module::encode_tree,
+        il::{
+            module::{ModuleList, Module_},
+            util::{WasmType, WasmTypeList},
+        },
+        tests::{
+            compile::compile,
+            runner::{validate, Value},
+        },
+    },
+};
+
+fn read_u32(bytes: &[u8], offset: &mut usize) -> u32 {
+    let mut result = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*offset];
+        *offset += 1;
+        result |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return result;
+        }
+        shift += 7;
+    }
+}
+
+// (id, item count) of each section; the start section holds an index instead of a count.
+fn sections(bytes: &[u8]) -> Vec<(u8, u32)> {
+    assert_eq!(bytes[..8], [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]);
+    let mut sections = vec![];
+    let mut offset = 8;
+    while offset < bytes.len() {
+        let id = bytes[offset];
+        offset += 1;
+        let size = read_u32(bytes, &mut offset) as usize;
+        let mut content_offset = offset;
+        sections.push((id, read_u32(bytes, &mut content_offset)));
+        offset += size;
+    }
+    assert_eq!(offset, bytes.len());
+    sections
+}
+
+// The signatures of the functions and the types in the module list,
+// with the ones of memorySize and clearMemory that encode_tree adds.
+fn signatures(module_list: &ModuleList) -> Vec<(WasmTypeList, WasmType)> {
+    let mut signatures = vec![(vec![], WasmType::I32), (vec![], WasmType::None)];
+    for module in module_list {
+        let signature = match &**module {
+            Module_::Type(params, result) | Module_::Func(_, params, _, result, _, _) => {
+                (params.clone(), result.clone())
+            }
+            Module_::JsImport(_, _, import) => match &**import {
+                Module_::Func(_, params, _, result, _, _) => (params.clone(), result.clone()),
+                _ => continue,
+            },
+            _ => continue,
+        };
+        if !signatures.contains(&signature) {
+            signatures.push(signature);
+        }
+    }
+    signatures
+}
+
+// Each section holds as many items as there are modules of its kind in the list.
+#[test]
+fn section_layout() {
+    let compiled = compile("./laze_tests/stm/while_stm/while_simple.laze");
+    let bytes = encode_tree(&compiled.module_list, compiled.mem_size);
+    let count = |is_kind: &dyn Fn(&Module_) -> bool| {
+        compiled
+            .module_list
+            .iter()
+            .filter(|module| is_kind(module))
+            .count() as u32
+    };
+    let imports = count(&|module| matches!(module, Module_::JsImport(..)));
+    // memorySize and clearMemory are added to the functions and the exports
+    let funcs = count(&|module| matches!(module, Module_::Func(..))) + 2;
+    let globals = count(&|module| matches!(module, Module_::Global(..)));
+    let exports = count(&|module| {
+        matches!(
+            module,
+            Module_::Func(_, _, _, _, _, Some(_)) | Module_::JsExport(..)
+        )
+    }) + 2;
+    let expected: Vec<(u8, u32)> = [
+        (1, signatures(&compiled.module_list).len() as u32),
+        (2, imports),
+        (3, funcs),
+        (6, globals),
+        (7, exports),
+        (10, funcs),
+    ]
+    .into_iter()
+    .filter(|(_, count)| *count > 0)
+    .collect();
+    assert_eq!(sections(&bytes), expected);
+}
+
+// wasmi validates the module when it loads it, and it runs as while_simple does.
+#[test]
+fn runs_encoded_module() {
+    let mut instance = compile("./laze_tests/stm/while_stm/while_simple.laze").instantiate();
+    instance.call("main", &[]);
+    assert_eq!(
+        instance.calls_of("testOutput"),
+        (0..100).map(|x| vec![Value::I64(x)]).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn start_section() {
+    let compiled = compile("./laze_tests/dec/global_var.laze");
+    let bytes = encode_tree(&compiled.module_list, compiled.mem_size);
+    let ids: Vec<u8> = sections(&bytes).iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids, vec![1, 2, 3, 6, 7, 8, 10]);
+}
+
+fn laze_files(dir: &Path, files: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            laze_files(&path, files);
+        } else if path.to_string_lossy().ends_with(".laze") {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+}
+
+// Every fixture written with ja.peg either reports an error or gives a module that validates.
+#[test]
+fn fixtures_validate() {
+    let mut files = vec![];
+    laze_files(Path::new("./laze_tests"), &mut files);
+    files.sort();
+    let mut not_compiled = vec![];
+    for file in files.iter().filter(|file| !file.ends_with(".en.laze")) {
+        let compiled = compile(file);
+        if has_error(&compiled.diagnostics) {
+            not_compiled.push(file.trim_start_matches("./laze_tests/"));
+        } else {
+            validate(&encode_tree(&compiled.module_list, compiled.mem_size));
+        }
+    }
+    // these report errors, so they are not encoded
+    assert_eq!(
+        not_compiled,
+        vec![
+            "dec/class_dec.laze",
+            "dec/func_dec.laze",
+            "dec/jsexport_dec.laze",
+            "exp/all_operators_exp.laze",
+            "stm/if_stm/if_elseif_else.laze",
+            "stm/while_stm/while_break.laze",
+        ]
+    );
+}
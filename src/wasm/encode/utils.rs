@@ -0,0 +1,94 @@
+use crate::wasm::il::util::{WasmType, WasmTypeList};
+
+pub fn encode_u32(mut value: u32, bytes: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            break;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+pub fn encode_i64(mut value: i64, bytes: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let sign_bit_clear = byte & 0x40 == 0;
+        if (value == 0 && sign_bit_clear) || (value == -1 && !sign_bit_clear) {
+            bytes.push(byte);
+            break;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+pub fn encode_i32(value: i32, bytes: &mut Vec<u8>) {
+    encode_i64(value as i64, bytes);
+}
+
+pub fn encode_index(index: i32, bytes: &mut Vec<u8>) {
+    encode_u32(index as u32, bytes);
+}
+
+pub fn encode_name(name: &str, bytes: &mut Vec<u8>) {
+    encode_u32(name.len() as u32, bytes);
+    bytes.extend_from_slice(name.as_bytes());
+}
+
+pub fn encode_valtype(ty: &WasmType) -> Option<u8> {
+    match ty {
+        WasmType::I32 => Some(0x7f),
+        WasmType::I64 => Some(0x7e),
+        WasmType::F32 => Some(0x7d),
+        WasmType::F64 => Some(0x7c),
+        WasmType::None => None,
+    }
+}
+
+pub fn encode_functype(params: &WasmTypeList, result: &WasmType, bytes: &mut Vec<u8>) {
+    bytes.push(0x60);
+    encode_u32(params.len() as u32, bytes);
+    for param in params {
+        bytes.extend(encode_valtype(param));
+    }
+    match encode_valtype(result) {
+        Some(valtype) => {
+            bytes.push(1);
+            bytes.push(valtype);
+        }
+        None => bytes.push(0),
+    }
+}
+
+pub fn encode_locals(locals: &WasmTypeList, bytes: &mut Vec<u8>) {
+    // Consecutive locals of the same type are written as one entry.
+    let mut groups: Vec<(u32, u8)> = vec![];
+    for local in locals {
+        if let Some(valtype) = encode_valtype(local) {
+            match groups.last_mut() {
+                Some((count, ty)) if *ty == valtype => *count += 1,
+                _ => groups.push((1, valtype)),
+            }
+        }
+    }
+    encode_u32(groups.len() as u32, bytes);
+    for (count, valtype) in groups {
+        encode_u32(count, bytes);
+        bytes.push(valtype);
+    }
+}
+
+pub fn encode_section(id: u8, count: usize, content: Vec<u8>, bytes: &mut Vec<u8>) {
+    if count == 0 {
+        return;
+    }
+    let mut section = vec![];
+    encode_u32(count as u32, &mut section);
+    section.extend(content);
+    bytes.push(id);
+    encode_u32(section.len() as u32, bytes);
+    bytes.extend(section);
+}
@@ -1,6 +1,26 @@
-use std::io::{stderr, Write};
+use crate::{
+    ast::ty::Type,
+    wasm::{
+        il::{
+            exp::{Exp, Exp_},
+            util::WasmType,
+        },
+        semantic::laze_type::{LazeType, LazeType_},
+    },
+};
 
-use crate::{ast::ty::Type, wasm::semantic::laze_type::LazeType};
+// index of the global that holds the stack pointer
+pub const STACK_POINTER_INDEX: i32 = 0;
+// index of the global that holds the next free address of the heap
+pub const HEAP_POINTER_INDEX: i32 = 1;
+// index of the global that holds the address of the heads of the free lists
+pub const FREE_LISTS_INDEX: i32 = 2;
+// index of the first global of the program, which come after the ones above
+pub const FIRST_GLOBAL_INDEX: i32 = 3;
+// the free lists start this many bytes after the start of the stack
+pub const STACK_SIZE: i32 = 1 << 20;
+// one head for each block size from 2^0 to 2^31 bytes
+pub const FREE_LISTS_SIZE: i32 = 32 * 4;
 
 pub type Frame = Box<Frame_>;
 
@@ -10,10 +30,25 @@ pub struct Frame_ {
     pub locals: Vec<FrameAccess>,
     pub locals_type: Vec<LazeType>,
     pub params: Vec<FrameAccess>,
-    // memory offset of the frame
-    pub memory_offset: i32,
+    // local that holds the address of the frame on the stack
+    pub frame_pointer: i32,
+    // local that holds the closure record, only in functions lifted from function expressions
+    pub env_pointer: i32,
+    // local that holds the return value while the frame is popped
+    pub return_local: Option<i32>,
     // total memory size of the frame
     pub frame_size: i32,
+    // objects declared in each scope that is open, which are destructed when the scope ends
+    pub objects: Vec<Vec<(FrameAccess, LazeType)>>,
+    // loops that enclose the statement: the labels that 抜ける and 次へ branch to,
+    // and the number of the object scopes open outside the loop
+    pub loops: Vec<(i32, i32, usize)>,
+    // variables that & is applied to, which are kept in memory even when they are scalars
+    pub address_taken: Vec<String>,
+    // variables that are assigned to, which are searched only for the top-level variables
+    pub assigned: Vec<String>,
+    // function expressions whose closure record is kept in the frame, by their positions
+    pub frame_closures: Vec<(usize, usize)>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -31,28 +66,38 @@ impl Frame_ {
             locals: vec![],
             locals_type: vec![],
             params: vec![],
-            memory_offset: 0,
+            frame_pointer: -1,
+            env_pointer: -1,
+            return_local: None,
             frame_size: 0,
+            objects: vec![],
+            loops: vec![],
+            address_taken: vec![],
+            assigned: vec![],
+            frame_closures: vec![],
         })
     }
-    pub fn new(memory_offset: i32, frame_type: FrameType) -> Frame {
+    pub fn new(frame_type: FrameType) -> Frame {
         Box::new(Frame_ {
             data: frame_type,
             locals: vec![],
             locals_type: vec![],
             params: vec![],
-            memory_offset,
+            frame_pointer: -1,
+            env_pointer: -1,
+            return_local: None,
             frame_size: 0,
+            objects: vec![],
+            loops: vec![],
+            address_taken: vec![],
+            assigned: vec![],
+            frame_closures: vec![],
         })
     }
-    pub fn alloc_param(&mut self, ty: &LazeType) -> FrameAccess {
+    pub fn alloc_param(&mut self, name: &str, ty: &LazeType) -> FrameAccess {
         let new_access: FrameAccess;
-        if ty.escape {
-            new_access = FrameAccess::EscapedParam(
-                self.params.len() as i32,
-                self.memory_offset,
-                self.frame_size,
-            );
+        if ty.escape || self.is_address_taken(name) {
+            new_access = FrameAccess::EscapedParam(self.params.len() as i32, self.frame_size);
             self.params.push(new_access);
             self.frame_size += ty.size;
         } else {
@@ -61,10 +106,11 @@ impl Frame_ {
         }
         new_access
     }
+    // A global that is escaped is kept in memory, and the global holds its address.
     pub fn alloc(&mut self, ty: &LazeType) -> FrameAccess {
         let new_access: FrameAccess;
-        if ty.escape {
-            new_access = FrameAccess::InFrame(self.memory_offset, self.frame_size);
+        if ty.escape && self.data != FrameType::Global {
+            new_access = FrameAccess::InFrame(self.frame_size);
             self.frame_size += ty.size;
         } else {
             match self.data {
@@ -82,41 +128,141 @@ impl Frame_ {
                 }
                 FrameType::Global => {
                     new_access =
-                        FrameAccess::InGlobal((self.locals.len() + self.params.len()) as i32);
+                        FrameAccess::InGlobal(FIRST_GLOBAL_INDEX + self.locals.len() as i32);
+                    self.locals_type.push(ty.clone());
                     self.locals.push(new_access);
                 }
+                // the ones that access the variable report that it does not exist
                 FrameType::None => {
                     new_access = FrameAccess::None;
-                    let _ = writeln!(stderr(), "Frame does not exist.");
                 }
             }
         }
         new_access
     }
+    // Allocates a variable declared with the name, which is put in the frame if its address is taken.
+    pub fn alloc_var(&mut self, name: &str, ty: &LazeType) -> FrameAccess {
+        if self.data != FrameType::Global && self.is_address_taken(name) {
+            self.alloc_inframe(ty)
+        } else {
+            self.alloc(ty)
+        }
+    }
+    pub fn is_address_taken(&self, name: &str) -> bool {
+        self.address_taken.iter().any(|taken| taken == name)
+    }
+    pub fn is_assigned(&self, name: &str) -> bool {
+        self.assigned.iter().any(|assigned| assigned == name)
+    }
     pub fn alloc_inframe(&mut self, ty: &LazeType) -> FrameAccess {
-        let new_access = FrameAccess::InFrame(self.memory_offset, self.frame_size);
+        let new_access = FrameAccess::InFrame(self.frame_size);
         self.frame_size += ty.size;
         new_access
     }
+    pub fn is_frame_closure(&self, pos: (usize, usize)) -> bool {
+        self.frame_closures.contains(&pos)
+    }
+    // A closure record is aligned to 8 bytes like the records on the heap.
+    pub fn alloc_record(&mut self, size: i32) -> FrameAccess {
+        let offset = (self.frame_size + 7) & !7;
+        self.frame_size = offset + size;
+        FrameAccess::InFrame(offset)
+    }
+    // Must be called after all the parameters are allocated.
+    pub fn alloc_frame_pointer(&mut self) -> i32 {
+        self.frame_pointer = self.alloc_local(LazeType_::pointer_type(LazeType_::void_type()));
+        self.frame_pointer
+    }
+    pub fn alloc_return_local(&mut self, ty: &LazeType) -> i32 {
+        let index = self.alloc_local(ty.clone());
+        self.return_local = Some(index);
+        index
+    }
+    pub fn alloc_local(&mut self, ty: LazeType) -> i32 {
+        let index = (self.locals.len() + self.params.len()) as i32;
+        self.locals.push(FrameAccess::InLocal(index));
+        self.locals_type.push(ty);
+        index
+    }
+    // The frame is aligned to 8 bytes so that every frame starts at an aligned address.
+    pub fn aligned_size(&self) -> i32 {
+        (self.frame_size + 7) & !7
+    }
+    pub fn address_exp(&self, frame_offset: i32) -> Exp {
+        Exp_::add_addr_exp(
+            Exp_::getlocal_exp(WasmType::I32, self.frame_pointer),
+            Exp_::consti32_exp(frame_offset),
+        )
+    }
+    pub fn env_address_exp(&self, env_offset: i32) -> Exp {
+        Exp_::add_addr_exp(
+            Exp_::getlocal_exp(WasmType::I32, self.env_pointer),
+            Exp_::consti32_exp(env_offset),
+        )
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FrameAccess {
-    EscapedParam(i32, i32, i32),
-    InFrame(i32, i32),
+    // param_index: i32, frame_offset: i32
+    EscapedParam(i32, i32),
+    // frame_offset: i32
+    InFrame(i32),
     InLocal(i32),
     InGlobal(i32),
+    // env_offset: i32
+    InEnv(i32),
+    // address: i32
+    // a top-level variable whose address is taken is kept in the static data
+    InStatic(i32),
     None,
 }
 
 impl FrameAccess {
-    pub fn get_address(&self) -> i32 {
+    pub fn get_frame_offset(&self) -> i32 {
         match self {
-            Self::EscapedParam(_, memory_offset, frame_offset) => memory_offset + frame_offset,
-            Self::InFrame(memory_offset, frame_offset) => memory_offset + frame_offset,
+            Self::EscapedParam(_, frame_offset) => *frame_offset,
+            Self::InFrame(frame_offset) => *frame_offset,
             _ => {
                 panic!("The variable is not in frame.");
             }
         }
     }
 }
+
+// Variables of the enclosing functions that a function expression uses.
+// They are copied into the closure record when the closure is made,
+// right after the index of the lifted function in the table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClosureEnv {
+    // length of the venv when the function expression started
+    pub venv_boundary: usize,
+    // name: String, var_type: LazeType, outer_access: FrameAccess, env_offset: i32
+    pub captures: Vec<(String, LazeType, FrameAccess, i32)>,
+    // total memory size of the closure record
+    pub size: i32,
+}
+
+impl ClosureEnv {
+    pub fn new(venv_boundary: usize) -> Self {
+        ClosureEnv {
+            venv_boundary,
+            captures: vec![],
+            // the table index takes the first 8 bytes to keep the captures aligned
+            size: 8,
+        }
+    }
+    pub fn get_capture(&self, name: &str) -> Option<i32> {
+        self.captures
+            .iter()
+            .find(|capture| capture.0 == *name)
+            .map(|capture| capture.3)
+    }
+    pub fn alloc_capture(&mut self, name: &str, ty: &LazeType, outer_access: FrameAccess) -> i32 {
+        let env_offset = self.size;
+        self.captures
+            .push((name.to_string(), ty.clone(), outer_access, env_offset));
+        self.size += (ty.size + 7) & !7;
+        env_offset
+    }
+}
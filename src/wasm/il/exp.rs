@@ -1,4 +1,7 @@
-use super::util::{BinOper, UniOper, WasmType};
+use super::{
+    stm::StmList,
+    util::{BinOper, UniOper, WasmType},
+};
 
 pub type ExpList = Vec<Exp>;
 pub type Exp = Box<Exp_>;
@@ -15,12 +18,19 @@ pub enum ExpData {
     UnaryOp(UniOper, Exp),
     Const(ConstData),
     GetLocal(i32),
+    TeeLocal(i32, Exp),
     GetGlobal(i32),
     CallExp(i32, Option<String>, ExpList),
     CallIndirect(Exp, ExpList, i32),
     IfExp(Exp, Exp, Exp),
+    // runs the statements and gives the value of the expression after them
+    Block(StmList, Exp),
     Load(Exp),
     Convert(Exp),
+    // the number of pages of the memory
+    MemorySize,
+    // grows the memory by the pages, the value is the old number of pages or -1
+    MemoryGrow(Exp),
     None,
 }
 
@@ -35,7 +45,7 @@ impl Exp_ {
         Self::binop_exp(WasmType::I32, BinOper::Add, left, right)
     }
     pub fn mul_addr_exp(left: Exp, right: Exp) -> Exp {
-        Self::binop_exp(WasmType::I32, BinOper::Add, left, right)
+        Self::binop_exp(WasmType::I32, BinOper::Mul, left, right)
     }
     pub fn binop_exp(ty: WasmType, oper: BinOper, left: Exp, right: Exp) -> Exp {
         Box::new(Exp_ {
@@ -79,6 +89,12 @@ impl Exp_ {
             data: ExpData::GetLocal(index),
         })
     }
+    pub fn teelocal_exp(ty: WasmType, index: i32, exp: Exp) -> Exp {
+        Box::new(Exp_ {
+            ty,
+            data: ExpData::TeeLocal(index, exp),
+        })
+    }
     pub fn getglobal_exp(ty: WasmType, index: i32) -> Exp {
         Box::new(Exp_ {
             ty,
@@ -103,6 +119,12 @@ impl Exp_ {
             data: ExpData::IfExp(test, if_body, else_body),
         })
     }
+    pub fn block_exp(ty: WasmType, stmlist: StmList, exp: Exp) -> Exp {
+        Box::new(Exp_ {
+            ty,
+            data: ExpData::Block(stmlist, exp),
+        })
+    }
     pub fn load_exp(ty: WasmType, addr: Exp) -> Exp {
         Box::new(Exp_ {
             ty,
@@ -115,6 +137,18 @@ impl Exp_ {
             data: ExpData::Convert(exp),
         })
     }
+    pub fn memory_size_exp() -> Exp {
+        Box::new(Exp_ {
+            ty: WasmType::I32,
+            data: ExpData::MemorySize,
+        })
+    }
+    pub fn memory_grow_exp(pages: Exp) -> Exp {
+        Box::new(Exp_ {
+            ty: WasmType::I32,
+            data: ExpData::MemoryGrow(pages),
+        })
+    }
 }
 
 #[derive(Debug)]
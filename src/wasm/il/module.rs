@@ -19,12 +19,15 @@ pub enum Module_ {
     ),
     JsImport(String, String, Module),
     JsExport(String, i32),
-    Global(WasmType, Exp),
+    // the type, whether it is mutable, and the initial value
+    Global(WasmType, bool, Exp),
     Data(String, Exp),
     Elem(Exp, ModuleList),
     Table(i32),
     Type(WasmTypeList, WasmType),
     Memory(i32),
+    // the function called when the module is instantiated
+    Start(i32),
     None,
 }
 
@@ -56,7 +59,10 @@ impl Module_ {
         Box::new(Module_::JsExport(name, index))
     }
     pub fn global_mod(ty: WasmType, exp: Exp) -> Module {
-        Box::new(Module_::Global(ty, exp))
+        Box::new(Module_::Global(ty, true, exp))
+    }
+    pub fn const_global_mod(ty: WasmType, exp: Exp) -> Module {
+        Box::new(Module_::Global(ty, false, exp))
     }
     pub fn data_mod(data: String, exp: Exp) -> Module {
         Box::new(Module_::Data(data, exp))
@@ -73,4 +79,7 @@ impl Module_ {
     pub fn mem_mod(page_size: i32) -> Module {
         Box::new(Module_::Memory(page_size))
     }
+    pub fn start_mod(index: i32) -> Module {
+        Box::new(Module_::Start(index))
+    }
 }
@@ -1,4 +1,4 @@
-use std::io::{stderr, Write};
+use crate::util::diagnostic::{Diagnostic, DiagnosticCode, DiagnosticList};
 
 use super::exp::{Exp, ExpList};
 
@@ -9,15 +9,24 @@ pub type Stm = Box<Stm_>;
 pub enum Stm_ {
     If(Exp, Stm, Stm),
     Block(StmList),
-    Loop(Exp, Stm, i32, bool),
+    // a block that Break with the label leaves
+    LabeledBlock(i32, StmList),
+    // Break with the label goes back to the start of the loop
+    Loop(i32, StmList),
     SetLocal(i32, Exp),
     SetGlobal(i32, Exp),
     Store(Exp, Exp),
+    // the label of the block or the loop, not the depth of it
     Break(i32),
+    BreakIf(i32, Exp),
     Call(i32, ExpList, Option<String>),
     CallIndirect(Exp, ExpList, i32),
     Return(Exp),
     Copy(Exp, Exp, Exp),
+    // evaluates the expression for its side effects and drops the value
+    Drop(Exp),
+    // traps when it is run
+    Unreachable,
     None,
 }
 
@@ -28,25 +37,33 @@ impl Stm_ {
     pub fn if_stm(test: Exp, if_body: Stm, else_body: Stm) -> Stm {
         Box::new(Stm_::If(test, if_body, else_body))
     }
-    pub fn set_if_else_body(&mut self, new_else_body: Stm, stm_pos: (usize, usize)) {
+    pub fn set_if_else_body(
+        &mut self,
+        new_else_body: Stm,
+        stm_pos: (usize, usize),
+        diagnostics: &mut DiagnosticList,
+    ) {
         match self {
             Stm_::If(_, _, else_body) => {
                 *else_body = new_else_body;
             }
             _ => {
-                let _ = writeln!(
-                    stderr(),
-                    "This statement is not an if statement: {:?}",
-                    stm_pos
-                );
+                diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::UnexpectedNode,
+                    stm_pos,
+                    "This statement is not an if statement.".to_string(),
+                ));
             }
         }
     }
     pub fn block_stm(body: StmList) -> Stm {
         Box::new(Stm_::Block(body))
     }
-    pub fn loop_stm(test: Exp, body: Stm, index: i32, is_for: bool) -> Stm {
-        Box::new(Stm_::Loop(test, body, index, is_for))
+    pub fn labeled_block_stm(label: i32, body: StmList) -> Stm {
+        Box::new(Stm_::LabeledBlock(label, body))
+    }
+    pub fn loop_stm(label: i32, body: StmList) -> Stm {
+        Box::new(Stm_::Loop(label, body))
     }
     pub fn setlocal_stm(index: i32, exp: Exp) -> Stm {
         Box::new(Stm_::SetLocal(index, exp))
@@ -57,8 +74,11 @@ impl Stm_ {
     pub fn store_stm(addr: Exp, exp: Exp) -> Stm {
         Box::new(Stm_::Store(addr, exp))
     }
-    pub fn break_stm(index: i32) -> Stm {
-        Box::new(Stm_::Break(index))
+    pub fn break_stm(label: i32) -> Stm {
+        Box::new(Stm_::Break(label))
+    }
+    pub fn break_if_stm(label: i32, test: Exp) -> Stm {
+        Box::new(Stm_::BreakIf(label, test))
     }
     pub fn call_stm(index: i32, args: ExpList, label: Option<String>) -> Stm {
         Box::new(Stm_::Call(index, args, label))
@@ -72,4 +92,10 @@ impl Stm_ {
     pub fn copy_stm(dest: Exp, src: Exp, size: Exp) -> Stm {
         Box::new(Stm_::Copy(dest, src, size))
     }
+    pub fn unreachable_stm() -> Stm {
+        Box::new(Stm_::Unreachable)
+    }
+    pub fn drop_stm(exp: Exp) -> Stm {
+        Box::new(Stm_::Drop(exp))
+    }
 }
@@ -1,7 +1,6 @@
-use std::io::{stderr, Write};
-
 use crate::{
     ast::op::Oper,
+    util::diagnostic::{Diagnostic, DiagnosticCode, DiagnosticList},
     wasm::semantic::laze_type::{LazeType, LazeType_},
 };
 
@@ -19,11 +18,32 @@ pub struct WasmExpTy {
 }
 
 impl WasmExpTy {
-    pub fn ty_exp(self, message: String) -> (LazeType, Exp) {
+    // The data of a translation that failed is none, and the failure has already been reported.
+    // Data of another kind than the caller expects is a bug of the translation.
+    fn report_unexpected(
+        data: &WasmData,
+        diagnostics: &mut DiagnosticList,
+        pos: (usize, usize),
+        message: &str,
+    ) {
+        if !matches!(data, WasmData::None) {
+            diagnostics.push(Diagnostic::error(
+                DiagnosticCode::UnexpectedNode,
+                pos,
+                message.to_string(),
+            ));
+        }
+    }
+    pub fn ty_exp(
+        self,
+        diagnostics: &mut DiagnosticList,
+        pos: (usize, usize),
+        message: &str,
+    ) -> (LazeType, Exp) {
         match self.data {
             WasmData::Exp(exp) => (self.ty, exp),
-            _ => {
-                let _ = writeln!(stderr(), "{message}");
+            data => {
+                Self::report_unexpected(&data, diagnostics, pos, message);
                 (LazeType_::none_type(), Exp_::none_exp())
             }
         }
@@ -34,56 +54,76 @@ impl WasmExpTy {
             data: WasmData::None,
         }
     }
-    pub fn stm(self, message: String) -> Stm {
+    pub fn stm(self, diagnostics: &mut DiagnosticList, pos: (usize, usize), message: &str) -> Stm {
         match self.data {
             WasmData::Stm(stm) => stm,
-            _ => {
-                let _ = writeln!(stderr(), "{message}");
+            data => {
+                Self::report_unexpected(&data, diagnostics, pos, message);
                 Stm_::none_stm()
             }
         }
     }
-    pub fn stmlist(self, message: String) -> StmList {
+    pub fn stmlist(
+        self,
+        diagnostics: &mut DiagnosticList,
+        pos: (usize, usize),
+        message: &str,
+    ) -> StmList {
         match self.data {
             WasmData::StmList(stmlist) => stmlist,
-            _ => {
-                let _ = writeln!(stderr(), "{message}");
+            data => {
+                Self::report_unexpected(&data, diagnostics, pos, message);
                 vec![]
             }
         }
     }
-    pub fn exp(self, message: String) -> Exp {
+    pub fn exp(self, diagnostics: &mut DiagnosticList, pos: (usize, usize), message: &str) -> Exp {
         match self.data {
             WasmData::Exp(exp) => exp,
-            _ => {
-                let _ = writeln!(stderr(), "{message}");
+            data => {
+                Self::report_unexpected(&data, diagnostics, pos, message);
                 Exp_::none_exp()
             }
         }
     }
-    pub fn explist(self, message: String) -> ExpList {
+    pub fn explist(
+        self,
+        diagnostics: &mut DiagnosticList,
+        pos: (usize, usize),
+        message: &str,
+    ) -> ExpList {
         match self.data {
             WasmData::ExpList(explist) => explist,
-            _ => {
-                let _ = writeln!(stderr(), "{message}");
+            data => {
+                Self::report_unexpected(&data, diagnostics, pos, message);
                 vec![]
             }
         }
     }
-    pub fn module(self, message: String) -> Module {
+    pub fn module(
+        self,
+        diagnostics: &mut DiagnosticList,
+        pos: (usize, usize),
+        message: &str,
+    ) -> Module {
         match self.data {
             WasmData::Module(module) => module,
-            _ => {
-                let _ = writeln!(stderr(), "{message}");
+            data => {
+                Self::report_unexpected(&data, diagnostics, pos, message);
                 Module_::none_mod()
             }
         }
     }
-    pub fn modulelist(self, message: String) -> ModuleList {
+    pub fn modulelist(
+        self,
+        diagnostics: &mut DiagnosticList,
+        pos: (usize, usize),
+        message: &str,
+    ) -> ModuleList {
         match self.data {
             WasmData::ModuleList(modulelist) => modulelist,
-            _ => {
-                let _ = writeln!(stderr(), "{message}");
+            data => {
+                Self::report_unexpected(&data, diagnostics, pos, message);
                 vec![]
             }
         }
@@ -136,7 +176,7 @@ pub enum WasmData {
     None,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum WasmType {
     I32,
     I64,
@@ -178,6 +218,7 @@ pub enum BinOper {
     GeUnsigned,
     And,
     Or,
+    Shl,
 
     None,
 }
@@ -224,6 +265,7 @@ impl BinOper {
             Self::GeUnsigned => "ge_u".to_string(),
             Self::And => "and".to_string(),
             Self::Or => "or".to_string(),
+            Self::Shl => "shl".to_string(),
             Self::None => "".to_string(),
         }
     }
@@ -259,6 +301,8 @@ pub enum UniOper {
     Trunc,
     Nearest,
     Sqrt,
+    // count leading zeros, only for integers
+    Clz,
 }
 
 impl UniOper {
@@ -271,6 +315,7 @@ impl UniOper {
             Self::Trunc => "trunc".to_string(),
             Self::Nearest => "nearest".to_string(),
             Self::Sqrt => "sqrt".to_string(),
+            Self::Clz => "clz".to_string(),
         }
     }
 }
@@ -1,4 +1,7 @@
+pub mod encode;
 pub mod frame;
 pub mod il;
 pub mod print_tree;
 pub mod semantic;
+#[cfg(test)]
+pub mod tests;
@@ -3,6 +3,8 @@ use crate::wasm::il::{
     util::WasmType,
 };
 
+use super::print_stm::print_stmlist;
+
 pub fn print_exp(exp: &Exp) -> String {
     match &exp.data {
         ExpData::BinOp(oper, lhs, rhs) => format_args!(
@@ -83,6 +85,9 @@ pub fn print_exp(exp: &Exp) -> String {
         },
         ExpData::GetGlobal(index) => format_args!("(get_global {})", index).to_string(),
         ExpData::GetLocal(index) => format_args!("(local.get {})", index).to_string(),
+        ExpData::TeeLocal(index, value) => {
+            format_args!("(local.tee {} {})", index, print_exp(value)).to_string()
+        }
         ExpData::IfExp(test_exp, then_exp, else_exp) => format_args!(
             "(if({}) {} (then {}) (else {}))",
             exp.ty.to_string(),
@@ -91,9 +96,20 @@ pub fn print_exp(exp: &Exp) -> String {
             print_exp(else_exp)
         )
         .to_string(),
+        ExpData::Block(stmlist, value) => format_args!(
+            "(block (result {}) {} {})",
+            exp.ty.to_string(),
+            print_stmlist(stmlist),
+            print_exp(value)
+        )
+        .to_string(),
         ExpData::Load(addr) => {
             format_args!("({}.load {})", exp.ty.to_string(), print_exp(addr)).to_string()
         }
+        ExpData::MemorySize => "(memory.size)".to_string(),
+        ExpData::MemoryGrow(pages) => {
+            format_args!("(memory.grow {})", print_exp(pages)).to_string()
+        }
         ExpData::None => "".to_string(),
         ExpData::UnaryOp(oper, op_exp) => format_args!(
             "({}.{} {})",
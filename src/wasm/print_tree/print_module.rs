@@ -1,13 +1,12 @@
-use std::{
-    fs::File,
-    io::{stderr, Write},
-    path::Path,
-};
+use std::path::Path;
 
-use crate::wasm::il::{
-    module::{Module, ModuleList, Module_},
-    stm::Stm_,
-    util::WasmType,
+use crate::{
+    util::file_opener::write_output,
+    wasm::il::{
+        module::{Module, ModuleList, Module_},
+        stm::Stm_,
+        util::WasmType,
+    },
 };
 
 use super::{
@@ -25,7 +24,7 @@ pub fn print_module(module: &Module) -> String {
             let mut result = format_args!("(elem {}", print_exp(&offset)).to_string();
             for module in modlist {
                 if let Module_::Func(index, _, _, _, _, _) = **module {
-                    result += &index.to_string();
+                    result += &format_args!(" {}", index).to_string();
                 }
             }
             result += ")";
@@ -37,21 +36,31 @@ pub fn print_module(module: &Module) -> String {
                 result += &format_args!("(export \"{}\")", name).to_string();
             }
             result += &print_params(params).to_string();
-            result += &print_locals(locals).to_string();
             if let WasmType::None = return_type {
             } else {
                 result += &format_args!("(result {})", return_type.to_string()).to_string();
             }
-            result += &print_stm(body).to_string();
+            result += &print_locals(locals).to_string();
+            // the body of a function is already a block
+            if let Stm_::Block(stmlist) = &**body {
+                for stm in stmlist {
+                    result += &print_stm(stm).to_string();
+                }
+            } else {
+                result += &print_stm(body).to_string();
+            }
             result += ")";
             result
         }
-        Module_::Global(var_ty, init_val) => format_args!(
-            "(global (mut {}) {}",
+        Module_::Global(var_ty, true, init_val) => format_args!(
+            "(global (mut {}) {})",
             var_ty.to_string(),
             print_exp(&init_val)
         )
         .to_string(),
+        Module_::Global(var_ty, false, init_val) => {
+            format_args!("(global {} {})", var_ty.to_string(), print_exp(init_val)).to_string()
+        }
         Module_::JsExport(export_name, func_index) => {
             format_args!("(export \"{}\" (func {}))", export_name, func_index).to_string()
         }
@@ -64,7 +73,8 @@ pub fn print_module(module: &Module) -> String {
         .to_string(),
         Module_::Memory(page_size) => format_args!("(memory {})", page_size).to_string(),
         Module_::None => "".to_string(),
-        Module_::Table(table_size) => format_args!("(table {} anyfunc)", table_size).to_string(),
+        Module_::Start(func_index) => format_args!("(start {})", func_index).to_string(),
+        Module_::Table(table_size) => format_args!("(table {} funcref)", table_size).to_string(),
         Module_::Type(params, return_type) => format_args!(
             "(type {})",
             print_modprototype(&Module_::func_mod(
@@ -118,27 +128,10 @@ pub fn fwrite_tree(
     file_path: &Path,
     dist_path: Option<&Path>,
 ) {
-    let file_name_only = file_path.file_name();
-    if let Some(file_name) = file_name_only {
-        let dist_file = if let Some(dist) = dist_path {
-            format_args!("{}", dist.to_str().unwrap(),).to_string()
-        } else {
-            format_args!("dist/{}.wat", file_name.to_str().unwrap()).to_string()
-        };
-        let file_to_write = File::create(&dist_file);
-        if let Ok(mut file) = file_to_write {
-            let result = file.write_all(print_tree(modulelist, mem_size).as_bytes());
-            if let Err(error) = result {
-                println!("Error while writing to file {}: {}", dist_file, error);
-            }
-        } else if let Err(error) = file_to_write {
-            println!("Could not open file {}: {}", dist_file, error);
-        }
-    } else {
-        let _ = writeln!(
-            stderr(),
-            "Could not get file name of {}",
-            file_path.to_str().unwrap()
-        );
-    }
+    write_output(
+        file_path,
+        dist_path,
+        "wat",
+        print_tree(modulelist, mem_size).as_bytes(),
+    );
 }
@@ -5,7 +5,16 @@ use super::print_exp::{print_exp, print_explist};
 pub fn print_stm(stm: &Stm) -> String {
     match &**stm {
         Stm_::Block(stmlist) => format_args!("(block {})", print_stmlist(stmlist)).to_string(),
-        Stm_::Break(index) => format_args!("(br {})", index).to_string(),
+        Stm_::LabeledBlock(label, stmlist) => {
+            format_args!("(block $L{} {})", label, print_stmlist(stmlist)).to_string()
+        }
+        Stm_::Loop(label, stmlist) => {
+            format_args!("(loop $L{} {})", label, print_stmlist(stmlist)).to_string()
+        }
+        Stm_::Break(label) => format_args!("(br $L{})", label).to_string(),
+        Stm_::BreakIf(label, test_exp) => {
+            format_args!("(br_if $L{} {})", label, print_exp(test_exp)).to_string()
+        }
         Stm_::Call(index, args, _label) => {
             format_args!("(call {} {})", index, print_explist(args)).to_string()
         }
@@ -23,6 +32,7 @@ pub fn print_stm(stm: &Stm) -> String {
             print_exp(size)
         )
         .to_string(),
+        Stm_::Drop(exp) => format_args!("(drop {})", print_exp(exp)).to_string(),
         Stm_::If(test_exp, then_stm, else_stm) => {
             let mut result = "".to_string();
             result += &format_args!("(if {} (then {})", print_exp(test_exp), print_stm(then_stm),)
@@ -34,21 +44,8 @@ pub fn print_stm(stm: &Stm) -> String {
             }
             result
         }
-        Stm_::Loop(test_exp, loop_stm, loop_index, is_for) => {
-            let mut result = "(loop ".to_string();
-            if *is_for {
-                result +=
-                    &format_args!("(br_if {} {})", loop_index, print_exp(test_exp),).to_string();
-                result += &print_stm(loop_stm);
-            } else {
-                result += &print_stm(loop_stm);
-                result +=
-                    &format_args!("(br_if {} {})", loop_index, print_exp(test_exp),).to_string();
-            }
-            result += &format_args!("(br {}))", loop_index - 1).to_string();
-            result
-        }
         Stm_::None => "".to_string(),
+        Stm_::Unreachable => "(unreachable)".to_string(),
         Stm_::Return(return_exp) => format_args!("(return {})", print_exp(return_exp)).to_string(),
         Stm_::SetGlobal(index, exp) => {
             format_args!("(global.set {} {})", index, print_exp(exp)).to_string()
@@ -0,0 +1,222 @@
+use crate::ast::{
+    dec::{Dec, DecData, DecList},
+    exp::{ASTExp, ASTExpData},
+    field::{FieldData, FieldList},
+    ifelse::IfElseData,
+    op::Oper,
+    stm::{Stm, StmData, StmList},
+    suffix::{ASTExpSuffixList, SuffixData},
+    var::{Var, VarData},
+};
+
+// Names of the variables that & is applied to.
+// A scalar is kept in a wasm local or global, which has no address,
+// so a variable whose address is taken is kept in memory instead.
+// The names are collected before the body is translated, since & can come after the declaration.
+pub fn address_taken_in_stmlist(stmlist: &StmList) -> Vec<String> {
+    let mut names = Names::default();
+    for stm in stmlist {
+        walk_stm(stm, &mut names);
+    }
+    names.address_taken
+}
+
+// Top-level variables can be used in every function, so all the declarations are searched.
+pub fn address_taken_in_declist(declist: &DecList) -> Vec<String> {
+    walk_declist(declist).address_taken
+}
+
+// Names of the variables that are assigned to.
+// A top-level variable that is never assigned to and has a constant initial value
+// is an immutable wasm global.
+pub fn assigned_in_declist(declist: &DecList) -> Vec<String> {
+    walk_declist(declist).assigned
+}
+
+// Whether the allocator is needed, which is when メモリ確保 or メモリ解放 is used,
+// or when an object is made with 新規 or a function expression is made.
+// A function expression whose record is kept in the frame does not allocate,
+// but that is only known while its function is translated.
+pub fn uses_heap_in_declist(declist: &DecList) -> bool {
+    walk_declist(declist).uses_heap
+}
+
+#[derive(Default)]
+struct Names {
+    address_taken: Vec<String>,
+    assigned: Vec<String>,
+    uses_heap: bool,
+}
+
+fn walk_declist(declist: &DecList) -> Names {
+    let mut names = Names::default();
+    for dec in declist {
+        walk_dec(dec, &mut names);
+    }
+    names
+}
+
+fn walk_dec(dec: &Dec, names: &mut Names) {
+    match &dec.data {
+        DecData::Func(_, params, result, body) | DecData::Oper(_, params, result, body) => {
+            walk_fieldlist(params, names);
+            walk_fieldlist(result, names);
+            for stm in body {
+                walk_stm(stm, names);
+            }
+        }
+        DecData::Var(var, _, init) => {
+            walk_var(var, names);
+            walk_exp(init, names);
+        }
+        DecData::Class(_, members, _) => {
+            for member in members {
+                walk_dec(&member.dec, names);
+            }
+        }
+        DecData::Template(dec, _, _) => walk_dec(dec, names),
+        // imports, exports and constraints have no body
+        _ => {}
+    }
+}
+
+fn walk_fieldlist(fieldlist: &FieldList, names: &mut Names) {
+    for field in fieldlist {
+        if let FieldData::Field(var, _) = &field.data {
+            walk_var(var, names);
+        }
+    }
+}
+
+fn walk_stm(stm: &Stm, names: &mut Names) {
+    match &stm.data {
+        StmData::Compound(stmlist) => {
+            for stm in stmlist {
+                walk_stm(stm, names);
+            }
+        }
+        StmData::Assign(var, exp, _) => {
+            if let VarData::Simple(name) = &var.data {
+                if !names.assigned.contains(name) {
+                    names.assigned.push(name.clone());
+                }
+            }
+            walk_var(var, names);
+            walk_exp(exp, names);
+        }
+        StmData::Dec(dec) => walk_dec(dec, names),
+        StmData::Exp(exp) | StmData::Return(exp) => walk_exp(exp, names),
+        StmData::IfElse(ifelselist) => {
+            for ifelse in ifelselist {
+                match &ifelse.data {
+                    IfElseData::If(test, body) | IfElseData::ElseIf(test, body) => {
+                        walk_exp(test, names);
+                        walk_stm(body, names);
+                    }
+                    IfElseData::Else(body) => walk_stm(body, names),
+                }
+            }
+        }
+        StmData::While(test, body) | StmData::Until(test, body) | StmData::Repeat(test, body) => {
+            walk_exp(test, names);
+            walk_stm(body, names);
+        }
+        StmData::For(init, test, update, body) => {
+            walk_stm(init, names);
+            walk_exp(test, names);
+            walk_stm(update, names);
+            walk_stm(body, names);
+        }
+        StmData::Call(func, args) => {
+            walk_exp(func, names);
+            for arg in args {
+                walk_exp(arg, names);
+            }
+        }
+        StmData::Loop(body) => walk_stm(body, names),
+        StmData::Continue | StmData::Break | StmData::None => {}
+    }
+}
+
+fn walk_exp(exp: &ASTExp, names: &mut Names) {
+    match &exp.data {
+        ASTExpData::UnaryOp(oper_list, operand) => {
+            if let ASTExpData::Var(var) = &operand.data {
+                if let VarData::Simple(name) = &var.data {
+                    if oper_list.contains(&Oper::Address) && !names.address_taken.contains(name) {
+                        names.address_taken.push(name.clone());
+                    }
+                }
+            }
+            walk_exp(operand, names);
+        }
+        ASTExpData::Var(var) => walk_var(var, names),
+        ASTExpData::Call(func, args) => {
+            walk_exp(func, names);
+            for arg in args {
+                walk_exp(arg, names);
+            }
+        }
+        ASTExpData::New(_, explist) => {
+            names.uses_heap = true;
+            for exp in explist {
+                walk_exp(exp, names);
+            }
+        }
+        ASTExpData::BinOp(_, explist) | ASTExpData::Array(explist) => {
+            for exp in explist {
+                walk_exp(exp, names);
+            }
+        }
+        ASTExpData::Func(params, result, body) => {
+            names.uses_heap = true;
+            walk_fieldlist(params, names);
+            walk_fieldlist(result, names);
+            walk_stm(body, names);
+        }
+        ASTExpData::Field(exp, _) | ASTExpData::SizeOf(exp) | ASTExpData::Paren(exp) => {
+            walk_exp(exp, names)
+        }
+        ASTExpData::Suffix(exp, suffixlist) => {
+            walk_exp(exp, names);
+            walk_suffixlist(suffixlist, names);
+        }
+        ASTExpData::Int(_)
+        | ASTExpData::Short(_)
+        | ASTExpData::Real(_)
+        | ASTExpData::Char(_)
+        | ASTExpData::String(_)
+        | ASTExpData::Bool(_)
+        | ASTExpData::None => {}
+    }
+}
+
+fn walk_var(var: &Var, names: &mut Names) {
+    match &var.data {
+        VarData::SuffixVar(var, suffixlist) => {
+            walk_var(var, names);
+            walk_suffixlist(suffixlist, names);
+        }
+        VarData::Pointer(var) => walk_var(var, names),
+        VarData::Simple(name) => {
+            if name == "メモリ確保" || name == "メモリ解放" {
+                names.uses_heap = true;
+            }
+        }
+        VarData::Template(..) | VarData::None => {}
+    }
+}
+
+fn walk_suffixlist(suffixlist: &ASTExpSuffixList, names: &mut Names) {
+    for suffix in suffixlist {
+        match &suffix.data {
+            SuffixData::Call(explist) => {
+                for exp in explist {
+                    walk_exp(exp, names);
+                }
+            }
+            SuffixData::Subscript(exp) => walk_exp(exp, names),
+            SuffixData::Dot(_) | SuffixData::Arrow(_) => {}
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::ast::{
+    dec::{Dec, DecData},
+    exp::{ASTExp, ASTExpData, ASTExpList},
+    ifelse::IfElseData,
+    stm::{Stm, StmData, StmList},
+    suffix::{ASTExpSuffixList, SuffixData},
+    var::{Var, VarData},
+};
+
+// Positions of the function expressions whose closure record is kept in the frame of the function
+// that makes it, which is popped when the function returns.
+// The record of the other function expressions is allocated with メモリ確保 and is never freed,
+// because the closure can be copied anywhere and outlive the function.
+// A record is kept in the frame when the closure cannot be used after the function returns,
+// which is when it initializes a local variable that is only called.
+// A closure passed as an argument escapes, since the callee can keep it, for example in a global.
+// The variables are searched by their names before the body is translated, like address_taken.
+pub fn frame_closures_in_stmlist(stmlist: &StmList) -> Vec<(usize, usize)> {
+    let mut walker = Walker::default();
+    for stm in stmlist {
+        walker.stm(stm);
+    }
+    walker
+        .candidates
+        .into_iter()
+        .filter(|(_, name)| !walker.used_as_value.contains(name))
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+#[derive(Default)]
+struct Walker {
+    // the function expressions and the variables that they initialize
+    candidates: Vec<((usize, usize), String)>,
+    // variables whose value can be copied, so that a closure in them can outlive the function
+    used_as_value: Vec<String>,
+    // the number of function expressions that enclose the code being searched
+    nested: usize,
+}
+
+impl Walker {
+    fn candidate(&mut self, exp: &ASTExp, name: &str) -> bool {
+        if let ASTExpData::Func(_, _, body) = &exp.data {
+            if self.nested == 0 {
+                self.candidates.push((exp.pos, name.to_string()));
+            }
+            self.func_body(body);
+            return true;
+        }
+        false
+    }
+
+    // The variables used in a function expression are captured by copying them into its record,
+    // which can outlive the function.
+    fn func_body(&mut self, body: &Stm) {
+        self.nested += 1;
+        self.stm(body);
+        self.nested -= 1;
+    }
+
+    fn value(&mut self, name: &String) {
+        if !self.used_as_value.contains(name) {
+            self.used_as_value.push(name.clone());
+        }
+    }
+
+    fn stm(&mut self, stm: &Stm) {
+        match &stm.data {
+            StmData::Compound(stmlist) => {
+                for stm in stmlist {
+                    self.stm(stm);
+                }
+            }
+            StmData::Assign(var, exp, _) => {
+                self.assigned_var(var);
+                self.exp(exp);
+            }
+            StmData::Dec(dec) => self.dec(dec),
+            StmData::Exp(exp) | StmData::Return(exp) => self.exp(exp),
+            StmData::IfElse(ifelselist) => {
+                for ifelse in ifelselist {
+                    match &ifelse.data {
+                        IfElseData::If(test, body) | IfElseData::ElseIf(test, body) => {
+                            self.exp(test);
+                            self.stm(body);
+                        }
+                        IfElseData::Else(body) => self.stm(body),
+                    }
+                }
+            }
+            StmData::While(test, body)
+            | StmData::Until(test, body)
+            | StmData::Repeat(test, body) => {
+                self.exp(test);
+                self.stm(body);
+            }
+            StmData::For(init, test, update, body) => {
+                self.stm(init);
+                self.exp(test);
+                self.stm(update);
+                self.stm(body);
+            }
+            StmData::Call(func, args) => self.call(func, args),
+            StmData::Loop(body) => self.stm(body),
+            StmData::Continue | StmData::Break | StmData::None => {}
+        }
+    }
+
+    fn dec(&mut self, dec: &Dec) {
+        if let DecData::Var(var, _, init) = &dec.data {
+            let is_candidate = match &var.data {
+                VarData::Simple(name) => self.candidate(init, name),
+                _ => false,
+            };
+            if !is_candidate {
+                self.exp(init);
+            }
+        }
+    }
+
+    fn call(&mut self, func: &ASTExp, args: &ASTExpList) {
+        match &func.data {
+            ASTExpData::Var(var) if matches!(var.data, VarData::Simple(_)) => {}
+            _ => self.exp(func),
+        }
+        self.args(args);
+    }
+
+    fn args(&mut self, args: &ASTExpList) {
+        for arg in args {
+            self.exp(arg);
+        }
+    }
+
+    fn exp(&mut self, exp: &ASTExp) {
+        match &exp.data {
+            ASTExpData::Var(var) => self.var(var),
+            ASTExpData::Call(func, args) => self.call(func, args),
+            ASTExpData::BinOp(_, explist)
+            | ASTExpData::Array(explist)
+            | ASTExpData::New(_, explist) => {
+                for exp in explist {
+                    self.exp(exp);
+                }
+            }
+            ASTExpData::Func(_, _, body) => self.func_body(body),
+            ASTExpData::UnaryOp(_, exp)
+            | ASTExpData::Field(exp, _)
+            | ASTExpData::SizeOf(exp)
+            | ASTExpData::Paren(exp) => self.exp(exp),
+            ASTExpData::Suffix(exp, suffixlist) => {
+                self.exp(exp);
+                self.suffixlist(suffixlist);
+            }
+            ASTExpData::Int(_)
+            | ASTExpData::Short(_)
+            | ASTExpData::Real(_)
+            | ASTExpData::Char(_)
+            | ASTExpData::String(_)
+            | ASTExpData::Bool(_)
+            | ASTExpData::None => {}
+        }
+    }
+
+    // A variable that is called right away is not used as a value.
+    fn var(&mut self, var: &Var) {
+        match &var.data {
+            VarData::Simple(name) => self.value(name),
+            VarData::SuffixVar(var, suffixlist) => {
+                let called = matches!(
+                    suffixlist.first().map(|suffix| &suffix.data),
+                    Some(SuffixData::Call(_))
+                );
+                if !(called && self.nested == 0 && matches!(var.data, VarData::Simple(_))) {
+                    self.var(var);
+                }
+                self.suffixlist(suffixlist);
+            }
+            VarData::Pointer(var) => self.var(var),
+            VarData::Template(..) | VarData::None => {}
+        }
+    }
+
+    // A variable that is assigned to is not used as a value, but its suffixes are.
+    fn assigned_var(&mut self, var: &Var) {
+        match &var.data {
+            VarData::Simple(_) => {}
+            VarData::SuffixVar(var, suffixlist) => {
+                self.var(var);
+                self.suffixlist(suffixlist);
+            }
+            _ => self.var(var),
+        }
+    }
+
+    fn suffixlist(&mut self, suffixlist: &ASTExpSuffixList) {
+        for suffix in suffixlist {
+            match &suffix.data {
+                SuffixData::Call(explist) => self.args(explist),
+                SuffixData::Subscript(exp) => self.exp(exp),
+                SuffixData::Dot(_) | SuffixData::Arrow(_) => {}
+            }
+        }
+    }
+}
@@ -1,8 +1,5 @@
 use crate::{
-    ast::{
-        dec::{Dec, MemberSpecifier},
-        ty::TypeList,
-    },
+    ast::dec::{Dec, DecList, MemberSpecifier},
     wasm::frame::frame::FrameAccess,
 };
 
@@ -38,15 +35,32 @@ impl EntryMap {
         }
         return None;
     }
+    // the index is compared with the length of the map to know which scope the entry is in
+    pub fn get_data_with_index(&self, id: &String) -> Option<(usize, &EnvEntry)> {
+        self.data
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, pair)| pair.0 == *id)
+            .map(|(index, pair)| (index, &pair.1))
+    }
+    // the overloaded functions with the name, up to the variable that hides them
+    pub fn get_overloads(&self, id: &String) -> Vec<&EnvEntry> {
+        self.data
+            .iter()
+            .rev()
+            .filter(|pair| pair.0 == *id)
+            .map(|pair| &pair.1)
+            .take_while(|entry| matches!(entry, EnvEntry::Func(..)))
+            .collect()
+    }
+    pub fn entry_num(&self) -> usize {
+        self.data.len()
+    }
+    // removes the latest entry with the name
     pub fn remove_data(&mut self, id: &String) {
-        let mut found_index: i32 = -1;
-        for (index, pair) in self.data.iter().rev().enumerate() {
-            if pair.0 == *id {
-                found_index = index as i32;
-            }
-        }
-        if found_index >= 0 {
-            self.data.remove(found_index as usize);
+        if let Some(index) = self.data.iter().rposition(|pair| pair.0 == *id) {
+            self.data.remove(index);
         }
     }
     pub fn get_mut_data(&mut self, id: &String) -> Option<&mut EnvEntry> {
@@ -65,6 +79,12 @@ impl EntryMap {
         }
         return None;
     }
+    pub fn entries(&self) -> impl Iterator<Item = &EnvEntry> {
+        self.data.iter().map(|pair| &pair.1)
+    }
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = &mut EnvEntry> {
+        self.data.iter_mut().map(|pair| &mut pair.1)
+    }
     pub fn add_data(&mut self, id: String, data: EnvEntry) {
         self.data.push((id, data));
     }
@@ -80,29 +100,57 @@ pub enum EnvEntry {
     Var(LazeType, FrameAccess),
     // func_num: i32, params: LazeTypeList, return_type: LazeType, return_var_access: FrameAccess, func_frame: Frame
     Func(i32, LazeTypeList, LazeType),
-    // base_dec: Dec, template_map: TemplateMap, venv_when_declared: EntryMap, type_params: Vec<String>
-    Template(Dec, TemplateMap, EntryMap, Vec<String>),
-    // class_name: String, members: Entrymap, size: i32
-    Class(String, EntryMap, i32),
+    // base_dec: Dec, template_map: TemplateMap, venv_when_declared: EntryMap, type_params: Vec<String>, constraints: Vec<(type_param, constraint_name)>
+    Template(
+        Dec,
+        TemplateMap,
+        EntryMap,
+        Vec<String>,
+        Vec<(String, String)>,
+    ),
+    // type_param: String, requirements: DecList
+    Constraint(String, DecList),
+    // class_name: String, members: Entrymap, size: i32, base_class: Option<String>, vtable: Option<VTable>
+    Class(String, EntryMap, i32, Option<String>, Option<VTable>),
     // type_var_value: LazeType
     Poly(LazeType),
-    // specifier: MemberSpecifier, member_type: LazeType, offset: i32
-    Member(MemberSpecifier, LazeType, i32),
-    // specifier: MemberSpecifier, func_num: i32, params: LazeTypeList, return_type: LazeType, return_var_access: FrameAccess, func_frame: Frame
-    Method(MemberSpecifier, i32, LazeTypeList, LazeType),
+    // specifier: MemberSpecifier, member_type: LazeType, offset: i32, declaring_class: String
+    Member(MemberSpecifier, LazeType, i32, String),
+    // specifier: MemberSpecifier, func_num: i32, params: LazeTypeList, return_type: LazeType, vtable_slot: Option<i32>, declaring_class: String
+    Method(
+        MemberSpecifier,
+        i32,
+        LazeTypeList,
+        LazeType,
+        Option<i32>,
+        String,
+    ),
     None,
 }
 
+// The vtable of a class is a run of entries in the function table.
+// Objects hold the table index of the first entry,
+// and a virtual method is called through the entry at its slot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VTable {
+    // offset of the vtable pointer in the object
+    pub pointer_offset: i32,
+    pub table_start: i32,
+    // function indices, the index is the slot
+    pub funcs: Vec<i32>,
+}
+
+// The instances of a template, by the types given to the type parameters.
 #[derive(Clone, Debug, PartialEq)]
 pub struct TemplateMap {
-    map: Vec<(TypeList, EnvEntry)>,
+    map: Vec<(LazeTypeList, EnvEntry)>,
 }
 
 impl TemplateMap {
     pub fn new() -> TemplateMap {
         TemplateMap { map: vec![] }
     }
-    pub fn get_data<'a>(&'a self, type_param: &TypeList) -> Option<&'a EnvEntry> {
+    pub fn get_data<'a>(&'a self, type_param: &LazeTypeList) -> Option<&'a EnvEntry> {
         for data in &self.map {
             if type_param == &data.0 {
                 return Some(&data.1);
@@ -110,10 +158,18 @@ impl TemplateMap {
         }
         None
     }
-    pub fn add_data(&mut self, type_param: TypeList, entry: EnvEntry) {
-        self.map.push((type_param, entry));
+    // an instance replaces the entry that was added while it was being made
+    pub fn add_data(&mut self, type_param: LazeTypeList, entry: EnvEntry) {
+        match self.map.iter_mut().find(|data| data.0 == type_param) {
+            Some(data) => data.1 = entry,
+            None => self.map.push((type_param, entry)),
+        }
     }
-    pub fn add_data_return_mut(&mut self, type_param: TypeList, entry: EnvEntry) -> &mut EnvEntry {
+    pub fn add_data_return_mut(
+        &mut self,
+        type_param: LazeTypeList,
+        entry: EnvEntry,
+    ) -> &mut EnvEntry {
         self.map.push((type_param, entry));
         &mut self.map.last_mut().unwrap().1
     }
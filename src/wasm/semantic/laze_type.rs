@@ -1,9 +1,8 @@
-use std::io::{stderr, Write};
-
 use crate::{
     ast::ty::TypeList,
+    util::diagnostic::{Diagnostic, DiagnosticCode, DiagnosticList},
     wasm::il::{
-        exp::{Exp, Exp_},
+        exp::{ConstData, Exp, ExpData, Exp_},
         util::{WasmType, WasmTypeList},
     },
 };
@@ -30,10 +29,23 @@ pub enum LazeTypeData {
     Template(String, LazeTypeList, TypeList),
     Array(LazeType, i32),
     Pointer(LazeType),
-    Func(LazeTypeList, LazeType, i32),
+    Func(LazeTypeList, LazeType, FuncRef),
+    // a method bound to an object, the value is the address of the object
+    // params: LazeTypeList, return_type: LazeType, func_num: i32, vtable: Option<(pointer_offset, slot)>
+    Method(LazeTypeList, LazeType, i32, Option<(i32, i32)>),
     None,
 }
 
+// How a value of a function type is called.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FuncRef {
+    // a declared function, called by its function index
+    // it becomes an Indirect closure when it is used as a value
+    Direct(i32),
+    // the address of a closure record, called through the table with the type index
+    Indirect(i32),
+}
+
 impl LazeType_ {
     pub fn to_wasm_type(&self) -> WasmType {
         match self.data {
@@ -46,6 +58,7 @@ impl LazeType_ {
             LazeTypeData::Array(_, _) => WasmType::I32,
             LazeTypeData::Class(_) => WasmType::I32,
             LazeTypeData::Func(_, _, _) => WasmType::I32,
+            LazeTypeData::Method(_, _, _, _) => WasmType::I32,
             LazeTypeData::Pointer(_) => WasmType::I32,
             LazeTypeData::Template(_, _, _) => WasmType::I32,
             LazeTypeData::None => WasmType::None,
@@ -141,20 +154,34 @@ impl LazeType_ {
             data: LazeTypeData::Template(name, lazetype_params, type_params),
         })
     }
-    pub fn func_type(params: LazeTypeList, result: LazeType, type_index: i32) -> LazeType {
+    pub fn func_type(params: LazeTypeList, result: LazeType, func_ref: FuncRef) -> LazeType {
         Box::new(LazeType_ {
             size: 4,
             escape: false,
-            data: LazeTypeData::Func(params, result, type_index),
+            data: LazeTypeData::Func(params, result, func_ref),
+        })
+    }
+    pub fn method_type(
+        params: LazeTypeList,
+        result: LazeType,
+        func_num: i32,
+        vtable: Option<(i32, i32)>,
+    ) -> LazeType {
+        Box::new(LazeType_ {
+            size: 4,
+            escape: false,
+            data: LazeTypeData::Method(params, result, func_num, vtable),
         })
     }
 }
 
-pub fn comp_type_binop<'a>(
+pub fn comp_type_binop(
     left: LazeType,
     left_exp: Exp,
     right: LazeType,
     right_exp: Exp,
+    pos: (usize, usize),
+    diagnostics: &mut DiagnosticList,
 ) -> Option<(LazeType, Exp, Exp)> {
     if left == right {
         Some((left, left_exp, right_exp))
@@ -166,6 +193,9 @@ pub fn comp_type_binop<'a>(
                 LazeTypeData::Short => {
                     Some((left, left_exp, Exp_::convert_exp(left_wasm_type, right_exp)))
                 }
+                LazeTypeData::Pointer(_) if is_null(&left_exp) => {
+                    Some((right, Exp_::consti32_exp(0), right_exp))
+                }
                 LazeTypeData::Real => Some((
                     right,
                     Exp_::convert_exp(right_wasm_type, left_exp),
@@ -186,25 +216,35 @@ pub fn comp_type_binop<'a>(
                     right_exp,
                 )),
                 LazeTypeData::Char => {
-                    let _ = writeln!(
-                        stderr(),
-                        "Warning: conversion from char to short is dangerous."
-                    );
+                    diagnostics.push(Diagnostic::warning(
+                        DiagnosticCode::CharShortConversion,
+                        pos,
+                        "The char is converted to a short without a check.".to_string(),
+                    ));
                     Some((left, left_exp, right_exp))
                 }
                 _ => None,
             },
             LazeTypeData::Char => match right.data {
                 LazeTypeData::Short => {
-                    let _ = writeln!(
-                        stderr(),
-                        "Warning: conversion from short to char is dangerous."
-                    );
+                    diagnostics.push(Diagnostic::warning(
+                        DiagnosticCode::CharShortConversion,
+                        pos,
+                        "The short is converted to a char without a check.".to_string(),
+                    ));
                     Some((left, left_exp, right_exp))
                 }
                 _ => None,
             },
+            LazeTypeData::Pointer(_) if is_null(&right_exp) => {
+                Some((left, left_exp, Exp_::consti32_exp(0)))
+            }
             _ => None,
         }
     }
 }
+
+// 0 is the null pointer, which a pointer can be compared with
+fn is_null(exp: &Exp) -> bool {
+    matches!(exp.data, ExpData::Const(ConstData::I64(0)))
+}
@@ -1,10 +1,19 @@
+pub mod address_taken;
+pub mod closure_lifetime;
 pub mod entry_map;
 pub mod laze_type;
 pub mod semantic_param;
+#[cfg(test)]
+pub mod tests;
 pub mod trans_ast;
+pub mod trans_builtin;
+pub mod trans_closure;
 pub mod trans_dec;
 pub mod trans_exp;
 pub mod trans_funcdec;
+pub mod trans_global;
+pub mod trans_object;
 pub mod trans_stm;
+pub mod trans_template;
 pub mod trans_ty;
 pub mod trans_var;
@@ -1,28 +1,66 @@
 use crate::{
-    ast::ty::{Type, TypeData},
+    ast::{
+        exp::{ASTExp, ASTExpList},
+        ty::{Type, TypeData},
+        var::Var,
+    },
+    util::{
+        diagnostic::{Diagnostic, DiagnosticCode, DiagnosticList},
+        source_map::SourceMap,
+    },
     wasm::{
-        frame::frame::{Frame, FrameType, Frame_},
+        frame::frame::{
+            ClosureEnv, Frame, FrameAccess, FrameType, Frame_, FREE_LISTS_SIZE, STACK_SIZE,
+        },
         il::{
+            exp::{Exp, Exp_},
             module::{ModuleList, Module_},
             stm::StmList,
+            util::{WasmType, WasmTypeList},
         },
     },
 };
 
-use super::entry_map::EntryMap;
+use super::{
+    entry_map::{EntryMap, EnvEntry, VTable},
+    laze_type::{LazeType, LazeTypeData},
+};
 
 pub struct SemanticParam {
     pub venv: EntryMap,
     pub tenv: EntryMap,
-    pub loop_index: i32,
+    // the label given to the next block or loop that can be branched to
+    pub label_num: i32,
     pub func_num: i32,
     pub frame: Vec<Frame>,
+    // one for each function expression that is being translated
+    pub closures: Vec<ClosureEnv>,
+    // signatures used by call_indirect, the index is the type index
+    pub func_types: Vec<(WasmTypeList, WasmType)>,
+    // indices of the functions in the table, the index is the table index
+    pub table_funcs: Vec<i32>,
+    // the declared functions used as values and the addresses of their closure records
+    pub func_values: Vec<(i32, i32)>,
+    // index of メモリ確保, which is declared right after the imports
+    pub alloc_func: i32,
+    // index of メモリ解放, which is declared right after メモリ確保
+    pub free_func: i32,
     pub temp_stmlist: StmList,
+    // stores into the static data, which the start function runs first
+    pub static_inits: StmList,
     pub result_modlist: ModuleList,
+    // the globals of the program, which are put after the ones of the runtime
+    pub global_modlist: ModuleList,
+    // top-level variables set by the start function: var, constructor arguments, initial value
+    pub global_inits: Vec<(Var, ASTExpList, ASTExp)>,
+    // size of the static data placed before the stack
+    pub static_size: i32,
+    pub diagnostics: DiagnosticList,
+    pub source_map: SourceMap,
 }
 
 impl SemanticParam {
-    pub fn new() -> SemanticParam {
+    pub fn new(source_map: SourceMap) -> SemanticParam {
         let result_modlist = vec![Module_::jsimport_mod(
             "memory".to_string(),
             "std".to_string(),
@@ -31,35 +69,173 @@ impl SemanticParam {
         SemanticParam {
             venv: EntryMap::new(),
             tenv: EntryMap::new(),
-            loop_index: 0,
+            label_num: 0,
             func_num: 0,
             frame: vec![],
+            closures: vec![],
+            func_types: vec![],
+            table_funcs: vec![],
+            func_values: vec![],
+            alloc_func: -1,
+            free_func: -1,
             temp_stmlist: vec![],
+            static_inits: vec![],
             result_modlist,
+            global_modlist: vec![],
+            global_inits: vec![],
+            // the address 0 is left unused, so that it can be used as null
+            static_size: 8,
+            diagnostics: vec![],
+            source_map,
         }
     }
     pub fn get_mem_size(&self) -> i32 {
-        if self.frame.len() > 0 {
-            self.frame.last().unwrap().memory_offset + self.frame.last().unwrap().frame_size
-        } else {
-            0
+        self.static_size
+    }
+    // Memory for the escaped globals is taken from the static data.
+    pub fn alloc_static(&mut self, size: i32) -> i32 {
+        let address = (self.static_size + 7) & !7;
+        self.static_size = address + size;
+        address
+    }
+    // The stack starts right after the static data, aligned to 8 bytes.
+    pub fn get_stack_start(&self) -> i32 {
+        (self.static_size + 7) & !7
+    }
+    // The free lists are right after the stack, followed by the heap.
+    pub fn get_free_lists_start(&self) -> i32 {
+        self.get_stack_start() + STACK_SIZE
+    }
+    pub fn get_heap_start(&self) -> i32 {
+        self.get_free_lists_start() + FREE_LISTS_SIZE
+    }
+    pub fn func_type_index(&mut self, params: WasmTypeList, result: WasmType) -> i32 {
+        let signature = (params, result);
+        match self.func_types.iter().position(|ty| *ty == signature) {
+            Some(index) => index as i32,
+            None => {
+                self.func_types.push(signature);
+                self.func_types.len() as i32 - 1
+            }
+        }
+    }
+    // the class entry of a class type or an instance of a class template
+    pub fn class_entry(&self, ty: &LazeType) -> Option<&EnvEntry> {
+        match &ty.data {
+            LazeTypeData::Class(name) => self.tenv.get_data(name),
+            LazeTypeData::Template(name, type_params, _) => match self.tenv.get_data(name) {
+                Some(EnvEntry::Template(_, template_map, _, _, _)) => {
+                    template_map.get_data(type_params)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+    pub fn class_vtable(&self, ty: &LazeType) -> Option<&VTable> {
+        match self.class_entry(ty) {
+            Some(EnvEntry::Class(_, _, _, _, vtable)) => vtable.as_ref(),
+            _ => None,
+        }
+    }
+    // the class whose method is being translated
+    pub fn current_class(&self) -> Option<&String> {
+        match &self.frame.last()?.data {
+            FrameType::Method(_, class_type) => match &class_type.data {
+                TypeData::Name(name) | TypeData::Template(name, _) => Some(name),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+    // A class is also derived from itself.
+    pub fn is_derived_class(&self, class_name: &str, base_name: &str) -> bool {
+        let mut current = Some(class_name.to_string());
+        while let Some(name) = current {
+            if name == *base_name {
+                return true;
+            }
+            current = match self.tenv.get_data(&name) {
+                Some(EnvEntry::Class(_, _, _, base_class, _)) => base_class.clone(),
+                _ => None,
+            };
         }
+        false
     }
     pub fn new_frame(&mut self, func_name: &String, class: Option<&Type>) -> &mut Frame {
         let new_frame = match class {
             Some(ty) => match &ty.data {
-                TypeData::Name(_) | TypeData::Template(_, _) => Frame_::new(
-                    self.get_mem_size(),
-                    FrameType::Method(func_name.clone(), ty.clone()),
-                ),
-                _ => Frame_::new(self.get_mem_size(), FrameType::Func(func_name.clone())),
+                TypeData::Name(_) | TypeData::Template(_, _) => {
+                    Frame_::new(FrameType::Method(func_name.clone(), ty.clone()))
+                }
+                _ => Frame_::new(FrameType::Func(func_name.clone())),
             },
-            None => Frame_::new(self.get_mem_size(), FrameType::Func(func_name.clone())),
+            None => Frame_::new(FrameType::Func(func_name.clone())),
         };
         self.frame.push(new_frame);
         self.frame.last_mut().unwrap()
     }
+    pub fn new_label(&mut self) -> i32 {
+        self.label_num += 1;
+        self.label_num
+    }
     pub fn current_frame(&self) -> Option<&Frame> {
         self.frame.last()
     }
+    pub fn frame_address_exp(&self, access: &FrameAccess) -> Exp {
+        let frame = self.frame.last().unwrap();
+        match access {
+            FrameAccess::InEnv(env_offset) => frame.env_address_exp(*env_offset),
+            FrameAccess::InStatic(address) => Exp_::consti32_exp(*address),
+            _ => frame.address_exp(access.get_frame_offset()),
+        }
+    }
+    // Looks up a name in the venv.
+    // Variables of the enclosing functions are captured by the function expressions in between,
+    // and their access is replaced with the place in the closure record.
+    pub fn get_var_entry(&mut self, name: &String) -> Option<EnvEntry> {
+        let (index, entry) = self.venv.get_data_with_index(name)?;
+        match entry.clone() {
+            EnvEntry::Var(ty, access)
+                if !matches!(access, FrameAccess::InGlobal(_) | FrameAccess::InStatic(_)) =>
+            {
+                let depth = self
+                    .closures
+                    .iter()
+                    .filter(|closure| closure.venv_boundary <= index)
+                    .count();
+                let access = self.capture(name, &ty, access, depth, self.closures.len());
+                Some(EnvEntry::Var(ty, access))
+            }
+            entry => Some(entry),
+        }
+    }
+    fn capture(
+        &mut self,
+        name: &String,
+        ty: &LazeType,
+        access: FrameAccess,
+        var_depth: usize,
+        depth: usize,
+    ) -> FrameAccess {
+        if depth <= var_depth {
+            return access;
+        }
+        if let Some(env_offset) = self.closures[depth - 1].get_capture(name) {
+            return FrameAccess::InEnv(env_offset);
+        }
+        let outer_access = self.capture(name, ty, access, var_depth, depth - 1);
+        FrameAccess::InEnv(self.closures[depth - 1].alloc_capture(name, ty, outer_access))
+    }
+    // file:line:column of a pos, for messages that are embedded in the output
+    pub fn location(&self, pos: (usize, usize)) -> String {
+        self.source_map.location(pos)
+    }
+    pub fn error(&mut self, code: DiagnosticCode, pos: (usize, usize), message: String) {
+        self.diagnostics.push(Diagnostic::error(code, pos, message));
+    }
+    pub fn warning(&mut self, code: DiagnosticCode, pos: (usize, usize), message: String) {
+        self.diagnostics
+            .push(Diagnostic::warning(code, pos, message));
+    }
 }
@@ -0,0 +1,64 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::Value,
+    },
+};
+
+const ACCOUNT: &str = "クラス: 口座 {\n非公開:\n    整数: 暗証;\n保護:\n    整数: 残高;\n}\n";
+
+#[test]
+fn members_inside_class() {
+    let mut instance = compile("./laze_tests/dec/class_access.laze").instantiate();
+    // 貯金 reads the protected 残高 of 口座
+    assert_eq!(
+        instance.call("interest", &[Value::I64(40)]),
+        Some(Value::I64(4))
+    );
+    // 口座 reads its private 暗証 and the 残高 of another 口座
+    assert_eq!(
+        instance.call("query", &[Value::I64(40)]),
+        Some(Value::I64(40 + 1234))
+    );
+}
+
+#[test]
+fn private_from_outside() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!("{ACCOUNT}関数: 実行() => (整数: r) {{\n    口座: a;\n    r = a.暗証;\n}}\n"),
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::PrivateMember]);
+}
+
+#[test]
+fn protected_from_outside() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!("{ACCOUNT}関数: 実行() => (整数: r) {{\n    口座: a;\n    r = a.残高;\n}}\n"),
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::PrivateMember]);
+}
+
+#[test]
+fn private_from_derived_class() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!(
+            "{ACCOUNT}クラス: 貯金 <- 口座 {{\n    関数: 読む() => (整数: r) {{\n        r = 暗証 + 残高;\n    }}\n}}\n"
+        ),
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::PrivateMember]);
+}
+
+// the call of the method is not reported again after the access error
+#[test]
+fn private_method_call() {
+    let compiled = compile_source(
+        "test.laze",
+        "クラス: 金庫 {\n非公開:\n    関数: 開ける() => (整数: r) {\n        r = 1;\n    }\n}\n\
+         関数: 実行() => (整数: r) {\n    金庫: a;\n    r = a.開ける();\n}\n",
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::PrivateMember]);
+}
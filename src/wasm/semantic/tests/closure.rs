@@ -0,0 +1,86 @@
+use crate::wasm::{
+    il::module::Module_,
+    tests::{compile::compile, runner::Value},
+};
+
+#[test]
+fn captured_variables() {
+    let mut instance = compile("./laze_tests/exp/func_exp.laze").instantiate();
+    // 適用 calls the closure that adds n to x
+    assert_eq!(
+        instance.call("add", &[Value::I64(2), Value::I64(5)]),
+        Some(Value::I64(7))
+    );
+    // the inner closure adds m + n twice to 1, where m = n * 10
+    assert_eq!(
+        instance.call("nest", &[Value::I64(2)]),
+        Some(Value::I64(45))
+    );
+}
+
+// a declared function can be passed and kept in a variable as a closure
+#[test]
+fn declared_function_values() {
+    let mut instance = compile("./laze_tests/exp/func_value.laze").instantiate();
+    assert_eq!(
+        instance.call("direct", &[Value::I64(4)]),
+        Some(Value::I64(8))
+    );
+    assert_eq!(
+        instance.call("variable", &[Value::I64(3)]),
+        Some(Value::I64(206))
+    );
+}
+
+#[test]
+fn lifted_to_table() {
+    let compiled = compile("./laze_tests/exp/func_exp.laze");
+    let lifted_num: usize = compiled
+        .module_list
+        .iter()
+        .map(|module| match &**module {
+            Module_::Elem(_, modlist) => modlist.len(),
+            _ => 0,
+        })
+        .sum();
+    assert_eq!(lifted_num, 3);
+    assert!(compiled
+        .module_list
+        .iter()
+        .any(|module| matches!(**module, Module_::Table(3))));
+}
+
+#[test]
+fn frame_records() {
+    let mut instance = compile("./laze_tests/exp/closure_lifetime.laze").instantiate();
+    let result = instance.call("loop", &[Value::I64(100)]);
+    let sum: i64 = (0..100).map(|i| 2 * i).sum();
+    assert_eq!(result, Some(Value::I64(sum)));
+    // the records of the closures that do not escape are in the frame,
+    // so the block freed before the loop is the one given after it
+    assert_eq!(instance.calls_of("testOutput"), [[Value::I64(1)]]);
+}
+
+#[test]
+fn escaping_records() {
+    let mut instance = compile("./laze_tests/exp/closure_lifetime.laze").instantiate();
+    // the returned closures keep their own records on the heap
+    instance.call("escape", &[Value::I64(3)]);
+    assert_eq!(
+        instance.calls_of("testOutput"),
+        [[Value::I64(1 + 3)], [Value::I64(2 + 30)]]
+    );
+}
+
+#[test]
+fn argument_records() {
+    let mut instance = compile("./laze_tests/exp/closure_lifetime.laze").instantiate();
+    // 登録 keeps the closure in a global, so its record outlives the call of 作って登録
+    instance.call("register", &[Value::I64(100)]);
+    // the frames of these calls are where the record would have been
+    instance.call("loop", &[Value::I64(3)]);
+    assert_eq!(
+        instance.call("callStored", &[Value::I64(1)]),
+        Some(Value::I64(101))
+    );
+}
@@ -0,0 +1,67 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::Value,
+    },
+};
+
+const POINT: &str = "クラス: 点 {\n    公開: 整数: x;\n}\n";
+
+const COMPARABLE: &str = "型<T>: 制約: 比較可能 {\n    演算子: Gt(T: a, T: b) => (真偽: r);\n}\n\
+                          型<T>(比較可能: T): 関数: 最大(T: a, T: b) => (T: r) {\n    r = a;\n    もし (b > a) ならば {\n        r = b;\n    }\n}\n";
+
+const CALL: &str = "関数: 実行() => () {\n    点: p;\n    点: q;\n    p = 最大(p, q);\n}\n";
+
+#[test]
+fn satisfied_constraints() {
+    let mut instance = compile("./laze_tests/dec/template_constraint.laze").instantiate();
+    // 整数 has Gt without a declaration
+    assert_eq!(
+        instance.call("max_int", &[Value::I64(6)]),
+        Some(Value::I64(6))
+    );
+    // 点 has Gt declared as an operator
+    assert_eq!(
+        instance.call("max_point", &[Value::I64(5)]),
+        Some(Value::I64(9))
+    );
+    assert_eq!(
+        instance.call("max_point", &[Value::I64(12)]),
+        Some(Value::I64(12))
+    );
+    // 袋 has 大きさ as a method: 6 * 2 + 1
+    assert_eq!(
+        instance.call("amount", &[Value::I64(6)]),
+        Some(Value::I64(13))
+    );
+}
+
+#[test]
+fn missing_operator() {
+    let compiled = compile_source("test.laze", &format!("{POINT}{COMPARABLE}{CALL}"));
+    assert_eq!(
+        compiled.codes(),
+        vec![DiagnosticCode::UnsatisfiedConstraint]
+    );
+}
+
+#[test]
+fn operator_declared_before_template() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!(
+            "{POINT}演算子: Gt(点: a, 点: b) => (真偽: r) {{\n    r = a.x > b.x;\n}}\n{COMPARABLE}{CALL}"
+        ),
+    );
+    assert_eq!(compiled.diagnostics, vec![]);
+}
+
+#[test]
+fn unknown_constraint() {
+    let compiled = compile_source(
+        "test.laze",
+        "型<T>(数える: T): 関数: 恒等(T: a) => (T: r) {\n    r = a;\n}\n",
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::InvalidDeclaration]);
+}
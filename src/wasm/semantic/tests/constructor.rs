@@ -0,0 +1,107 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::{outputs, Value},
+    },
+};
+
+const CAR: &str = "クラス: 車 {\n    公開:\n        整数: 速度 = 3;\n        \
+                   関数: 車(整数: s) => () {\n            速度 = 速度 + s;\n        }\n}\n";
+
+#[test]
+fn constructors() {
+    let mut instance = compile("./laze_tests/dec/class_constructor.laze").instantiate();
+    // the initial value of the member is set before the constructor adds to it
+    assert_eq!(
+        instance.call("speed", &[Value::I64(5)]),
+        Some(Value::I64(8))
+    );
+    // the object is destroyed when the function returns
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        [1, 2].map(Value::I64)
+    );
+    // the member of the base class is initialized too
+    assert_eq!(instance.call("number", &[]), Some(Value::I64(7)));
+}
+
+// ~車 logs 1 and ~基底 logs 2, and the function logs 3 after the object is gone
+#[test]
+fn destructors_at_end_of_scope() {
+    let mut instance = compile("./laze_tests/dec/class_constructor.laze").instantiate();
+    assert_eq!(
+        instance.call("scope", &[Value::I64(5)]),
+        Some(Value::I64(8))
+    );
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        [1, 2, 3].map(Value::I64)
+    );
+}
+
+#[test]
+fn destructors_on_free() {
+    let mut instance = compile("./laze_tests/dec/class_constructor.laze").instantiate();
+    assert_eq!(
+        instance.call("new", &[Value::I64(20)]),
+        Some(Value::I64(23))
+    );
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        [1, 2, 3].map(Value::I64)
+    );
+}
+
+#[test]
+fn wrong_argument_count() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!("{CAR}関数: 実行() => () {{\n    車: a(1, 2);\n}}\n"),
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::TypeMismatch]);
+}
+
+#[test]
+fn arguments_to_scalar() {
+    let compiled = compile_source("test.laze", "関数: 実行() => () {\n    整数: a(1);\n}\n");
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::NotAClass]);
+}
+
+#[test]
+fn misnamed_destructor() {
+    let compiled = compile_source(
+        "test.laze",
+        "クラス: 車 {\n    関数: ~船() => () {\n    }\n}\n",
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::InvalidDeclaration]);
+}
+
+const MACHINE: &str = "関数: テスト出力(整数: x) => () = js読み込み(\"std\", \"testOutput\");\n\
+                       クラス: 部品 {\n    公開:\n        整数: 値 = 4;\n        関数: ~部品() => () {\n            テスト出力(値);\n        }\n}\n\
+                       クラス: 機械 {\n    公開:\n        部品: 中[2];\n        部品: 外;\n        関数: ~機械() => () {\n            テスト出力(1);\n        }\n}\n\
+                       クラス: 箱 {\n    公開:\n        部品: 中;\n}\n\
+                       関数: 汚す(整数: n) => () {\n    整数: 表[8];\n    (整数: i = 0; i < 8; i += 1) の間 {\n        表[i] = n;\n    }\n}\n\
+                       関数: 実行() => (整数: r) {\n    汚す(9);\n    機械: m;\n    m.外.値 = 5;\n    r = m.中[1].値;\n}\n\
+                       関数: 解放() => (整数: r) {\n    箱: *b = 新規 箱();\n    r = b->中.値;\n    メモリ解放(b);\n}\n\
+                       js書き出し(解放, \"free\");\n";
+
+// the members are constructed even though the memory of the frame was used by 汚す,
+// and destructed after ~機械, in the reverse order
+#[test]
+fn member_objects() {
+    let mut instance = compile_source("test.laze", MACHINE).instantiate();
+    assert_eq!(instance.call("main", &[]), Some(Value::I64(4)));
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        [1, 5, 4, 4].map(Value::I64)
+    );
+}
+
+// 箱 has no destructor, but its member does
+#[test]
+fn member_objects_on_free() {
+    let mut instance = compile_source("test.laze", MACHINE).instantiate();
+    assert_eq!(instance.call("free", &[]), Some(Value::I64(4)));
+    assert_eq!(outputs(instance.calls_of("testOutput")), [Value::I64(4)]);
+}
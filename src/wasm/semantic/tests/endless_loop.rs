@@ -0,0 +1,43 @@
+use std::fs;
+
+use crate::{
+    util::{diagnostic::DiagnosticCode, source_map::SourceMap},
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::Value,
+    },
+};
+
+#[test]
+fn break_from_loop() {
+    let path = "./laze_tests/stm/loop_stm/loop_break.laze";
+    let compiled = compile(path);
+    // 抜ける in the while loop of 止まらない leaves only the while loop
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::EndlessLoop]);
+    let source = fs::read_to_string(path).unwrap();
+    let func = source.find("止まらない").unwrap();
+    let loop_start = func + source[func..].find("無限ループ").unwrap();
+    let offset = source[..loop_start].chars().count();
+    assert_eq!(compiled.diagnostics[0].span.0, offset);
+    assert_eq!(SourceMap::new(path, &source).line_column(offset), (17, 5));
+    let mut instance = compiled.instantiate();
+    // each turn adds 1 and 10 before the inner loop is left
+    assert_eq!(
+        instance.call("get", &[Value::I64(25)]),
+        Some(Value::I64(33))
+    );
+}
+
+#[test]
+fn return_from_loop() {
+    let compiled = compile_source(
+        "test.laze",
+        "関数: 実行() => (整数: r) {\n    r = 0;\n    無限ループ {\n        r += 1;\n        \
+         もし (r == 4) ならば {\n            終了(r);\n        }\n    }\n}\n",
+    );
+    assert_eq!(compiled.diagnostics, vec![]);
+    assert_eq!(
+        compiled.instantiate().call("main", &[]),
+        Some(Value::I64(4))
+    );
+}
@@ -0,0 +1,10 @@
+use crate::wasm::tests::{compile::compile, runner::Value};
+
+// The results of the calls that are statements are dropped, or the module would not validate.
+#[test]
+fn call_result_dropped() {
+    let mut instance = compile("./laze_tests/stm/exp_stm/call_result.laze").instantiate();
+    assert_eq!(instance.call("direct", &[]), Some(Value::I64(1)));
+    let mut instance = compile("./laze_tests/stm/exp_stm/call_result.laze").instantiate();
+    assert_eq!(instance.call("indirect", &[]), Some(Value::I64(1)));
+}
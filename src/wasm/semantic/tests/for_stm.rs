@@ -0,0 +1,25 @@
+use crate::wasm::tests::{
+    compile::{compile, compile_source},
+    runner::{outputs, Value},
+};
+
+#[test]
+fn increment_after_body() {
+    let mut instance = compile("./laze_tests/stm/for_stm/for_continue.laze").instantiate();
+    instance.call("main", &[]);
+    // 次へ skips 2 but still runs the increment
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        [0, 1, 3, 4, 10, 7, 4, 1].map(Value::I64)
+    );
+}
+
+#[test]
+fn false_at_start() {
+    let mut instance = compile_source(
+        "test.laze",
+        "関数: 実行() => (整数: r) {\n    r = 0;\n    (整数: a = 5; a < 5; a += 1) の間 {\n        r += 1;\n    }\n}\n",
+    )
+    .instantiate();
+    assert_eq!(instance.call("main", &[]), Some(Value::I64(0)));
+}
@@ -0,0 +1,114 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::{
+        il::module::Module_,
+        tests::{
+            compile::{compile, compile_source},
+            runner::Value,
+        },
+    },
+};
+
+#[test]
+fn initialized_by_start_function() {
+    let mut instance = compile("./laze_tests/dec/global_var.laze").instantiate();
+    // constant initial values
+    assert_eq!(instance.call("total", &[]), Some(Value::I64(10)));
+    assert_eq!(instance.call("ratio", &[]), Some(Value::F64(-0.5)));
+    // a call to a function declared after the variable
+    assert_eq!(instance.call("double", &[]), Some(Value::I64(42)));
+    // an array is filled with zeros
+    assert_eq!(instance.call("table", &[]), Some(Value::I64(0)));
+    // the constructor adds 100 to the initial value of the member
+    assert_eq!(instance.call("counter", &[]), Some(Value::I64(105)));
+    // each object of an array has the initial value of the member
+    assert_eq!(instance.call("array", &[]), Some(Value::I64(7)));
+}
+
+#[test]
+fn kept_between_calls() {
+    let mut instance = compile("./laze_tests/dec/global_var.laze").instantiate();
+    instance.call("add", &[Value::I64(4)]);
+    instance.call("add", &[Value::I64(1)]);
+    assert_eq!(instance.call("total", &[]), Some(Value::I64(15)));
+    assert_eq!(instance.call("table", &[]), Some(Value::I64(5)));
+    assert_eq!(instance.call("counter", &[]), Some(Value::I64(110)));
+    assert_eq!(instance.call("array", &[]), Some(Value::I64(12)));
+}
+
+#[test]
+fn immutable_globals() {
+    let compiled = compile_source(
+        "test.laze",
+        "整数: a = 1;\n整数: b = 2;\n整数: c = 足す(a);\n整数: d[2];\n\
+         関数: 足す(整数: n) => (整数: r) {\n    r = n + b;\n}\n\
+         関数: 実行() => (整数: r) {\n    b += 1;\n    d[0] = 足す(c);\n    r = d[0];\n}\n",
+    );
+    let mutable: Vec<bool> = compiled
+        .module_list
+        .iter()
+        .filter_map(|module| match &**module {
+            Module_::Global(_, mutable, _) => Some(*mutable),
+            _ => None,
+        })
+        .skip(3)
+        .collect();
+    // b is assigned to, c is set by the start function, and d holds the address of the array
+    assert_eq!(mutable, [false, true, true, false]);
+    let mut instance = compiled.instantiate();
+    assert_eq!(instance.call("main", &[]), Some(Value::I64(6)));
+}
+
+#[test]
+fn address_of_scalar_global() {
+    // a is kept in the static data instead of a wasm global, and b points to it
+    let mut instance = compile_source(
+        "test.laze",
+        "整数: a = 40;\n整数: *b = &a;\n\
+         関数: 実行() => (整数: r) {\n    *b = *b + 2;\n    r = a;\n}\n",
+    )
+    .instantiate();
+    assert_eq!(instance.call("main", &[]), Some(Value::I64(42)));
+    assert_eq!(instance.call("main", &[]), Some(Value::I64(44)));
+}
+
+#[test]
+fn address_of_scalar_local_and_param() {
+    let mut instance = compile_source(
+        "test.laze",
+        "関数: 足す(整数: *p, 整数: n) => () {\n    *p = *p + n;\n}\n\
+         関数: 実行(整数: n) => (整数: r) {\n    整数: x = 3;\n    足す(&x, n);\n    r = x;\n}\n\
+         関数: 引数(整数: n) => (整数: r) {\n    足す(&n, 1);\n    r = n;\n}\n\
+         js書き出し(引数, \"param\");\n",
+    )
+    .instantiate();
+    // x is changed through the pointer
+    assert_eq!(instance.call("main", &[Value::I64(4)]), Some(Value::I64(7)));
+    // and so is the parameter n
+    assert_eq!(
+        instance.call("param", &[Value::I64(4)]),
+        Some(Value::I64(5))
+    );
+}
+
+#[test]
+fn address_of_object_global() {
+    let mut instance = compile_source(
+        "test.laze",
+        "クラス: 箱 {\n    公開: 整数: 値 = 5;\n}\n箱: a;\n箱: *b = &a;\n\
+         関数: 実行() => (整数: r) {\n    b->値 = b->値 + 1;\n    r = a.値;\n}\n",
+    )
+    .instantiate();
+    assert_eq!(instance.call("main", &[]), Some(Value::I64(6)));
+}
+
+#[test]
+fn var_dec() {
+    let compiled = compile("./laze_tests/dec/var_dec.laze");
+    // only a warning, so the module is made
+    assert_eq!(
+        compiled.codes(),
+        vec![DiagnosticCode::MultiDimensionalArray]
+    );
+    compiled.instantiate();
+}
@@ -0,0 +1,76 @@
+use crate::wasm::{
+    il::module::Module_,
+    tests::{
+        compile::{compile, compile_source},
+        runner::Value,
+    },
+};
+
+#[test]
+fn allocate_and_free() {
+    let mut instance = compile("./laze_tests/dec/heap_alloc.laze").instantiate();
+    // 5 - 7
+    assert_eq!(
+        instance.call("ends", &[Value::I64(3)]),
+        Some(Value::I64(-2))
+    );
+    // a freed block is given again for the same size
+    assert_eq!(
+        instance.call("reuse", &[Value::I64(3)]),
+        Some(Value::I32(1))
+    );
+    // the memory grows to hold the large block
+    assert_eq!(instance.call("big", &[]), Some(Value::I64(3)));
+    assert!(instance.memory().len() >= 10000000);
+    // 3 + 2 + 1 in a list of blocks
+    assert_eq!(instance.call("list", &[Value::I64(3)]), Some(Value::I64(6)));
+}
+
+#[test]
+fn distinct_blocks() {
+    let mut instance = compile_source(
+        "test.laze",
+        "関数: 実行() => (整数: r) {\n    整数: *a = メモリ確保(8);\n    整数: *b = メモリ確保(8);\n    \
+         a[0] = 1;\n    b[0] = 2;\n    r = a[0];\n}\n",
+    )
+    .instantiate();
+    // a would have 2 if b were the same block
+    assert_eq!(instance.call("main", &[]), Some(Value::I64(1)));
+}
+
+#[test]
+fn invalid_sizes() {
+    let mut instance = compile_source(
+        "test.laze",
+        "関数: 実行(整数: n) => (整数: *p) {\n    p = メモリ確保(n);\n}\n",
+    )
+    .instantiate();
+    // the sizes that do not fit give null without touching the free lists
+    for size in [-1, -8, 1 << 31, 1 << 32, i64::MAX] {
+        assert_eq!(
+            instance.call("main", &[Value::I64(size)]),
+            Some(Value::I32(0)),
+            "メモリ確保({size})"
+        );
+    }
+    let Some(Value::I32(block)) = instance.call("main", &[Value::I64(8)]) else {
+        panic!("メモリ確保 did not give a block");
+    };
+    assert_ne!(block, 0);
+}
+
+// メモリ確保 and メモリ解放 are only declared when the program uses the heap
+#[test]
+fn allocator_only_when_used() {
+    let compiled = compile_source("test.laze", "関数: 実行() => (整数: r) {\n    r = 1;\n}\n");
+    let func_num = compiled
+        .module_list
+        .iter()
+        .filter(|module| matches!(***module, Module_::Func(..)))
+        .count();
+    assert_eq!(func_num, 1);
+    assert_eq!(
+        compiled.instantiate().call("main", &[]),
+        Some(Value::I64(1))
+    );
+}
@@ -0,0 +1,28 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::Value,
+    },
+};
+
+#[test]
+fn derived_pointer_argument() {
+    let mut instance = compile("./laze_tests/dec/class_inheritance.laze").instantiate();
+    // 合計(&q) reads the inherited x and y, then z is added
+    assert_eq!(
+        instance.call("get", &[Value::I64(4)]),
+        Some(Value::I64(112))
+    );
+}
+
+#[test]
+fn unrelated_pointer_argument() {
+    let compiled = compile_source(
+        "test.laze",
+        "クラス: 点 {\n    整数: x;\n}\nクラス: 別 {\n    整数: w;\n}\n\
+         関数: 読む(点*: p) => (整数: r) {\n    r = p->x;\n}\n\
+         関数: 実行() => (整数: r) {\n    別: e;\n    r = 読む(&e);\n}\n",
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::TypeMismatch]);
+}
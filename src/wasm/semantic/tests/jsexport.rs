@@ -0,0 +1,34 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::{il::module::Module_, tests::compile::compile_source},
+};
+
+#[test]
+fn unknown_function() {
+    let compiled = compile_source("test.laze", "js書き出し(ワールド, \"world\");");
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::UndefinedVariable]);
+    assert_eq!(compiled.diagnostics[0].span, (0, 22));
+    assert!(!compiled
+        .module_list
+        .iter()
+        .any(|module| matches!(**module, Module_::JsExport(..))));
+}
+
+#[test]
+fn not_a_function() {
+    let compiled = compile_source("test.laze", "整数: a = 0;\njs書き出し(a, \"a\");");
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::NotAFunction]);
+}
+
+#[test]
+fn exported_function() {
+    let compiled = compile_source(
+        "test.laze",
+        "関数: 実行() => () {\n}\njs書き出し(実行, \"run\");",
+    );
+    assert!(compiled.diagnostics.is_empty());
+    assert!(compiled
+        .module_list
+        .iter()
+        .any(|module| matches!(&**module, Module_::JsExport(name, _) if name == "run")));
+}
@@ -0,0 +1,124 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::{outputs, Value},
+    },
+};
+
+// the char offset of the first `operand` in the source
+fn offset_of(source: &str, operand: &str) -> usize {
+    source[..source.find(operand).unwrap()].chars().count()
+}
+
+// 数える logs 1, and is called only when the left operand does not decide the result
+fn short_circuit(name: &str, n: i64, result: bool, counted: bool) {
+    let mut instance = compile("./laze_tests/exp/logical_exp.laze").instantiate();
+    assert_eq!(
+        instance.call(name, &[Value::I64(n)]),
+        Some(Value::I32(result as i32)),
+        "{name}({n})"
+    );
+    let expected: &[Value] = if counted { &[Value::I64(1)] } else { &[] };
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        expected,
+        "{name}({n})"
+    );
+}
+
+#[test]
+fn short_circuit_and() {
+    short_circuit("and", 5, true, true);
+    short_circuit("and", 2, false, false);
+}
+
+#[test]
+fn short_circuit_or() {
+    short_circuit("or", 5, true, false);
+    short_circuit("or", 2, false, true);
+}
+
+// 指す checks `p != 0 && p->x > 0`, where p is null when n is not more than 3
+#[test]
+fn member_in_right_operand() {
+    let mut instance = compile("./laze_tests/exp/logical_exp.laze").instantiate();
+    assert_eq!(
+        instance.call("pointer", &[Value::I64(5)]),
+        Some(Value::I32(1))
+    );
+    assert_eq!(
+        instance.call("pointer", &[Value::I64(2)]),
+        Some(Value::I32(0))
+    );
+}
+
+#[test]
+fn null_on_the_left() {
+    let source = "クラス: 点 {
+    公開:
+        整数: x = 5;
+}
+関数: 空(整数: n) => (真偽: r) {
+    点: *p;
+    もし (n > 3) ならば {
+        p = 新規 点();
+    }
+    r = 0 == p;
+}
+js書き出し(空, \"isNull\");
+";
+    let compiled = compile_source("null_on_the_left", source);
+    assert!(
+        compiled.diagnostics.is_empty(),
+        "{:?}",
+        compiled.diagnostics
+    );
+    let mut instance = compiled.instantiate();
+    assert_eq!(
+        instance.call("isNull", &[Value::I64(2)]),
+        Some(Value::I32(1))
+    );
+    assert_eq!(
+        instance.call("isNull", &[Value::I64(5)]),
+        Some(Value::I32(0))
+    );
+}
+
+// The closure in the right operand needs statements, which run after the arguments before it.
+#[test]
+fn evaluation_order() {
+    let mut instance = compile("./laze_tests/exp/logical_order.laze").instantiate();
+    assert_eq!(instance.call("get", &[]), Some(Value::I64(1)));
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        [1, 2, 3].map(Value::I64)
+    );
+}
+
+// `n != 0 && 数える(n);` is a statement that calls 数える only when n is not 0
+#[test]
+fn expression_statement() {
+    let mut instance = compile("./laze_tests/stm/exp_stm/logical_stm.laze").instantiate();
+    instance.call("run", &[Value::I64(5)]);
+    assert_eq!(outputs(instance.calls_of("testOutput")), [Value::I64(5)]);
+    let mut instance = compile("./laze_tests/stm/exp_stm/logical_stm.laze").instantiate();
+    instance.call("run", &[Value::I64(0)]);
+    assert_eq!(outputs(instance.calls_of("testOutput")), [Value::I64(100)]);
+}
+
+#[test]
+fn left_operand_not_bool() {
+    let source = "関数: 実行() => (真偽: r) {\n    r = 1 && 真;\n}\n";
+    let compiled = compile_source("test.laze", source);
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::TypeMismatch]);
+    assert_eq!(compiled.diagnostics[0].span.0, offset_of(source, "1"));
+}
+
+#[test]
+fn right_operand_not_bool() {
+    let source = "関数: 実行() => (真偽: r) {\n    r = 真 || 2;\n}\n";
+    let compiled = compile_source("test.laze", source);
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::TypeMismatch]);
+    assert_eq!(compiled.diagnostics[0].span.0, offset_of(source, "2"));
+}
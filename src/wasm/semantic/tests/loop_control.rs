@@ -0,0 +1,59 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::Value,
+    },
+};
+
+// the values that the loops of the function give to テスト出力
+fn outputs(name: &str, n: i64) -> Vec<Value> {
+    let mut instance = compile("./laze_tests/stm/while_stm/loop_nested_break.laze").instantiate();
+    instance.call(name, &[Value::I64(n)]);
+    instance
+        .calls_of("testOutput")
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[test]
+fn continue_in_for() {
+    // 次へ skips 2
+    assert_eq!(outputs("for", 4), [0, 1, 3].map(Value::I64));
+}
+
+#[test]
+fn break_and_continue_in_while() {
+    // 次へ skips 3 and 抜ける leaves after 5
+    assert_eq!(outputs("while", 5), [1, 2, 4, 5].map(Value::I64));
+}
+
+#[test]
+fn break_in_nested_repeat() {
+    // 抜ける leaves only the inner loop
+    assert_eq!(outputs("repeat", 3), [1, 2, 1, 2, 1, 2].map(Value::I64));
+}
+
+#[test]
+fn break_in_for_inside_until() {
+    assert_eq!(
+        outputs("until", 4),
+        [0, 0, 1, 0, 1, 2, 0, 1, 2, 3].map(Value::I64)
+    );
+}
+
+#[test]
+fn break_outside_loop() {
+    let compiled = compile_source("test.laze", "関数: 実行() => () {\n    抜ける;\n}\n");
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::NotInLoop]);
+}
+
+#[test]
+fn continue_outside_loop() {
+    let compiled = compile_source(
+        "test.laze",
+        "関数: 実行() => () {\n    もし (真) ならば {\n        次へ;\n    }\n}\n",
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::NotInLoop]);
+}
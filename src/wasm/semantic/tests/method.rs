@@ -0,0 +1,48 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::Value,
+    },
+};
+
+#[test]
+fn receivers() {
+    let mut instance = compile("./laze_tests/dec/method_call.laze").instantiate();
+    let receivers = [
+        // (*p).取得()
+        ("deref", 2),
+        // 取得() * 2 called on self
+        ("self", 4),
+        // o.中.取得()
+        ("member", 2),
+        // o.ptr->取得()
+        ("pointer", 2),
+        // q->出す().取得() on the object that the template method returns
+        ("template", 2),
+        // a.複製().取得() on the returned copy, which has one more
+        ("returned", 3),
+    ];
+    for (name, expected) in receivers {
+        assert_eq!(
+            instance.call(name, &[Value::I64(2)]),
+            Some(Value::I64(expected)),
+            "{name}"
+        );
+    }
+    // 7 + 1 + 2 + 10 + 100 + 1000 through chained calls on an array element
+    assert_eq!(
+        instance.call("getFromArray", &[Value::I64(7)]),
+        Some(Value::I64(1120))
+    );
+}
+
+#[test]
+fn dereference_non_pointer() {
+    let compiled = compile_source(
+        "test.laze",
+        "クラス: 計数 {\n    公開:\n        整数: 値 = 0;\n        関数: 取得() => (整数: r) {\n            r = 値;\n        }\n}\n\
+         関数: 実行() => (整数: r) {\n    計数: a;\n    r = (*a).取得();\n}\n",
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::NotAPointer]);
+}
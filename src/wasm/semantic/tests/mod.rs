@@ -0,0 +1,21 @@
+pub mod access;
+pub mod closure;
+pub mod constraint;
+pub mod constructor;
+pub mod endless_loop;
+pub mod exp_stm;
+pub mod for_stm;
+pub mod global;
+pub mod heap;
+pub mod inheritance;
+pub mod jsexport;
+pub mod logical;
+pub mod loop_control;
+pub mod method;
+pub mod operator;
+pub mod overload;
+pub mod recursion;
+pub mod template;
+pub mod type_mismatch;
+pub mod until_stm;
+pub mod virtual_method;
@@ -0,0 +1,53 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::{outputs, Value},
+    },
+};
+
+const VECTOR: &str = "クラス: ベクトル {\n    整数: x;\n    整数: y;\n}\n\
+                      演算子: Add(ベクトル: a, ベクトル: b) => (ベクトル: r) {\n    r.x = a.x + b.x;\n    r.y = a.y + b.y;\n}\n";
+
+#[test]
+fn member_and_free_operators() {
+    let mut instance = compile("./laze_tests/dec/oper_overload.laze").instantiate();
+    // c == c
+    assert_eq!(instance.call("get", &[Value::I64(1)]), Some(Value::I32(1)));
+    // c = ((1, 2) + (10, 20) + (1, 2)) * 3 - (10, 20)
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        [Value::I64(26), Value::I64(52)]
+    );
+}
+
+#[test]
+fn undeclared_operator() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!(
+            "{VECTOR}関数: 実行() => () {{\n    ベクトル: a;\n    ベクトル: b;\n    a = a * b;\n}}\n"
+        ),
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::InvalidOperator]);
+}
+
+#[test]
+fn unknown_operator_name() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!(
+            "{VECTOR}演算子: Foo(ベクトル: a, ベクトル: b) => (ベクトル: r) {{\n    r = a;\n}}\n"
+        ),
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::InvalidOperator]);
+}
+
+#[test]
+fn char_short_conversion() {
+    let compiled = compile_source(
+        "test.laze",
+        "関数: 比較(文字: c, 整数32: s) => (真偽: b) {\n    b = c == s;\n}\n",
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::CharShortConversion]);
+}
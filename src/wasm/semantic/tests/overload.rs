@@ -0,0 +1,77 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::{outputs, Value},
+    },
+};
+
+const ADD: &str = "関数: 足す(整数: a, 整数: b) => (整数: r) {\n    r = a + b;\n}\n\
+                   関数: 足す(整数: a) => (整数: r) {\n    r = a + 1000;\n}\n";
+
+#[test]
+fn resolve_by_argument_types() {
+    let mut instance = compile("./laze_tests/dec/func_overload.laze").instantiate();
+    instance.call("get", &[Value::I64(3)]);
+    // 足す(整数, 整数), 足す(整数), 足す(実数, 実数), 数える(動物*) given a 犬*, 数える(整数)
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        [
+            Value::I64(5),
+            Value::I64(1003),
+            Value::I64(7),
+            Value::I64(4),
+            Value::I64(300)
+        ]
+    );
+}
+
+#[test]
+fn same_parameter_types() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!("{ADD}関数: 足す(整数: x) => (整数: r) {{\n    r = x;\n}}\n"),
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::InvalidDeclaration]);
+}
+
+#[test]
+fn no_matching_overload() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!("{ADD}関数: 実行() => (整数: r) {{\n    r = 足す(1, 2, 3);\n}}\n"),
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::TypeMismatch]);
+}
+
+#[test]
+fn overloaded_export() {
+    let compiled = compile_source("test.laze", &format!("{ADD}js書き出し(足す, \"add\");\n"));
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::InvalidDeclaration]);
+}
+
+#[test]
+fn single_declaration_checked_like_overloads() {
+    // func_dec.laze passes a string where 実数 is expected
+    let compiled = compile("./laze_tests/dec/func_dec.laze");
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::TypeMismatch]);
+    assert!(compiled.diagnostics[0].message.contains("表示"));
+
+    let compiled = compile_source(
+        "test.laze",
+        "関数: 二倍(整数: a) => (整数: r) {\n    r = a * 2;\n}\n\
+         関数: 実行() => (整数: r) {\n    r = 二倍(1, 2) + 二倍(真);\n}\n",
+    );
+    let messages: Vec<&str> = compiled
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "\"二倍\" takes 1 arguments, but 2 are given.",
+            "Argument 1 of \"二倍\" does not have the type of its parameter.",
+        ]
+    );
+}
@@ -0,0 +1,42 @@
+use crate::wasm::tests::{compile::compile, runner::Value};
+
+// 木(n) = 木(n - 1) + 木(n - 2) + n, with 木(0) = 0 and 木(1) = 1
+fn tree(n: i64) -> i64 {
+    if n <= 1 {
+        n
+    } else {
+        tree(n - 1) + tree(n - 2) + n
+    }
+}
+
+#[test]
+fn escaped_locals_in_each_frame() {
+    let mut instance = compile("./laze_tests/dec/func_recursion.laze").instantiate();
+    for n in [0, 1, 2, 10] {
+        assert_eq!(
+            instance.call("get", &[Value::I64(n)]),
+            Some(Value::I64(tree(n) + 1000)),
+            "木({n})"
+        );
+    }
+}
+
+#[test]
+fn first_frame_is_not_at_null() {
+    let mut instance = compile("./laze_tests/dec/func_recursion.laze").instantiate();
+    instance.call("get", &[Value::I64(3)]);
+    // the node of the outermost call would be at the address 0 if the stack started there
+    assert_eq!(&instance.memory()[..8], &[0; 8]);
+}
+
+#[test]
+fn stack_overflow_traps() {
+    let mut instance = compile("./laze_tests/dec/stack_overflow.laze").instantiate();
+    assert_eq!(
+        instance.call("deep", &[Value::I64(10)]),
+        Some(Value::I64(55))
+    );
+    // 100 frames of 16000 bytes are more than the 1MB stack
+    let error = instance.try_call("deep", &[Value::I64(100)]).unwrap_err();
+    assert!(error.contains("unreachable"), "{error}");
+}
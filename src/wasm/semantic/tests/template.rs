@@ -0,0 +1,38 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::tests::{
+        compile::{compile, compile_source},
+        runner::{outputs, Value},
+    },
+};
+
+const MAX: &str = "型<T>: 関数: 最大(T: a, T: b) => (T: r) {\n    r = a;\n    もし (b > a) ならば {\n        r = b;\n    }\n}\n";
+
+#[test]
+fn instantiate_per_type_argument() {
+    let mut instance = compile("./laze_tests/dec/func_template.laze").instantiate();
+    instance.call("get", &[Value::I64(4)]);
+    // 箱<整数> holding 最大<整数>(4, 10), 最大(4, 2), 4!
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        [Value::I64(10), Value::I64(4), Value::I64(24)]
+    );
+}
+
+#[test]
+fn too_many_type_arguments() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!("{MAX}関数: 実行() => (整数: r) {{\n    r = 最大<整数, 整数>(1, 2);\n}}\n"),
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::TemplateParams]);
+}
+
+#[test]
+fn generic_function_as_value() {
+    let compiled = compile_source(
+        "test.laze",
+        &format!("{MAX}関数: 実行() => () {{\n    整数: a;\n    a = 最大;\n}}\n"),
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::NotAFunction]);
+}
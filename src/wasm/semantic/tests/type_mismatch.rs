@@ -0,0 +1,19 @@
+use crate::{util::diagnostic::DiagnosticCode, wasm::tests::compile::compile_source};
+
+#[test]
+fn binop_operands() {
+    let compiled = compile_source(
+        "test.laze",
+        "関数: 実行() => (整数: r) {\n    r = 1 + 真;\n}\n",
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::TypeMismatch]);
+}
+
+#[test]
+fn assigned_value() {
+    let compiled = compile_source(
+        "test.laze",
+        "クラス: 点 {\n    公開:\n        整数: x = 5;\n}\n関数: 実行() => () {\n    点: *p = 0;\n}\n",
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::TypeMismatch]);
+}
@@ -0,0 +1,43 @@
+use crate::{
+    ast::{ast::ASTNode, dec::Dec_, exp::ASTExp_, stm::Stm_, var::Var_},
+    util::{diagnostic::DiagnosticCode, source_map::SourceMap},
+    wasm::{
+        semantic::trans_ast::trans_ast,
+        tests::{compile::compile, runner::Value},
+    },
+};
+
+#[test]
+fn until_condition() {
+    let mut instance = compile("./laze_tests/stm/until_stm/until_simple.laze").instantiate();
+    instance.call("main", &[]);
+    assert_eq!(
+        instance.calls_of("testOutput"),
+        [1, 3, 9, 27, 81].map(|x| vec![Value::I64(x)])
+    );
+}
+
+// The grammar does not give call statements, so the tree is made by hand.
+#[test]
+fn unsupported_statement() {
+    let source = "関数: 実行() => () {\n    実行();\n}\n";
+    let pos = (21, 26);
+    let ast = ASTNode::DecList(vec![Dec_::func_dec(
+        (0, source.chars().count()),
+        "実行".to_string(),
+        vec![],
+        vec![],
+        vec![Stm_::call_stm(
+            pos,
+            ASTExp_::var_exp(pos, Var_::simple_var(pos, "実行".to_string())),
+            vec![],
+        )],
+    )]);
+    let (_, _, diagnostics) = trans_ast(ast, SourceMap::new("test.laze", source));
+    let codes: Vec<DiagnosticCode> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.code)
+        .collect();
+    assert_eq!(codes, vec![DiagnosticCode::UnsupportedStatement]);
+    assert_eq!(diagnostics[0].span, pos);
+}
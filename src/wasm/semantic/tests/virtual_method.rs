@@ -0,0 +1,53 @@
+use crate::{
+    util::diagnostic::DiagnosticCode,
+    wasm::{
+        il::module::Module_,
+        tests::{
+            compile::{compile, compile_source},
+            runner::{outputs, Value},
+        },
+    },
+};
+
+#[test]
+fn dispatch_through_vtable() {
+    let mut instance = compile("./laze_tests/dec/class_virtual.laze").instantiate();
+    instance.call("get", &[Value::I64(4)]);
+    // 名前 and then 面積 of 図形, 四角 and 正方形
+    assert_eq!(
+        outputs(instance.calls_of("testOutput")),
+        [
+            Value::I64(1),
+            Value::I64(0),
+            Value::I64(1),
+            Value::I64(8),
+            Value::I64(3),
+            Value::I64(16)
+        ]
+    );
+}
+
+#[test]
+fn vtables_in_table() {
+    let compiled = compile("./laze_tests/dec/class_virtual.laze");
+    // each of the three classes has a vtable with two slots
+    let table_funcs: usize = compiled
+        .module_list
+        .iter()
+        .map(|module| match &**module {
+            Module_::Elem(_, modlist) => modlist.len(),
+            _ => 0,
+        })
+        .sum();
+    assert_eq!(table_funcs, 6);
+}
+
+#[test]
+fn override_with_different_type() {
+    let compiled = compile_source(
+        "test.laze",
+        "クラス: 図形 {\n仮想:\n    関数: 面積() => (整数: r) {\n        r = 0;\n    }\n}\n\
+         クラス: 四角 <- 図形 {\n    関数: 面積() => (実数: r) {\n        r = 1.0;\n    }\n}\n",
+    );
+    assert_eq!(compiled.codes(), vec![DiagnosticCode::TypeMismatch]);
+}
@@ -1,22 +1,122 @@
 use std::cmp::Ordering;
+use std::mem::take;
 
 use crate::ast::ast::ASTNode;
 use crate::ast::dec::{DecData, DecList};
-use crate::wasm::il::module::ModuleList;
+use crate::util::diagnostic::DiagnosticList;
+use crate::util::source_map::SourceMap;
+use crate::wasm::frame::frame::{FrameType, Frame_};
+use crate::wasm::il::exp::Exp_;
+use crate::wasm::il::module::{ModuleList, Module_};
+use crate::wasm::il::stm::Stm_;
+use crate::wasm::il::util::WasmType;
 
+use super::address_taken::{address_taken_in_declist, assigned_in_declist, uses_heap_in_declist};
 use super::semantic_param::SemanticParam;
+use super::trans_builtin::trans_builtins;
 use super::trans_dec::trans_dec;
+use super::trans_global::trans_global_inits;
 
-pub fn trans_ast(tree: ASTNode) -> (ModuleList, i32) {
+pub fn trans_ast(tree: ASTNode, source_map: SourceMap) -> (ModuleList, i32, DiagnosticList) {
     match tree {
         ASTNode::DecList(declist) => {
             let new_list = sort_declist(declist);
-            let mut semantic_param = SemanticParam::new();
-            for dec in new_list {
-                trans_dec(&dec, None, &mut semantic_param);
+            let mut semantic_param = SemanticParam::new(source_map);
+            // top-level variables are allocated in the global frame,
+            // which is the only frame left after each declaration
+            semantic_param.frame.push(Frame_::new(FrameType::Global));
+            semantic_param.frame[0].address_taken = address_taken_in_declist(&new_list);
+            semantic_param.frame[0].assigned = assigned_in_declist(&new_list);
+            let dec_import_num = new_list
+                .iter()
+                .take_while(|dec| matches!(dec.data, DecData::JsImport(..)))
+                .count();
+            for dec in &new_list[..dec_import_num] {
+                trans_dec(dec, None, &mut semantic_param);
+                semantic_param.frame.truncate(1);
+            }
+            if uses_heap_in_declist(&new_list) {
+                trans_builtins(&mut semantic_param);
+            }
+            for dec in &new_list[dec_import_num..] {
+                trans_dec(dec, None, &mut semantic_param);
+                semantic_param.frame.truncate(1);
+            }
+            trans_global_inits(&mut semantic_param);
+            // functions are not translated in the order of their indices,
+            // because function expressions are lifted while the enclosing function is translated
+            semantic_param
+                .result_modlist
+                .sort_by_key(|module| match **module {
+                    Module_::Func(index, ..) => index,
+                    _ => -1,
+                });
+            // the stack pointer, the heap pointer and the free lists are the first globals,
+            // declared right after the imports and followed by the globals of the program
+            let import_num = semantic_param
+                .result_modlist
+                .iter()
+                .take_while(|module| matches!(***module, Module_::JsImport(..)))
+                .count();
+            semantic_param.result_modlist.insert(
+                import_num,
+                Module_::global_mod(
+                    WasmType::I32,
+                    Exp_::consti32_exp(semantic_param.get_stack_start()),
+                ),
+            );
+            semantic_param.result_modlist.insert(
+                import_num + 1,
+                Module_::global_mod(
+                    WasmType::I32,
+                    Exp_::consti32_exp(semantic_param.get_heap_start()),
+                ),
+            );
+            semantic_param.result_modlist.insert(
+                import_num + 2,
+                Module_::global_mod(
+                    WasmType::I32,
+                    Exp_::consti32_exp(semantic_param.get_free_lists_start()),
+                ),
+            );
+            let global_modlist = take(&mut semantic_param.global_modlist);
+            semantic_param
+                .result_modlist
+                .splice(import_num + 3..import_num + 3, global_modlist);
+            // the types come first so that their indices are the ones used by call_indirect
+            for (index, (params, result)) in semantic_param.func_types.iter().enumerate() {
+                semantic_param
+                    .result_modlist
+                    .insert(index, Module_::type_mod(params.clone(), result.clone()));
+            }
+            if !semantic_param.table_funcs.is_empty() {
+                let funcs = semantic_param
+                    .table_funcs
+                    .iter()
+                    .map(|index| {
+                        Module_::func_mod(
+                            *index,
+                            vec![],
+                            vec![],
+                            WasmType::None,
+                            Stm_::none_stm(),
+                            None,
+                        )
+                    })
+                    .collect();
+                semantic_param
+                    .result_modlist
+                    .push(Module_::table_mod(semantic_param.table_funcs.len() as i32));
+                semantic_param
+                    .result_modlist
+                    .push(Module_::elem_mod(Exp_::consti32_exp(0), funcs));
             }
             let mem_size = semantic_param.get_mem_size();
-            (semantic_param.result_modlist, mem_size)
+            (
+                semantic_param.result_modlist,
+                mem_size,
+                semantic_param.diagnostics,
+            )
         }
         _ => {
             panic!("The parsed ASTNode is not a declist.");
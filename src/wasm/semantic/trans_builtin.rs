@@ -0,0 +1,213 @@
+use crate::wasm::{
+    frame::frame::{FREE_LISTS_INDEX, HEAP_POINTER_INDEX},
+    il::{
+        exp::{Exp, Exp_},
+        module::{Module, Module_},
+        stm::Stm_,
+        util::{BinOper, UniOper, WasmType},
+    },
+};
+
+use super::{entry_map::EnvEntry, laze_type::LazeType_, semantic_param::SemanticParam};
+
+const PAGE_SIZE: i32 = 1 << 16;
+// the size class of a block is kept in the 8 bytes before it, which keeps the block aligned
+const HEADER_SIZE: i32 = 8;
+// the largest block, so that the end of a block and the size class fit in an i32
+const MAX_BLOCK_SIZE: i64 = 1 << 30;
+
+// The built-in functions are declared right after the imports,
+// because the imported functions have to take the first indices.
+pub fn trans_builtins(semantic_data: &mut SemanticParam) {
+    let pointer_type = LazeType_::pointer_type(LazeType_::void_type());
+
+    semantic_data.alloc_func = semantic_data.func_num;
+    semantic_data.venv.add_data(
+        "メモリ確保".to_string(),
+        EnvEntry::Func(
+            semantic_data.func_num,
+            vec![LazeType_::int_type()],
+            pointer_type.clone(),
+        ),
+    );
+    semantic_data
+        .result_modlist
+        .push(trans_alloc(semantic_data.func_num));
+    semantic_data.func_num += 1;
+
+    semantic_data.free_func = semantic_data.func_num;
+    semantic_data.venv.add_data(
+        "メモリ解放".to_string(),
+        EnvEntry::Func(
+            semantic_data.func_num,
+            vec![pointer_type],
+            LazeType_::void_type(),
+        ),
+    );
+    semantic_data
+        .result_modlist
+        .push(trans_free(semantic_data.func_num));
+    semantic_data.func_num += 1;
+}
+
+fn local(index: i32) -> Exp {
+    Exp_::getlocal_exp(WasmType::I32, index)
+}
+
+fn binop(oper: BinOper, left: Exp, right: Exp) -> Exp {
+    Exp_::binop_exp(WasmType::I32, oper, left, right)
+}
+
+// address of the head of the free list for the size class
+fn free_list_head(size_class: Exp) -> Exp {
+    Exp_::add_addr_exp(
+        Exp_::getglobal_exp(WasmType::I32, FREE_LISTS_INDEX),
+        Exp_::mul_addr_exp(size_class, Exp_::consti32_exp(4)),
+    )
+}
+
+// メモリ確保(整数: size) => (void*: block)
+// Blocks are rounded up to a power of two, and each size has its own free list.
+// A block is taken from the free list if there is one,
+// otherwise from the end of the heap, growing the memory when it runs out.
+// A negative size or one larger than MAX_BLOCK_SIZE gives null, like when the memory runs out.
+fn trans_alloc(func_index: i32) -> Module {
+    // locals: 0 size (i64), 1 rounded size, 2 size class, 3 free list head, 4 block, 5 end of the block
+    let body = vec![
+        // a negative size is larger than any other as an unsigned number
+        Stm_::if_stm(
+            Exp_::binop_exp(
+                WasmType::I64,
+                BinOper::GtUnsigned,
+                Exp_::getlocal_exp(WasmType::I64, 0),
+                Exp_::consti64_exp(MAX_BLOCK_SIZE),
+            ),
+            Stm_::return_stm(Exp_::consti32_exp(0)),
+            Stm_::none_stm(),
+        ),
+        Stm_::setlocal_stm(
+            1,
+            Exp_::convert_exp(WasmType::I32, Exp_::getlocal_exp(WasmType::I64, 0)),
+        ),
+        Stm_::setlocal_stm(
+            1,
+            Exp_::if_exp(
+                WasmType::I32,
+                binop(BinOper::LtUnsigned, local(1), Exp_::consti32_exp(8)),
+                Exp_::consti32_exp(8),
+                local(1),
+            ),
+        ),
+        // the smallest power of two that is not less than the size
+        Stm_::setlocal_stm(
+            2,
+            binop(
+                BinOper::Sub,
+                Exp_::consti32_exp(32),
+                Exp_::unaryop_exp(
+                    WasmType::I32,
+                    UniOper::Clz,
+                    binop(BinOper::Sub, local(1), Exp_::consti32_exp(1)),
+                ),
+            ),
+        ),
+        Stm_::setlocal_stm(3, free_list_head(local(2))),
+        Stm_::setlocal_stm(4, Exp_::load_exp(WasmType::I32, local(3))),
+        // a free block holds the next free block of the same size
+        Stm_::if_stm(
+            binop(BinOper::Ne, local(4), Exp_::consti32_exp(0)),
+            Stm_::block_stm(vec![
+                Stm_::store_stm(local(3), Exp_::load_exp(WasmType::I32, local(4))),
+                Stm_::return_stm(local(4)),
+            ]),
+            Stm_::none_stm(),
+        ),
+        Stm_::setlocal_stm(
+            4,
+            Exp_::add_addr_exp(
+                Exp_::getglobal_exp(WasmType::I32, HEAP_POINTER_INDEX),
+                Exp_::consti32_exp(HEADER_SIZE),
+            ),
+        ),
+        Stm_::setlocal_stm(
+            5,
+            Exp_::add_addr_exp(
+                local(4),
+                binop(BinOper::Shl, Exp_::consti32_exp(1), local(2)),
+            ),
+        ),
+        Stm_::if_stm(
+            binop(BinOper::GtUnsigned, local(5), memory_end()),
+            Stm_::if_stm(
+                binop(
+                    BinOper::Eq,
+                    Exp_::memory_grow_exp(binop(
+                        BinOper::DivUnsigned,
+                        binop(
+                            BinOper::Add,
+                            binop(BinOper::Sub, local(5), memory_end()),
+                            Exp_::consti32_exp(PAGE_SIZE - 1),
+                        ),
+                        Exp_::consti32_exp(PAGE_SIZE),
+                    )),
+                    Exp_::consti32_exp(-1),
+                ),
+                // out of memory
+                Stm_::return_stm(Exp_::consti32_exp(0)),
+                Stm_::none_stm(),
+            ),
+            Stm_::none_stm(),
+        ),
+        Stm_::store_stm(
+            binop(BinOper::Sub, local(4), Exp_::consti32_exp(HEADER_SIZE)),
+            local(2),
+        ),
+        Stm_::setglobal_stm(HEAP_POINTER_INDEX, local(5)),
+        Stm_::return_stm(local(4)),
+    ];
+    Module_::func_mod(
+        func_index,
+        vec![WasmType::I64],
+        vec![WasmType::I32; 5],
+        WasmType::I32,
+        Stm_::block_stm(body),
+        None,
+    )
+}
+
+// メモリ解放(void*: block) => ()
+// The block is put at the head of the free list for its size. Null is ignored.
+fn trans_free(func_index: i32) -> Module {
+    // locals: 0 block, 1 free list head
+    let body = vec![Stm_::if_stm(
+        binop(BinOper::Ne, local(0), Exp_::consti32_exp(0)),
+        Stm_::block_stm(vec![
+            Stm_::setlocal_stm(
+                1,
+                free_list_head(Exp_::load_exp(
+                    WasmType::I32,
+                    binop(BinOper::Sub, local(0), Exp_::consti32_exp(HEADER_SIZE)),
+                )),
+            ),
+            Stm_::store_stm(local(0), Exp_::load_exp(WasmType::I32, local(1))),
+            Stm_::store_stm(local(1), local(0)),
+        ]),
+        Stm_::none_stm(),
+    )];
+    Module_::func_mod(
+        func_index,
+        vec![WasmType::I32],
+        vec![WasmType::I32],
+        WasmType::None,
+        Stm_::block_stm(body),
+        None,
+    )
+}
+
+fn memory_end() -> Exp {
+    binop(
+        BinOper::Mul,
+        Exp_::memory_size_exp(),
+        Exp_::consti32_exp(PAGE_SIZE),
+    )
+}
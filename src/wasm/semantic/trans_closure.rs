@@ -0,0 +1,225 @@
+use std::mem::take;
+
+use crate::{
+    ast::{
+        field::{FieldList, Field_},
+        stm::Stm,
+        ty::Type_,
+        var::Var_,
+    },
+    wasm::{
+        frame::frame::ClosureEnv,
+        il::{
+            exp::{Exp, ExpList, Exp_},
+            module::Module_,
+            stm::Stm_,
+            util::{WasmExpTy, WasmType},
+        },
+    },
+};
+
+use super::{
+    laze_type::{FuncRef, LazeType, LazeTypeList, LazeType_},
+    semantic_param::SemanticParam,
+    trans_exp::trans_access_to_exp,
+    trans_funcdec::trans_funcdec,
+    trans_ty::{trans_params, trans_result},
+};
+
+// name of the first parameter of a lifted function, which cannot be written in laze
+const CLOSURE_PARAM_NAME: &str = "<closure>";
+
+// A function expression is lifted to a function that takes the closure record as the first parameter.
+// The record holds the table index of the lifted function followed by the copies of the captured variables.
+// The value of a function expression is the address of the record.
+// A closure that cannot outlive the enclosing function, as found by closure_lifetime,
+// has its record in the frame, which lives until the function returns.
+// The other records are made on the heap with メモリ確保 and are never freed.
+pub fn trans_func_exp(
+    pos: (usize, usize),
+    params: &FieldList,
+    result: &FieldList,
+    func_body: &Stm,
+    semantic_data: &mut SemanticParam,
+) -> WasmExpTy {
+    let func_index = semantic_data.func_num;
+    semantic_data.func_num += 1;
+    let frame_num = semantic_data.frame.len();
+    // the statements of the enclosing statement must not go into the lifted function
+    let outer_temp_stmlist = take(&mut semantic_data.temp_stmlist);
+    semantic_data
+        .closures
+        .push(ClosureEnv::new(semantic_data.venv.entry_num()));
+
+    let new_frame = semantic_data.new_frame(&"無名関数".to_string(), None);
+    // the closure record is a pointer, so it is always the local 0
+    new_frame.env_pointer = 0;
+    let closure_param = Field_::new(
+        pos,
+        Var_::simple_var(pos, CLOSURE_PARAM_NAME.to_string()),
+        Type_::pointer_type(pos, Type_::void_type(pos)),
+    );
+    let mut params_with_closure = vec![closure_param];
+    params_with_closure.append(&mut params.clone());
+    let params_lazetype = trans_params(&params_with_closure, semantic_data);
+    let (return_var, return_type) = trans_result(pos, result, semantic_data);
+    let func_mod = trans_funcdec(
+        &vec![func_body.clone()],
+        &params_with_closure,
+        &params_lazetype,
+        return_var,
+        &return_type,
+        None,
+        func_index,
+        semantic_data,
+    );
+    semantic_data.result_modlist.push(func_mod);
+
+    let closure = semantic_data.closures.pop().unwrap();
+    semantic_data.frame.truncate(frame_num);
+    semantic_data.temp_stmlist = outer_temp_stmlist;
+
+    let table_index = semantic_data.table_funcs.len() as i32;
+    semantic_data.table_funcs.push(func_index);
+    let type_index = semantic_data.func_type_index(
+        LazeType_::list_to_wasm_type(&params_lazetype),
+        return_type.to_wasm_type(),
+    );
+
+    let frame = semantic_data.frame.last_mut().unwrap();
+    let record_local = frame.alloc_local(LazeType_::pointer_type(LazeType_::void_type()));
+    let record_exp = if frame.is_frame_closure(pos) {
+        let access = frame.alloc_record(closure.size);
+        semantic_data.frame_address_exp(&access)
+    } else {
+        Exp_::call_exp(
+            WasmType::I32,
+            semantic_data.alloc_func,
+            vec![Exp_::consti64_exp(closure.size as i64)],
+            None,
+        )
+    };
+    let mut init_stmlist = vec![Stm_::setlocal_stm(record_local, record_exp)];
+    init_stmlist.push(Stm_::store_stm(
+        Exp_::getlocal_exp(WasmType::I32, record_local),
+        Exp_::consti32_exp(table_index),
+    ));
+    for (name, ty, outer_access, env_offset) in &closure.captures {
+        let value = trans_access_to_exp(
+            outer_access,
+            ty,
+            &Var_::simple_var(pos, name.clone()),
+            name,
+            semantic_data,
+        );
+        let dest = Exp_::add_addr_exp(
+            Exp_::getlocal_exp(WasmType::I32, record_local),
+            Exp_::consti32_exp(*env_offset),
+        );
+        // escaped variables are given by their address
+        init_stmlist.push(if ty.escape {
+            Stm_::copy_stm(dest, value, Exp_::consti32_exp(ty.size))
+        } else {
+            Stm_::store_stm(dest, value)
+        });
+    }
+    semantic_data.temp_stmlist.append(&mut init_stmlist);
+
+    WasmExpTy::new_exp(
+        LazeType_::func_type(
+            params_lazetype[1..].to_vec(),
+            return_type,
+            FuncRef::Indirect(type_index),
+        ),
+        Exp_::getlocal_exp(WasmType::I32, record_local),
+    )
+}
+
+// The record is passed as the first argument, and the table index is read from it.
+// local.tee keeps the record so that the closure expression is evaluated only once.
+pub fn trans_closure_call(
+    closure_exp: Exp,
+    type_index: i32,
+    return_type: &LazeType,
+    mut args: ExpList,
+    semantic_data: &mut SemanticParam,
+) -> Exp {
+    let record_local = semantic_data
+        .frame
+        .last_mut()
+        .unwrap()
+        .alloc_local(LazeType_::pointer_type(LazeType_::void_type()));
+    args.insert(
+        0,
+        Exp_::teelocal_exp(WasmType::I32, record_local, closure_exp),
+    );
+    Exp_::call_indirect_exp(
+        return_type.to_wasm_type(),
+        Exp_::load_exp(
+            WasmType::I32,
+            Exp_::getlocal_exp(WasmType::I32, record_local),
+        ),
+        type_index,
+        args,
+    )
+}
+
+// A declared function used as a value is a closure without captured variables.
+// Its record is in the static data, and holds the table index of a function
+// that takes the record before the parameters and calls the declared function with the rest.
+// The record is made once for each function, and set by the start function.
+pub fn trans_func_value(
+    func_num: i32,
+    params: &LazeTypeList,
+    return_type: &LazeType,
+    semantic_data: &mut SemanticParam,
+) -> WasmExpTy {
+    let mut wasm_params = vec![WasmType::I32];
+    wasm_params.append(&mut LazeType_::list_to_wasm_type(params));
+    let wasm_result = return_type.to_wasm_type();
+    let type_index = semantic_data.func_type_index(wasm_params.clone(), wasm_result.clone());
+    let value_ty = LazeType_::func_type(
+        params.clone(),
+        return_type.clone(),
+        FuncRef::Indirect(type_index),
+    );
+    if let Some(&(_, record_address)) = semantic_data
+        .func_values
+        .iter()
+        .find(|(func, _)| *func == func_num)
+    {
+        return WasmExpTy::new_exp(value_ty, Exp_::consti32_exp(record_address));
+    }
+
+    let wrapper_index = semantic_data.func_num;
+    semantic_data.func_num += 1;
+    let args: ExpList = wasm_params
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(index, ty)| Exp_::getlocal_exp(ty.clone(), index as i32))
+        .collect();
+    let body = if wasm_result == WasmType::None {
+        Stm_::call_stm(func_num, args, None)
+    } else {
+        Stm_::return_stm(Exp_::call_exp(wasm_result.clone(), func_num, args, None))
+    };
+    semantic_data.result_modlist.push(Module_::func_mod(
+        wrapper_index,
+        wasm_params,
+        vec![],
+        wasm_result,
+        Stm_::block_stm(vec![body]),
+        None,
+    ));
+
+    let table_index = semantic_data.table_funcs.len() as i32;
+    semantic_data.table_funcs.push(wrapper_index);
+    let record_address = semantic_data.alloc_static(4);
+    semantic_data.static_inits.push(Stm_::store_stm(
+        Exp_::consti32_exp(record_address),
+        Exp_::consti32_exp(table_index),
+    ));
+    semantic_data.func_values.push((func_num, record_address));
+    WasmExpTy::new_exp(value_ty, Exp_::consti32_exp(record_address))
+}
@@ -1,25 +1,34 @@
 use crate::{
     ast::{
-        dec::{Dec, DecData},
+        dec::{ClassMemberList, ClassMember_, Dec, DecData, Dec_, MemberSpecifier},
         exp::ASTExpData,
         field::Field_,
+        op::{oper_to_name, string_to_oper},
         stm::{AssignType, StmList, Stm_},
         ty::{Type, TypeData, Type_},
         var::Var_,
     },
-    wasm::il::{
-        module::{ModuleList, Module_},
-        stm::Stm_ as WASMStm_,
-        util::WasmExpTy,
+    util::diagnostic::DiagnosticCode,
+    wasm::{
+        frame::frame::FrameType,
+        il::{
+            module::{ModuleList, Module_},
+            stm::Stm_ as WASMStm_,
+            util::WasmExpTy,
+        },
     },
 };
 
 use super::{
-    entry_map::{EntryMap, EnvEntry, TemplateMap},
-    laze_type::LazeType_,
+    entry_map::{EntryMap, EnvEntry, TemplateMap, VTable},
+    laze_type::{LazeType, LazeTypeData, LazeTypeList, LazeType_},
     semantic_param::SemanticParam,
+    trans_exp::oper_entry_name,
     trans_funcdec::trans_funcdec,
+    trans_global::trans_global_var,
+    trans_object::{add_object, trans_init_object},
     trans_stm::trans_stm,
+    trans_template::trans_constraints,
     trans_ty::{trans_params, trans_result, trans_ty, trans_var_ty},
     trans_var::get_var_name,
 };
@@ -32,39 +41,99 @@ pub fn trans_dec(
     let mut _result_list: ModuleList = vec![];
     match &dec.data {
         DecData::Var(var, var_ty, init) => {
-            let (new_var, new_var_ty, _object_explist) = trans_var_ty(var, var_ty);
+            if semantic_data.frame.last().unwrap().data == FrameType::Global {
+                return trans_global_var(var, var_ty, init, semantic_data);
+            }
+            let (new_var, new_var_ty, object_explist) = trans_var_ty(var, var_ty, semantic_data);
             let var_lazetype = trans_ty(&new_var_ty, semantic_data);
-            let new_var_access = semantic_data.frame.last_mut().unwrap().alloc(&var_lazetype);
-            semantic_data.venv.add_data(
-                get_var_name(new_var),
-                EnvEntry::Var(var_lazetype, new_var_access),
+            let var_name = get_var_name(new_var, semantic_data);
+            let new_var_access = semantic_data
+                .frame
+                .last_mut()
+                .unwrap()
+                .alloc_var(&var_name, &var_lazetype);
+            // an object given an initial value is not constructed, since the value is copied into it
+            let constructor_args = match init.data {
+                ASTExpData::None => Some(object_explist.cloned().unwrap_or_default()),
+                _ => None,
+            };
+            let mut init_stmlist = trans_init_object(
+                dec.pos,
+                &new_var_access,
+                &var_lazetype,
+                constructor_args.as_ref(),
+                semantic_data,
             );
-            WasmExpTy::new_stm(
-                LazeType_::none_type(),
-                trans_stm(
+            add_object(new_var_access, var_lazetype.clone(), semantic_data);
+            semantic_data
+                .venv
+                .add_data(var_name, EnvEntry::Var(var_lazetype, new_var_access));
+            if !matches!(init.data, ASTExpData::None) {
+                init_stmlist.push(trans_stm(
                     &Stm_::assign_stm(dec.pos, new_var.clone(), init.clone(), AssignType::Normal),
                     semantic_data,
-                ),
+                ));
+            }
+            WasmExpTy::new_stm(
+                LazeType_::none_type(),
+                match init_stmlist.len() {
+                    0 => WASMStm_::none_stm(),
+                    1 => init_stmlist.pop().unwrap(),
+                    _ => WASMStm_::block_stm(init_stmlist),
+                },
             )
         }
         DecData::Func(func_name, params, result, func_body)
         | DecData::Oper(func_name, params, result, func_body) => {
-            if let DecData::Oper(_, _, _, _) = &dec.data {
-                if let None = parent_class {
-                    return WasmExpTy::none();
+            // operators are entered with a name that cannot be written as an ID
+            let entry_name = if let DecData::Oper(..) = &dec.data {
+                match trans_oper_name(dec.pos, func_name, semantic_data) {
+                    Some(entry_name) => entry_name,
+                    None => return WasmExpTy::none(),
                 }
+            } else {
+                func_name.clone()
+            };
+            if func_name.starts_with('~') {
+                semantic_data.error(
+                    DiagnosticCode::InvalidDeclaration,
+                    dec.pos,
+                    format!(
+                        "The destructor {:?} can only be declared in a class.",
+                        func_name
+                    ),
+                );
+                return WasmExpTy::none();
             }
             semantic_data.new_frame(&func_name, parent_class);
             let params_lazetype = trans_params(&params, semantic_data);
             let (return_var, return_lazetype) = trans_result(dec.pos, result, semantic_data);
-            // TODO: need to implement function overloading
+            // the index is taken before the body,
+            // since the function expressions in the body are lifted to functions of their own
+            let func_index = semantic_data.func_num;
+            semantic_data.func_num += 1;
+            // functions with the same name are overloaded by their parameter types
+            let redefined = semantic_data
+                .venv
+                .get_overloads(&entry_name)
+                .into_iter()
+                .any(|entry| match entry {
+                    EnvEntry::Func(_, other_params, _) => *other_params == params_lazetype,
+                    _ => false,
+                });
+            if redefined {
+                semantic_data.error(
+                    DiagnosticCode::InvalidDeclaration,
+                    dec.pos,
+                    format!(
+                        "A function named {:?} with the same parameter types is already declared.",
+                        func_name
+                    ),
+                );
+            }
             semantic_data.venv.add_data(
-                func_name.clone(),
-                EnvEntry::Func(
-                    semantic_data.func_num,
-                    params_lazetype.clone(),
-                    return_lazetype.clone(),
-                ),
+                entry_name,
+                EnvEntry::Func(func_index, params_lazetype.clone(), return_lazetype.clone()),
             );
             let export_name = if func_name == "実行" {
                 Some("main".to_string())
@@ -78,10 +147,10 @@ pub fn trans_dec(
                 return_var,
                 &return_lazetype,
                 export_name,
+                func_index,
                 semantic_data,
             );
             semantic_data.result_modlist.push(func_mod);
-            semantic_data.func_num += 1;
             WasmExpTy::none()
         }
         DecData::JsImport(func_name, params, result, module_name, name) => {
@@ -118,36 +187,78 @@ pub fn trans_dec(
             WasmExpTy::none()
         }
         DecData::JsExport(func_name, export_name) => {
-            let entry = semantic_data.venv.get_data(&func_name).expect(
-                format_args!(
-                    "Could not find function {:?} to export: {:?}",
-                    func_name, dec.pos
-                )
-                .as_str()
-                .unwrap(),
-            );
-            let export_mod = if let EnvEntry::Func(index, _, _) = entry {
-                Module_::jsexport_mod(export_name.clone(), *index)
-            } else {
-                Module_::none_mod()
+            if semantic_data.venv.get_overloads(func_name).len() > 1 {
+                semantic_data.error(
+                    DiagnosticCode::InvalidDeclaration,
+                    dec.pos,
+                    format!("Cannot export {:?} because it is overloaded.", func_name),
+                );
+                return WasmExpTy::none();
+            }
+            let export_mod = match semantic_data.venv.get_data(func_name) {
+                Some(EnvEntry::Func(index, _, _)) => {
+                    Module_::jsexport_mod(export_name.clone(), *index)
+                }
+                Some(_) => {
+                    semantic_data.error(
+                        DiagnosticCode::NotAFunction,
+                        dec.pos,
+                        format!(
+                            "Cannot export {:?} because it is not a function.",
+                            func_name
+                        ),
+                    );
+                    return WasmExpTy::none();
+                }
+                None => {
+                    semantic_data.error(
+                        DiagnosticCode::UndefinedVariable,
+                        dec.pos,
+                        format!("Could not find a function named {:?} to export.", func_name),
+                    );
+                    return WasmExpTy::none();
+                }
             };
             semantic_data.result_modlist.push(export_mod);
             WasmExpTy::none()
         }
-        DecData::Class(class_name, member_list, _inheritance) => {
-            let mut members_entrymap = EntryMap::new();
-            let mut class_size = 0;
+        DecData::Class(class_name, member_list, inheritance) => {
+            let base_class = trans_base_class(dec.pos, inheritance, semantic_data);
+            // the members of the base class come first,
+            // so that a pointer to this class can be used as a pointer to the base class
+            let (mut members_entrymap, mut class_size, base_vtable) = match &base_class {
+                Some((_, base_members, base_size, base_vtable)) => {
+                    (base_members.clone(), *base_size, base_vtable.clone())
+                }
+                None => (EntryMap::new(), 0, None),
+            };
+            // the overriding methods replace the functions of the base class in the same slots
+            let mut vtable_funcs = match &base_vtable {
+                Some(vtable) => vtable.funcs.clone(),
+                None => vec![],
+            };
+            let base_class = base_class.map(|(base_name, _, _, _)| base_name);
+            let member_list = &add_default_constructor(dec.pos, class_name, member_list);
             let mut default_assignlist: StmList = vec![];
+            let mut method_indices = vec![];
             // enter all members into members_entrymap
             for member in member_list {
                 match &member.dec.data {
                     DecData::Var(var, ty, init) => {
-                        let member_ty = trans_ty(ty, semantic_data);
-                        class_size += member_ty.size;
+                        // a pointer member is declared as <class>: *<name>
+                        let (member_var, member_ty, _) = trans_var_ty(var, ty, semantic_data);
+                        let member_ty = trans_ty(&member_ty, semantic_data);
+                        let member_size = member_ty.size;
                         members_entrymap.add_data(
-                            get_var_name(var),
-                            EnvEntry::Member(member.specifier, member_ty, class_size),
+                            get_var_name(member_var, semantic_data),
+                            EnvEntry::Member(
+                                member.specifier,
+                                member_ty,
+                                class_size,
+                                class_name.clone(),
+                            ),
                         );
+                        class_size += member_size;
                         // initializtion in constructor
                         match &init.data {
                             ASTExpData::None => {}
@@ -163,9 +274,63 @@ pub fn trans_dec(
                     }
                     DecData::Func(func_name, params, result, _)
                     | DecData::Oper(func_name, params, result, _) => {
+                        let func_name = if let DecData::Oper(..) = &member.dec.data {
+                            trans_oper_name(member.dec.pos, func_name, semantic_data)
+                                .unwrap_or_else(|| func_name.clone())
+                        } else {
+                            func_name.clone()
+                        };
+                        let func_name = &func_name;
+                        if (*func_name == *class_name && !result.is_empty())
+                            || (func_name.starts_with('~') && func_name[1..] != *class_name)
+                        {
+                            semantic_data.error(
+                                DiagnosticCode::InvalidDeclaration,
+                                member.dec.pos,
+                                if func_name.starts_with('~') {
+                                    format!(
+                                        "The destructor of {:?} must be named ~{}.",
+                                        class_name, class_name
+                                    )
+                                } else {
+                                    format!(
+                                        "The constructor of {:?} cannot return a value.",
+                                        class_name
+                                    )
+                                },
+                            );
+                        }
                         let mut params_lazetype = trans_params(&params, semantic_data);
                         params_lazetype.insert(0, LazeType_::pointer_type(LazeType_::void_type()));
                         let (_, return_type) = trans_result(dec.pos, result, semantic_data);
+                        let vtable_slot = match members_entrymap.get_data(func_name) {
+                            Some(EnvEntry::Method(
+                                _,
+                                _,
+                                base_params,
+                                base_return,
+                                Some(slot),
+                                _,
+                            )) => {
+                                if *base_params != params_lazetype || *base_return != return_type {
+                                    semantic_data.error(
+                                        DiagnosticCode::TypeMismatch,
+                                        member.dec.pos,
+                                        format!(
+                                            "{:?} overrides a virtual method with a different type.",
+                                            func_name
+                                        ),
+                                    );
+                                }
+                                vtable_funcs[*slot as usize] = semantic_data.func_num;
+                                Some(*slot)
+                            }
+                            _ if member.is_virtual => {
+                                vtable_funcs.push(semantic_data.func_num);
+                                Some(vtable_funcs.len() as i32 - 1)
+                            }
+                            _ => None,
+                        };
                         members_entrymap.add_data(
                             func_name.clone(),
                             EnvEntry::Method(
@@ -173,25 +338,69 @@ pub fn trans_dec(
                                 semantic_data.func_num,
                                 params_lazetype,
                                 return_type,
+                                vtable_slot,
+                                class_name.clone(),
                             ),
                         );
+                        method_indices.push(semantic_data.func_num);
+                        semantic_data.func_num += 1;
                     }
                     _ => {}
                 }
             }
+            let vtable = if vtable_funcs.is_empty() {
+                None
+            } else {
+                // the vtable pointer is put after the members of the first class that has one
+                let pointer_offset = match &base_vtable {
+                    Some(vtable) => vtable.pointer_offset,
+                    None => {
+                        class_size += 8;
+                        class_size - 8
+                    }
+                };
+                let table_start = semantic_data.table_funcs.len() as i32;
+                semantic_data.table_funcs.extend(&vtable_funcs);
+                Some(VTable {
+                    pointer_offset,
+                    table_start,
+                    funcs: vtable_funcs,
+                })
+            };
+            // the types of the methods were made before the size of this class was known
+            for entry in members_entrymap.entries_mut() {
+                if let EnvEntry::Method(_, _, params, return_type, _, _) = entry {
+                    for ty in params.iter_mut().chain([return_type]) {
+                        set_class_size(ty, class_name, class_size);
+                    }
+                }
+            }
+            // an instance of a class template is keyed by the types given to its type parameters
+            let template_key: LazeTypeList = match semantic_data.tenv.get_data(class_name) {
+                Some(EnvEntry::Template(_, _, _, type_param_names, _)) => type_param_names
+                    .iter()
+                    .map(|type_param| match semantic_data.tenv.get_data(type_param) {
+                        Some(EnvEntry::Poly(ty)) => ty.clone(),
+                        _ => LazeType_::none_type(),
+                    })
+                    .collect(),
+                _ => vec![],
+            };
             let class_entry = semantic_data.tenv.get_mut_data(class_name);
             let parent_class_type: Type;
-            if let Some(EnvEntry::Template(_, template_map, _, _)) = class_entry {
+            if let Some(EnvEntry::Template(_, template_map, _, _, _)) = class_entry {
                 if let Some(class_type) = parent_class {
                     if let TypeData::Template(_, type_params) = &class_type.data {
                         parent_class_type =
                             Type_::template_type(dec.pos, class_name.clone(), type_params.clone());
                         template_map.add_data(
-                            type_params.clone(),
+                            template_key,
                             EnvEntry::Class(
                                 class_name.clone(),
                                 members_entrymap.clone(),
                                 class_size,
+                                base_class,
+                                vtable,
                             ),
                         );
                     } else {
@@ -204,14 +413,22 @@ pub fn trans_dec(
                 parent_class_type = Type_::name_type(dec.pos, class_name.clone());
                 semantic_data.tenv.add_data(
                     class_name.clone(),
-                    EnvEntry::Class(class_name.clone(), members_entrymap.clone(), class_size),
+                    EnvEntry::Class(
+                        class_name.clone(),
+                        members_entrymap.clone(),
+                        class_size,
+                        base_class,
+                        vtable,
+                    ),
                 );
             }
 
+            let mut method_indices = method_indices.into_iter();
             for member in member_list {
                 match &member.dec.data {
                     DecData::Func(func_name, params, result, func_body)
                     | DecData::Oper(func_name, params, result, func_body) => {
+                        let func_index = method_indices.next().unwrap();
                         let _new_frame =
                             semantic_data.new_frame(func_name, Some(&parent_class_type));
                         let self_param = Field_::new(
@@ -224,26 +441,37 @@ pub fn trans_dec(
                         let params_lazetype = trans_params(&params_with_self, semantic_data);
                         let (return_var, return_type) =
                             trans_result(dec.pos, result, semantic_data);
+                        // the initial values of the members are set before the body of the constructor
+                        let func_body = if func_name == class_name {
+                            default_assignlist
+                                .iter()
+                                .chain(func_body)
+                                .cloned()
+                                .collect()
+                        } else {
+                            func_body.clone()
+                        };
                         let func_mod = trans_funcdec(
-                            func_body,
+                            &func_body,
                             &params_with_self,
                             &params_lazetype,
                             return_var,
                             &return_type,
                             None,
+                            func_index,
                             semantic_data,
                         );
                         semantic_data.result_modlist.push(func_mod);
-                        semantic_data.func_num += 1;
                     }
                     _ => {}
                 }
             }
             WasmExpTy::none()
         }
-        DecData::Template(original_dec, type_params) => {
+        DecData::Template(original_dec, type_params, constraints) => {
             match &original_dec.data {
                 DecData::Class(name, _, _) | DecData::Func(name, _, _, _) => {
+                    let constraints = trans_constraints(constraints, type_params, semantic_data);
                     semantic_data.tenv.add_data(
                         name.clone(),
                         EnvEntry::Template(
@@ -251,13 +479,131 @@ pub fn trans_dec(
                             TemplateMap::new(),
                             semantic_data.venv.clone(),
                             type_params.clone(),
+                            constraints,
                         ),
                     );
                 }
+                DecData::Constraint(name, requirements) => {
+                    if type_params.len() != 1 || !constraints.is_empty() {
+                        semantic_data.error(
+                            DiagnosticCode::InvalidDeclaration,
+                            dec.pos,
+                            format!("制約 {:?} must take exactly one type parameter.", name),
+                        );
+                        return WasmExpTy::none();
+                    }
+                    for requirement in requirements {
+                        if let DecData::Oper(oper_name, _, _, _) = &requirement.data {
+                            let _ = trans_oper_name(requirement.pos, oper_name, semantic_data);
+                        }
+                    }
+                    semantic_data.tenv.add_data(
+                        name.clone(),
+                        EnvEntry::Constraint(type_params[0].clone(), requirements.clone()),
+                    );
+                }
                 _ => {}
             };
             WasmExpTy::none()
         }
+        DecData::Constraint(name, _) => {
+            semantic_data.error(
+                DiagnosticCode::InvalidDeclaration,
+                dec.pos,
+                format!(
+                    "制約 {:?} needs a type parameter, as in 型<T>: 制約: {}.",
+                    name, name
+                ),
+            );
+            WasmExpTy::none()
+        }
         DecData::None => WasmExpTy::none(),
     }
 }
+
+// A class that has initial values of members but no constructor is given an empty one,
+// so that the initial values are set when its objects are constructed.
+fn add_default_constructor(
+    pos: (usize, usize),
+    class_name: &String,
+    member_list: &ClassMemberList,
+) -> ClassMemberList {
+    let mut member_list = member_list.clone();
+    let has_initial_values = member_list.iter().any(|member| {
+        matches!(&member.dec.data, DecData::Var(_, _, init) if !matches!(init.data, ASTExpData::None))
+    });
+    let has_constructor = member_list
+        .iter()
+        .any(|member| matches!(&member.dec.data, DecData::Func(name, ..) if name == class_name));
+    if has_initial_values && !has_constructor {
+        member_list.push(Box::new(ClassMember_ {
+            specifier: MemberSpecifier::Public,
+            is_virtual: false,
+            dec: Dec_::func_dec(pos, class_name.clone(), vec![], vec![], vec![]),
+        }));
+    }
+    member_list
+}
+
+fn set_class_size(ty: &mut LazeType, class_name: &String, class_size: i32) {
+    match &mut ty.data {
+        LazeTypeData::Class(name) if name == class_name => ty.size = class_size,
+        LazeTypeData::Pointer(pointee) => set_class_size(pointee, class_name, class_size),
+        LazeTypeData::Array(element, len) => {
+            set_class_size(element, class_name, class_size);
+            ty.size = element.size * *len;
+        }
+        _ => {}
+    }
+}
+
+// Returns the name of the entry of an operator declaration.
+fn trans_oper_name(
+    pos: (usize, usize),
+    oper_name: &str,
+    semantic_data: &mut SemanticParam,
+) -> Option<String> {
+    match oper_to_name(&string_to_oper(&format!("{}Op", oper_name))) {
+        Some(_) => Some(oper_entry_name(oper_name)),
+        None => {
+            semantic_data.error(
+                DiagnosticCode::InvalidOperator,
+                pos,
+                format!("{:?} is not an operator that can be declared.", oper_name),
+            );
+            None
+        }
+    }
+}
+
+// Returns the name, members, size and vtable of the base class.
+fn trans_base_class(
+    pos: (usize, usize),
+    inheritance: &[String],
+    semantic_data: &mut SemanticParam,
+) -> Option<(String, EntryMap, i32, Option<VTable>)> {
+    if inheritance.len() > 1 {
+        semantic_data.error(
+            DiagnosticCode::InvalidDeclaration,
+            pos,
+            "Laze does not support multiple inheritance.".to_string(),
+        );
+    }
+    let base_name = inheritance.first()?;
+    match semantic_data.tenv.get_data(base_name) {
+        Some(EnvEntry::Class(_, base_members, base_size, _, base_vtable)) => Some((
+            base_name.clone(),
+            base_members.clone(),
+            *base_size,
+            base_vtable.clone(),
+        )),
+        _ => {
+            semantic_data.error(
+                DiagnosticCode::NotAClass,
+                pos,
+                format!("Could not find the base class {:?}.", base_name),
+            );
+            None
+        }
+    }
+}
@@ -1,14 +1,10 @@
-use std::{
-    i32,
-    io::{stderr, Write},
-};
-
 use crate::{
     ast::{
         exp::{ASTExp, ASTExpData, ASTExpList, ASTExp_},
-        op::Oper,
+        op::{oper_to_name, Oper},
         var::{Var, VarData},
     },
+    util::diagnostic::DiagnosticCode,
     wasm::{
         frame::frame::FrameAccess,
         il::{
@@ -20,9 +16,12 @@ use crate::{
 };
 
 use super::{
+    entry_map::EnvEntry,
     laze_type::{comp_type_binop, LazeType, LazeTypeData, LazeTypeList, LazeType_},
     semantic_param::SemanticParam,
-    trans_var::trans_suffix_var_to_addr,
+    trans_closure::trans_func_exp,
+    trans_object::trans_new_exp,
+    trans_var::{resolve_overload, trans_method_call, trans_suffix_var_to_addr, trans_var_value},
 };
 
 pub fn trans_exp(exp: &ASTExp, semantic_data: &mut SemanticParam) -> WasmExpTy {
@@ -30,26 +29,35 @@ pub fn trans_exp(exp: &ASTExp, semantic_data: &mut SemanticParam) -> WasmExpTy {
         ASTExpData::Array(explist) => {
             let (access, ty, mut init_stmlist) = trans_arrayexp_to_stm(explist, semantic_data);
             semantic_data.temp_stmlist.append(&mut init_stmlist);
-            WasmExpTy::new_exp(ty, Exp_::consti32_exp(access.get_address()))
+            WasmExpTy::new_exp(ty, semantic_data.frame_address_exp(&access))
         }
         ASTExpData::BinOp(operlist, explist) => {
             let mut operlist_iter = operlist.iter();
             let mut explist_iter = explist.iter();
             let mut result_exp;
             let mut result_ty;
-            if let Some(exp) = explist_iter.next() {
-                (result_ty, result_exp) =
-                    trans_exp(exp, semantic_data).ty_exp(format_args!("{:?}", exp.pos).to_string());
+            let mut left_exp = explist_iter.next();
+            if let Some(exp) = left_exp {
+                (result_ty, result_exp) = trans_exp(exp, semantic_data).ty_exp(
+                    &mut semantic_data.diagnostics,
+                    exp.pos,
+                    "The first operand is not a value.",
+                );
             } else {
                 result_ty = LazeType_::none_type();
                 result_exp = Exp_::none_exp();
             }
-            while let Some(right_exp) = explist_iter.next() {
+            while let (Some(left), Some(right_exp)) = (left_exp, explist_iter.next()) {
                 if let Some(op) = operlist_iter.next() {
                     (result_ty, result_exp) =
-                        trans_binop_exp(op, result_exp, result_ty, right_exp, semantic_data)
-                            .ty_exp(format_args!("{:?}", exp.pos).to_string());
+                        trans_binop_exp(op, result_exp, result_ty, left, right_exp, semantic_data)
+                            .ty_exp(
+                                &mut semantic_data.diagnostics,
+                                right_exp.pos,
+                                &format!("The operator {:?} did not give a value.", op),
+                            );
                 }
+                left_exp = Some(right_exp);
             }
             WasmExpTy::new_exp(result_ty, result_exp)
         }
@@ -64,9 +72,8 @@ pub fn trans_exp(exp: &ASTExp, semantic_data: &mut SemanticParam) -> WasmExpTy {
         ASTExpData::Char(c) => {
             WasmExpTy::new_exp(LazeType_::char_type(), Exp_::consti32_exp(*c as i32))
         }
-        ASTExpData::Func(_params, _result, _func_body) => {
-            // Function Expression needs to be supported
-            WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
+        ASTExpData::Func(params, result, func_body) => {
+            trans_func_exp(exp.pos, params, result, func_body, semantic_data)
         }
         ASTExpData::Int(i) => {
             let int_data = i.parse::<i64>();
@@ -76,6 +83,7 @@ pub fn trans_exp(exp: &ASTExp, semantic_data: &mut SemanticParam) -> WasmExpTy {
                 WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
             }
         }
+        ASTExpData::New(ty, args) => trans_new_exp(exp.pos, ty, args, semantic_data),
         ASTExpData::Paren(exp) => trans_exp(exp, semantic_data),
         ASTExpData::Real(r) => {
             let real_data = r.parse::<f64>();
@@ -99,24 +107,31 @@ pub fn trans_exp(exp: &ASTExp, semantic_data: &mut SemanticParam) -> WasmExpTy {
         ),
         ASTExpData::String(exp) => {
             // using unwrap
-            let string_address = semantic_data.get_mem_size();
-            let string_type = LazeType_::array_type(LazeType_::char_type(), exp.len() as i32);
-            for c in exp.chars() {
-                let access = semantic_data
-                    .frame
-                    .last_mut()
-                    .unwrap()
-                    .alloc_inframe(&LazeType_::char_type());
+            let string_type =
+                LazeType_::array_type(LazeType_::char_type(), exp.chars().count() as i32);
+            let string_access = semantic_data
+                .frame
+                .last_mut()
+                .unwrap()
+                .alloc_inframe(&string_type);
+            for (index, c) in exp.chars().enumerate() {
+                let char_access = FrameAccess::InFrame(
+                    string_access.get_frame_offset() + index as i32 * LazeType_::char_type().size,
+                );
                 semantic_data.temp_stmlist.push(Stm_::store_stm(
-                    Exp_::consti32_exp(access.get_address()),
+                    semantic_data.frame_address_exp(&char_access),
                     Exp_::consti32_exp(c as i32),
                 ));
             }
-            WasmExpTy::new_exp(string_type, Exp_::consti32_exp(string_address))
+            WasmExpTy::new_exp(string_type, semantic_data.frame_address_exp(&string_access))
         }
         ASTExpData::UnaryOp(oper_list, calc_exp) => {
             if oper_list.len() > 1 {
-                let _ = writeln!(stderr(), "Warning: Laze doesn't support multiple unary operators, put parantheses around the expression: {:?}", exp.pos);
+                semantic_data.warning(
+                    DiagnosticCode::MultipleUnaryOperators,
+                    exp.pos,
+                    "Laze doesn't support multiple unary operators, put parantheses around the expression.".to_string(),
+                );
                 trans_unaryop_exp(&oper_list[0], calc_exp, semantic_data)
             } else if oper_list.len() == 1 {
                 trans_unaryop_exp(&oper_list[0], calc_exp, semantic_data)
@@ -125,9 +140,8 @@ pub fn trans_exp(exp: &ASTExp, semantic_data: &mut SemanticParam) -> WasmExpTy {
             }
         }
         ASTExpData::Var(var) => match &var.data {
-            VarData::Simple(..) => trans_suffix_var_to_addr(var, &vec![], semantic_data),
-            VarData::SuffixVar(var, suffixlist) => {
-                trans_suffix_var_to_addr(&var, &suffixlist, semantic_data)
+            VarData::Simple(..) | VarData::SuffixVar(..) | VarData::Template(..) => {
+                trans_var_value(var, semantic_data)
             }
             VarData::Pointer(pointer_var) => trans_exp(
                 &ASTExp_::unaryop_exp(
@@ -152,28 +166,44 @@ pub fn trans_explist(
     let mut tylist_result = vec![];
 
     for exp in explist {
-        let (ty, exp) = trans_exp(exp, semantic_data).ty_exp("".to_string());
+        let (ty, exp) = trans_exp(exp, semantic_data).ty_exp(
+            &mut semantic_data.diagnostics,
+            exp.pos,
+            "The expression did not give a value.",
+        );
         tylist_result.push(ty);
         explist_result.push(exp);
     }
     (tylist_result, explist_result)
 }
 
-pub fn trans_access_to_exp(access: &FrameAccess, var: &Var, name: &String) -> Exp {
+pub fn trans_access_to_exp(
+    access: &FrameAccess,
+    ty: &LazeType,
+    var: &Var,
+    name: &String,
+    semantic_data: &mut SemanticParam,
+) -> Exp {
     match access {
-        FrameAccess::InFrame(memory_offset, frame_offset) => {
-            Exp_::consti32_exp(memory_offset + frame_offset)
-        }
-        FrameAccess::InLocal(index) => Exp_::getlocal_exp(WasmType::I32, *index),
-        FrameAccess::InGlobal(index) => Exp_::getglobal_exp(WasmType::I32, *index),
-        FrameAccess::EscapedParam(_, memory_offset, frame_offset) => {
-            Exp_::consti32_exp(memory_offset + frame_offset)
+        // memory also holds the scalars whose address is taken, and the record of a closure
+        // holds every captured variable, so the value is loaded unless it is escaped
+        FrameAccess::InFrame(..)
+        | FrameAccess::EscapedParam(..)
+        | FrameAccess::InEnv(..)
+        | FrameAccess::InStatic(..) => {
+            if ty.escape {
+                semantic_data.frame_address_exp(access)
+            } else {
+                Exp_::load_exp(ty.to_wasm_type(), semantic_data.frame_address_exp(access))
+            }
         }
+        FrameAccess::InLocal(index) => Exp_::getlocal_exp(ty.to_wasm_type(), *index),
+        FrameAccess::InGlobal(index) => Exp_::getglobal_exp(ty.to_wasm_type(), *index),
         FrameAccess::None => {
-            let _ = writeln!(
-                stderr(),
-                "{name} does not exist in frame, local, global, or parameters: {:?}",
-                var.pos
+            semantic_data.error(
+                DiagnosticCode::UndefinedVariable,
+                var.pos,
+                format!("{name} does not exist in frame, local, global, or parameters."),
             );
             Exp_::none_exp()
         }
@@ -187,32 +217,46 @@ pub fn trans_arrayexp_to_stm(
     if explist.len() > 0 {
         let mut init_stmlist = vec![];
         let flat_explist = flatten_explist(explist);
-        let first_elem = trans_exp(&flat_explist[0], semantic_data);
+        let (first_ty, first_exp) = trans_exp(&flat_explist[0], semantic_data).ty_exp(
+            &mut semantic_data.diagnostics,
+            flat_explist[0].pos,
+            "The expression did not give a value.",
+        );
         let first_access = semantic_data
             .frame
             .last_mut()
             .unwrap()
-            .alloc(&first_elem.ty);
+            .alloc_inframe(&first_ty);
+        init_stmlist.push(Stm_::store_stm(
+            semantic_data.frame_address_exp(&first_access),
+            first_exp,
+        ));
         for exp in flat_explist.iter().skip(1) {
             let elem = trans_exp(exp, semantic_data);
-            if first_elem.ty != elem.ty {
-                let _ = writeln!(
-                    stderr(),
+            if first_ty != elem.ty {
+                semantic_data.error(
+                    DiagnosticCode::TypeMismatch,
+                    exp.pos,
                     "This expression's type does not match with the first element's type."
+                        .to_string(),
                 );
             } else {
                 let access = semantic_data
                     .frame
                     .last_mut()
                     .unwrap()
-                    .alloc_inframe(&first_elem.ty);
+                    .alloc_inframe(&first_ty);
                 init_stmlist.push(Stm_::store_stm(
-                    Exp_::consti32_exp(access.get_address()),
-                    elem.exp("".to_string()),
+                    semantic_data.frame_address_exp(&access),
+                    elem.exp(
+                        &mut semantic_data.diagnostics,
+                        exp.pos,
+                        "The expression did not give a value.",
+                    ),
                 ));
             }
         }
-        (first_access, first_elem.ty, init_stmlist)
+        (first_access, first_ty, init_stmlist)
     } else {
         (FrameAccess::None, LazeType_::none_type(), vec![])
     }
@@ -235,43 +279,125 @@ pub fn trans_binop_exp(
     oper: &Oper,
     left: Exp,
     left_ty: LazeType,
+    left_operand: &ASTExp,
     right: &ASTExp,
     semantic_data: &mut SemanticParam,
 ) -> WasmExpTy {
-    let (right_ty, right_exp) = trans_exp(right, semantic_data).ty_exp("".to_string());
-    if let Some((ty, lhs, rhs)) = comp_type_binop(left_ty, left, right_ty, right_exp) {
+    if let Oper::And | Oper::Or = oper {
+        return trans_logical_exp(oper, left, &left_ty, left_operand, right, semantic_data);
+    }
+    let (right_ty, right_exp) = trans_exp(right, semantic_data).ty_exp(
+        &mut semantic_data.diagnostics,
+        right.pos,
+        "The expression did not give a value.",
+    );
+    if is_user_type(&left_ty) || is_user_type(&right_ty) {
+        return trans_user_oper(
+            oper,
+            vec![(left_ty, left), (right_ty, right_exp)],
+            right.pos,
+            semantic_data,
+        );
+    }
+    // the operand that did not give a value is already reported
+    let reported = left_ty.data == LazeTypeData::None || right_ty.data == LazeTypeData::None;
+    if let Some((ty, lhs, rhs)) = comp_type_binop(
+        left_ty,
+        left,
+        right_ty,
+        right_exp,
+        right.pos,
+        &mut semantic_data.diagnostics,
+    ) {
         let wasm_type = ty.to_wasm_type();
         match oper {
-            Oper::Plus
-            | Oper::Minus
-            | Oper::Times
-            | Oper::Divide
-            | Oper::Mod
-            | Oper::Ge
-            | Oper::Gt
-            | Oper::Le
-            | Oper::Lt
-            | Oper::Eq
-            | Oper::Neq
-            | Oper::And
-            | Oper::Or => WasmExpTy::new_exp(
-                ty,
-                Exp_::binop_exp(wasm_type, BinOper::from_ast(&oper), lhs, rhs),
+            Oper::Plus | Oper::Minus | Oper::Times | Oper::Divide | Oper::Mod => {
+                WasmExpTy::new_exp(
+                    ty,
+                    Exp_::binop_exp(wasm_type, BinOper::from_ast(oper), lhs, rhs),
+                )
+            }
+            // the wasm type of a comparison is the one of the operands, but the result is 真偽
+            Oper::Ge | Oper::Gt | Oper::Le | Oper::Lt | Oper::Eq | Oper::Neq => WasmExpTy::new_exp(
+                LazeType_::bool_type(),
+                Exp_::binop_exp(wasm_type, BinOper::from_ast(oper), lhs, rhs),
             ),
             _ => {
-                let _ = writeln!(
-                    stderr(),
-                    "This operator is not functional right now: {:?}",
-                    right.pos
+                semantic_data.error(
+                    DiagnosticCode::InvalidOperator,
+                    right.pos,
+                    "This operator is not functional right now.".to_string(),
                 );
                 WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
             }
         }
     } else {
+        if !reported {
+            semantic_data.error(
+                DiagnosticCode::TypeMismatch,
+                right.pos,
+                "The types of the operands do not match.".to_string(),
+            );
+        }
         WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
     }
 }
 
+// The right operand of && and || is evaluated only when the left one does not decide the result.
+fn trans_logical_exp(
+    oper: &Oper,
+    left: Exp,
+    left_ty: &LazeType,
+    left_operand: &ASTExp,
+    right: &ASTExp,
+    semantic_data: &mut SemanticParam,
+) -> WasmExpTy {
+    let temp_len = semantic_data.temp_stmlist.len();
+    let (right_ty, right_exp) = trans_exp(right, semantic_data).ty_exp(
+        &mut semantic_data.diagnostics,
+        right.pos,
+        "The expression did not give a value.",
+    );
+    let mut is_bool = true;
+    for (operand, ty) in [(left_operand, left_ty), (right, &right_ty)] {
+        match ty.data {
+            LazeTypeData::Bool => {}
+            // the operand that did not give a value is already reported
+            LazeTypeData::None => is_bool = false,
+            _ => {
+                semantic_data.error(
+                    DiagnosticCode::TypeMismatch,
+                    operand.pos,
+                    "The operands of && and || must be 真偽.".to_string(),
+                );
+                is_bool = false;
+            }
+        }
+    }
+    if !is_bool {
+        // the statements of the right operand are dropped with it
+        semantic_data.temp_stmlist.truncate(temp_len);
+        return WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp());
+    }
+    // The statements that the right operand needs are in a block with it, so they run only
+    // when it is evaluated, and the operands before this expression are still evaluated first.
+    let right_stmlist = semantic_data.temp_stmlist.split_off(temp_len);
+    let right_exp = if right_stmlist.is_empty() {
+        right_exp
+    } else {
+        Exp_::block_exp(WasmType::I32, right_stmlist, right_exp)
+    };
+    let (then_exp, else_exp) = if matches!(oper, Oper::And) {
+        (right_exp, Exp_::consti32_exp(0))
+    } else {
+        (Exp_::consti32_exp(1), right_exp)
+    };
+    WasmExpTy::new_exp(
+        LazeType_::bool_type(),
+        Exp_::if_exp(WasmType::I32, left, then_exp, else_exp),
+    )
+}
+
 pub fn trans_unaryop_exp(
     oper: &Oper,
     exp: &ASTExp,
@@ -280,44 +406,80 @@ pub fn trans_unaryop_exp(
     match oper {
         Oper::Deref => match &exp.data {
             ASTExpData::Var(var) => {
-                let (result_ty, result_exp) =
-                    trans_suffix_var_to_addr(&var, &vec![], semantic_data).ty_exp("".to_string());
+                let (result_ty, result_exp) = trans_suffix_var_to_addr(var, &vec![], semantic_data)
+                    .ty_exp(
+                        &mut semantic_data.diagnostics,
+                        var.pos,
+                        "The variable does not have an address.",
+                    );
                 if let LazeTypeData::Pointer(pointer_ty) = result_ty.data {
                     let wasm_ty = pointer_ty.to_wasm_type();
                     WasmExpTy::new_exp(pointer_ty, Exp_::load_exp(wasm_ty, result_exp))
                 } else {
-                    let _ = writeln!(
-                        stderr(),
-                        "Cannot dereference a non-pointer type: {:?}",
-                        exp.pos
+                    semantic_data.error(
+                        DiagnosticCode::NotAPointer,
+                        exp.pos,
+                        "Cannot dereference a non-pointer type.".to_string(),
                     );
                     WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
                 }
             }
             _ => {
-                let _ = writeln!(
-                    stderr(),
-                    "Cannot dereference a non-variable expression: {:?}",
-                    exp.pos
+                semantic_data.error(
+                    DiagnosticCode::NotAddressable,
+                    exp.pos,
+                    "Cannot dereference a non-variable expression.".to_string(),
                 );
                 WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
             }
         },
         Oper::Address => match &exp.data {
             // handle non-framed variables
-            ASTExpData::Var(var) => trans_suffix_var_to_addr(&var, &vec![], semantic_data),
+            ASTExpData::Var(var) => {
+                // a scalar whose address is taken was put in memory, and its access gives the value
+                if let Some((ty, access)) = memory_scalar(var, semantic_data) {
+                    return WasmExpTy::new_exp(
+                        LazeType_::pointer_type(ty),
+                        semantic_data.frame_address_exp(&access),
+                    );
+                }
+                let result = trans_suffix_var_to_addr(var, &vec![], semantic_data);
+                if result.ty.escape {
+                    let (ty, exp) = result.ty_exp(
+                        &mut semantic_data.diagnostics,
+                        var.pos,
+                        "The variable does not have an address.",
+                    );
+                    WasmExpTy::new_exp(LazeType_::pointer_type(ty), exp)
+                } else {
+                    result
+                }
+            }
             _ => {
-                let _ = writeln!(
-                    stderr(),
-                    "Cannot get the address of a non-variable expression: {:?}",
-                    exp.pos
+                semantic_data.error(
+                    DiagnosticCode::NotAddressable,
+                    exp.pos,
+                    "Cannot get the address of a non-variable expression.".to_string(),
                 );
                 WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
             }
         },
-        Oper::Not => {
-            let (result_ty, result_exp) = trans_exp(exp, semantic_data).ty_exp("".to_string());
-            if let LazeTypeData::Bool = result_ty.data {
+        Oper::Not | Oper::Minus => {
+            let (result_ty, result_exp) = trans_exp(exp, semantic_data).ty_exp(
+                &mut semantic_data.diagnostics,
+                exp.pos,
+                "The expression did not give a value.",
+            );
+            if is_user_type(&result_ty) {
+                trans_user_oper(oper, vec![(result_ty, result_exp)], exp.pos, semantic_data)
+            } else if let Oper::Minus = oper {
+                semantic_data.error(
+                    DiagnosticCode::InvalidOperator,
+                    exp.pos,
+                    "Not a unary operator.".to_string(),
+                );
+                WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
+            } else if let LazeTypeData::Bool = result_ty.data {
                 WasmExpTy::new_exp(
                     LazeType_::bool_type(),
                     Exp_::binop_exp(
@@ -328,17 +490,146 @@ pub fn trans_unaryop_exp(
                     ),
                 )
             } else {
-                let _ = writeln!(
-                    stderr(),
-                    "Cannot get a Not expression of non-boolean type: {:?}",
-                    exp.pos
+                semantic_data.error(
+                    DiagnosticCode::TypeMismatch,
+                    exp.pos,
+                    "Cannot get a Not expression of non-boolean type.".to_string(),
                 );
                 WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
             }
         }
         _ => {
-            let _ = writeln!(stderr(), "Not a unary operator: {:?}", exp.pos);
+            semantic_data.error(
+                DiagnosticCode::InvalidOperator,
+                exp.pos,
+                "Not a unary operator.".to_string(),
+            );
             WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
         }
     }
 }
+
+// A variable that is not escaped but has an address: in the frame, in the static data,
+// or in the closure record.
+fn memory_scalar(var: &Var, semantic_data: &mut SemanticParam) -> Option<(LazeType, FrameAccess)> {
+    match &var.data {
+        VarData::Simple(name) => match semantic_data.get_var_entry(name) {
+            Some(EnvEntry::Var(ty, access))
+                if !ty.escape
+                    && !matches!(
+                        access,
+                        FrameAccess::InLocal(_) | FrameAccess::InGlobal(_) | FrameAccess::None
+                    ) =>
+            {
+                Some((ty, access))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// the key of an operator declaration in the venv and in the members of a class
+pub fn oper_entry_name(oper_name: &str) -> String {
+    format!("演算子:{}", oper_name)
+}
+
+pub fn is_user_type(ty: &LazeType) -> bool {
+    matches!(
+        ty.data,
+        LazeTypeData::Class(_) | LazeTypeData::Template(_, _, _)
+    )
+}
+
+// An operator on classes calls the 演算子 declared in the class of the first operand,
+// or else the 演算子 declared outside classes that takes the operands.
+fn trans_user_oper(
+    oper: &Oper,
+    operands: Vec<(LazeType, Exp)>,
+    pos: (usize, usize),
+    semantic_data: &mut SemanticParam,
+) -> WasmExpTy {
+    let oper_name = match oper_to_name(oper) {
+        Some(oper_name) => oper_name,
+        None => {
+            semantic_data.error(
+                DiagnosticCode::InvalidOperator,
+                pos,
+                "This operator cannot be used on classes.".to_string(),
+            );
+            return WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp());
+        }
+    };
+    let entry_name = oper_entry_name(oper_name);
+    let (args_ty, mut args): (LazeTypeList, ExpList) = operands.into_iter().unzip();
+    let method = match semantic_data.class_entry(&args_ty[0]) {
+        Some(EnvEntry::Class(_, members, _, _, _)) => match members.get_data(&entry_name) {
+            Some(EnvEntry::Method(_, func_num, params, return_ty, vtable_slot, _))
+                if params.len() == args_ty.len() =>
+            {
+                Some((*func_num, params.clone(), return_ty.clone(), *vtable_slot))
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    let (return_ty, call) = if let Some((func_num, params, return_ty, vtable_slot)) = method {
+        let vtable = match (vtable_slot, semantic_data.class_vtable(&args_ty[0])) {
+            (Some(slot), Some(vtable)) => Some((vtable.pointer_offset, slot)),
+            _ => None,
+        };
+        let object_exp = args.remove(0);
+        let call = trans_method_call(
+            object_exp,
+            func_num,
+            vtable,
+            &params,
+            &return_ty,
+            args,
+            semantic_data,
+        );
+        (return_ty, call)
+    } else {
+        let candidates: Vec<(i32, LazeTypeList, LazeType)> = semantic_data
+            .venv
+            .get_overloads(&entry_name)
+            .into_iter()
+            .filter_map(|entry| match entry {
+                EnvEntry::Func(index, params, return_ty) => {
+                    Some((*index, params.clone(), return_ty.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        if candidates.is_empty() {
+            semantic_data.error(
+                DiagnosticCode::InvalidOperator,
+                pos,
+                format!("No 演算子 {:?} is declared for these operands.", oper_name),
+            );
+            return WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp());
+        }
+        match resolve_overload(&entry_name, candidates, &args_ty, pos, semantic_data) {
+            Some((index, _, return_ty)) => {
+                let call = Exp_::call_exp(return_ty.to_wasm_type(), index, args, None);
+                (return_ty, call)
+            }
+            None => return WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp()),
+        }
+    };
+    if !return_ty.escape {
+        return WasmExpTy::new_exp(return_ty, call);
+    }
+    // the returned object is in the frame of the operator, which the next call overwrites
+    let result_access = semantic_data
+        .frame
+        .last_mut()
+        .unwrap()
+        .alloc_inframe(&return_ty);
+    semantic_data.temp_stmlist.push(Stm_::copy_stm(
+        semantic_data.frame_address_exp(&result_access),
+        call,
+        Exp_::consti32_exp(return_ty.size),
+    ));
+    WasmExpTy::new_exp(return_ty, semantic_data.frame_address_exp(&result_access))
+}
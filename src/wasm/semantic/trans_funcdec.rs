@@ -2,23 +2,33 @@ use crate::{
     ast::{
         exp::ASTExp_,
         field::{FieldData, FieldList},
-        stm::{StmList, Stm_},
+        stm::StmList,
         var::Var,
     },
-    wasm::il::{
-        module::{Module, Module_},
-        stm::Stm_ as WASMStm_,
+    wasm::{
+        frame::frame::{FrameAccess, FREE_LISTS_INDEX, STACK_POINTER_INDEX},
+        il::{
+            exp::{Exp, Exp_},
+            module::{Module, Module_},
+            stm::{Stm, StmList as WASMStmList, Stm_ as WASMStm_},
+            util::{BinOper, WasmType},
+        },
     },
 };
 
 use super::{
+    address_taken::address_taken_in_stmlist,
+    closure_lifetime::frame_closures_in_stmlist,
     entry_map::EnvEntry,
     laze_type::{LazeType, LazeTypeList, LazeType_},
     semantic_param::SemanticParam,
-    trans_stm::{trans_stm, trans_stmlist},
+    trans_exp::trans_exp,
+    trans_object::{enter_object_scope, trans_return_destructors},
+    trans_stm::trans_stmlist,
     trans_var::get_var_name,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn trans_funcdec(
     func_body: &StmList,
     params: &FieldList,
@@ -26,58 +36,170 @@ pub fn trans_funcdec(
     return_var: Option<&Var>,
     return_type: &LazeType,
     export_name: Option<String>,
+    func_index: i32,
     semantic_data: &mut SemanticParam,
 ) -> Module {
     //
     //start scope
     semantic_data.venv.enter_scope();
+    enter_object_scope(semantic_data);
+    semantic_data.frame.last_mut().unwrap().address_taken = address_taken_in_stmlist(func_body);
+    semantic_data.frame.last_mut().unwrap().frame_closures = frame_closures_in_stmlist(func_body);
     //
     // add the return var to the venv
+    let mut escaped_params = vec![];
     for (index, param) in params.iter().enumerate() {
         match &param.data {
             FieldData::Field(var, _) => {
                 // add the parameters to the venv
+                let param_name = get_var_name(var, semantic_data);
+                let access = semantic_data
+                    .frame
+                    .last_mut()
+                    .unwrap()
+                    .alloc_param(&param_name, &params_lazetype[index]);
+                if let FrameAccess::EscapedParam(..) = access {
+                    escaped_params.push((access, params_lazetype[index].clone()));
+                }
                 semantic_data.venv.add_data(
-                    get_var_name(&var),
-                    EnvEntry::Var(
-                        params_lazetype[index].clone(),
-                        semantic_data
-                            .frame
-                            .last_mut()
-                            .unwrap()
-                            .alloc_param(&params_lazetype[index]),
-                    ),
+                    param_name,
+                    EnvEntry::Var(params_lazetype[index].clone(), access),
                 );
-                // TODO: add copy statement
             }
             FieldData::None => {}
         }
     }
+    let frame = semantic_data.frame.last_mut().unwrap();
+    frame.alloc_frame_pointer();
+    if !matches!(return_type.to_wasm_type(), WasmType::None) {
+        frame.alloc_return_local(return_type);
+    }
     if let Some(var) = return_var {
+        let return_var_name = get_var_name(var, semantic_data);
+        let return_access = semantic_data
+            .frame
+            .last_mut()
+            .unwrap()
+            .alloc_var(&return_var_name, return_type);
         semantic_data.venv.add_data(
-            get_var_name(&var),
-            EnvEntry::Var(
-                return_type.clone(),
-                semantic_data.frame.last_mut().unwrap().alloc(&return_type),
-            ),
+            return_var_name,
+            EnvEntry::Var(return_type.clone(), return_access),
         );
     }
-    let mut result_body = vec![];
-    result_body.append(&mut trans_stmlist(func_body, semantic_data));
-    if let Some(var) = return_var {
-        let return_stm = Stm_::return_stm(var.pos, ASTExp_::var_exp(var.pos, var.clone()));
-        result_body.push(trans_stm(&return_stm, semantic_data));
-    };
+    let mut body = trans_stmlist(func_body, semantic_data);
+    match return_var {
+        Some(var) => {
+            let return_exp = trans_exp(&ASTExp_::var_exp(var.pos, var.clone()), semantic_data).exp(
+                &mut semantic_data.diagnostics,
+                var.pos,
+                "The result variable did not give a value.",
+            );
+            body.append(&mut trans_return_stmlist(return_exp, semantic_data));
+        }
+        None => {
+            body.append(&mut trans_return_destructors(semantic_data));
+            body.push(trans_pop_frame(semantic_data));
+        }
+    }
+    // the prologue is made after the body, since the size of the frame is not known before
+    let mut result_body = trans_prologue(&escaped_params, semantic_data);
+    result_body.append(&mut body);
     //
     semantic_data.venv.exit_scope();
     //exit scope
     //
     Module_::func_mod(
-        semantic_data.func_num,
-        LazeType_::list_to_wasm_type(&params_lazetype),
+        func_index,
+        LazeType_::list_to_wasm_type(params_lazetype),
         LazeType_::list_to_wasm_type(&semantic_data.frame.last().unwrap().locals_type),
         return_type.to_wasm_type(),
         WASMStm_::block_stm(result_body),
         export_name,
     )
 }
+
+// Pushes the frame on the stack and copies the escaped parameters into it.
+// The stack ends where the free lists start, and a frame that goes past it traps.
+// A scalar parameter whose address is taken is stored into the frame in the same way.
+pub fn trans_prologue(
+    escaped_params: &Vec<(FrameAccess, LazeType)>,
+    semantic_data: &SemanticParam,
+) -> WASMStmList {
+    let frame = semantic_data.frame.last().unwrap();
+    let mut stmlist = vec![
+        WASMStm_::setlocal_stm(
+            frame.frame_pointer,
+            Exp_::getglobal_exp(WasmType::I32, STACK_POINTER_INDEX),
+        ),
+        WASMStm_::setglobal_stm(
+            STACK_POINTER_INDEX,
+            Exp_::add_addr_exp(
+                Exp_::getlocal_exp(WasmType::I32, frame.frame_pointer),
+                Exp_::consti32_exp(frame.aligned_size()),
+            ),
+        ),
+        WASMStm_::if_stm(
+            Exp_::binop_exp(
+                WasmType::I32,
+                BinOper::GtUnsigned,
+                Exp_::getglobal_exp(WasmType::I32, STACK_POINTER_INDEX),
+                Exp_::getglobal_exp(WasmType::I32, FREE_LISTS_INDEX),
+            ),
+            WASMStm_::unreachable_stm(),
+            WASMStm_::none_stm(),
+        ),
+    ];
+    for (access, ty) in escaped_params {
+        if let FrameAccess::EscapedParam(param_index, _) = access {
+            stmlist.push(if ty.escape {
+                WASMStm_::copy_stm(
+                    semantic_data.frame_address_exp(access),
+                    Exp_::getlocal_exp(WasmType::I32, *param_index),
+                    Exp_::consti32_exp(ty.size),
+                )
+            } else {
+                WASMStm_::store_stm(
+                    semantic_data.frame_address_exp(access),
+                    Exp_::getlocal_exp(ty.to_wasm_type(), *param_index),
+                )
+            });
+        }
+    }
+    stmlist
+}
+
+pub fn trans_pop_frame(semantic_data: &SemanticParam) -> Stm {
+    WASMStm_::setglobal_stm(
+        STACK_POINTER_INDEX,
+        Exp_::getlocal_exp(
+            WasmType::I32,
+            semantic_data.frame.last().unwrap().frame_pointer,
+        ),
+    )
+}
+
+// The return value is saved before the objects are destructed and the frame is popped,
+// because it might be read from them.
+pub fn trans_return_stmlist(return_exp: Exp, semantic_data: &SemanticParam) -> WASMStmList {
+    let frame = semantic_data.frame.last().unwrap();
+    let mut destructors = trans_return_destructors(semantic_data);
+    match frame.return_local {
+        Some(return_local) => {
+            let return_ty =
+                frame.locals_type[return_local as usize - frame.params.len()].to_wasm_type();
+            let mut stmlist = vec![WASMStm_::setlocal_stm(return_local, return_exp)];
+            stmlist.append(&mut destructors);
+            stmlist.push(trans_pop_frame(semantic_data));
+            stmlist.push(WASMStm_::return_stm(Exp_::getlocal_exp(
+                return_ty,
+                return_local,
+            )));
+            stmlist
+        }
+        None => {
+            destructors.push(trans_pop_frame(semantic_data));
+            destructors.push(WASMStm_::return_stm(return_exp));
+            destructors
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::mem::take;
+
+use crate::{
+    ast::{
+        exp::{ASTExp, ASTExpData},
+        op::Oper,
+        stm::{AssignType, Stm_},
+        ty::Type,
+        var::Var,
+    },
+    wasm::{
+        frame::frame::FrameAccess,
+        il::{
+            exp::{Exp, Exp_},
+            module::Module_,
+            stm::Stm_ as WASMStm_,
+            util::{WasmExpTy, WasmType},
+        },
+    },
+};
+
+use super::{
+    entry_map::EnvEntry,
+    laze_type::{LazeType, LazeType_},
+    semantic_param::SemanticParam,
+    trans_funcdec::{trans_pop_frame, trans_prologue},
+    trans_object::{is_object, trans_init_object},
+    trans_stm::trans_stm,
+    trans_ty::{trans_ty, trans_var_ty},
+    trans_var::get_var_name,
+};
+
+// name of the frame of the start function, which cannot be written in laze
+const START_FUNC_NAME: &str = "<start>";
+
+// A top-level variable is a wasm global.
+// A constant initial value is the initial value of the global,
+// and the other ones are set by the start function, in the order of the declarations.
+// The global is immutable when it is never set after its initial value.
+pub fn trans_global_var(
+    var: &Var,
+    var_ty: &Type,
+    init: &ASTExp,
+    semantic_data: &mut SemanticParam,
+) -> WasmExpTy {
+    let (new_var, new_var_ty, object_explist) = trans_var_ty(var, var_ty, semantic_data);
+    let var_lazetype = trans_ty(&new_var_ty, semantic_data);
+    let var_name = get_var_name(new_var, semantic_data);
+    // a scalar whose address is taken is kept in memory, which the start function initializes
+    if !var_lazetype.escape && semantic_data.frame[0].is_address_taken(&var_name) {
+        let access = FrameAccess::InStatic(semantic_data.alloc_static(var_lazetype.size));
+        if !matches!(init.data, ASTExpData::None) {
+            semantic_data
+                .global_inits
+                .push((new_var.clone(), vec![], init.clone()));
+        }
+        semantic_data
+            .venv
+            .add_data(var_name, EnvEntry::Var(var_lazetype, access));
+        return WasmExpTy::none();
+    }
+    let access = semantic_data.frame[0].alloc(&var_lazetype);
+    let const_init =
+        const_exp(init).filter(|exp| !var_lazetype.escape && exp.ty == var_lazetype.to_wasm_type());
+    let needs_init = match init.data {
+        ASTExpData::None => is_object(&var_lazetype, semantic_data),
+        _ => const_init.is_none(),
+    };
+    let init_exp = if var_lazetype.escape {
+        Exp_::consti32_exp(semantic_data.alloc_static(var_lazetype.size))
+    } else {
+        const_init.unwrap_or_else(|| zero_exp(&var_lazetype))
+    };
+    // an escaped global only holds the address of its value
+    let global_mod =
+        if var_lazetype.escape || !(needs_init || semantic_data.frame[0].is_assigned(&var_name)) {
+            Module_::const_global_mod(var_lazetype.to_wasm_type(), init_exp)
+        } else {
+            Module_::global_mod(var_lazetype.to_wasm_type(), init_exp)
+        };
+    semantic_data.global_modlist.push(global_mod);
+    if needs_init {
+        semantic_data.global_inits.push((
+            new_var.clone(),
+            object_explist.cloned().unwrap_or_default(),
+            init.clone(),
+        ));
+    }
+    semantic_data
+        .venv
+        .add_data(var_name, EnvEntry::Var(var_lazetype, access));
+    WasmExpTy::none()
+}
+
+// literals and negated literals
+fn const_exp(exp: &ASTExp) -> Option<Exp> {
+    match &exp.data {
+        ASTExpData::Int(i) => i.parse::<i64>().ok().map(Exp_::consti64_exp),
+        ASTExpData::Short(s) => s.parse::<i32>().ok().map(Exp_::consti32_exp),
+        ASTExpData::Real(r) => r.parse::<f64>().ok().map(Exp_::constf64_exp),
+        ASTExpData::Bool(boolean) => Some(Exp_::consti32_exp(*boolean as i32)),
+        ASTExpData::Char(c) => Some(Exp_::consti32_exp(*c as i32)),
+        ASTExpData::Paren(exp) => const_exp(exp),
+        ASTExpData::UnaryOp(oper_list, exp) if oper_list[..] == [Oper::Minus] => match &exp.data {
+            ASTExpData::Int(i) => format!("-{}", i)
+                .parse::<i64>()
+                .ok()
+                .map(Exp_::consti64_exp),
+            ASTExpData::Short(s) => format!("-{}", s)
+                .parse::<i32>()
+                .ok()
+                .map(Exp_::consti32_exp),
+            ASTExpData::Real(r) => r.parse::<f64>().ok().map(|r| Exp_::constf64_exp(-r)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn zero_exp(ty: &LazeType) -> Exp {
+    match ty.to_wasm_type() {
+        WasmType::I64 => Exp_::consti64_exp(0),
+        WasmType::F64 => Exp_::constf64_exp(0.0),
+        _ => Exp_::consti32_exp(0),
+    }
+}
+
+// The start function is translated after all the declarations,
+// so the initial values can use functions declared after the variables.
+// The static data is set before the variables, since their initial values can use it.
+pub fn trans_global_inits(semantic_data: &mut SemanticParam) {
+    let global_inits = take(&mut semantic_data.global_inits);
+    if global_inits.is_empty() && semantic_data.static_inits.is_empty() {
+        return;
+    }
+    let func_index = semantic_data.func_num;
+    semantic_data.func_num += 1;
+    semantic_data.frame.truncate(1);
+    semantic_data.new_frame(&START_FUNC_NAME.to_string(), None);
+    semantic_data
+        .frame
+        .last_mut()
+        .unwrap()
+        .alloc_frame_pointer();
+    let mut body = vec![];
+    for (var, args, init) in &global_inits {
+        if let ASTExpData::None = init.data {
+            let var_name = get_var_name(var, semantic_data);
+            if let Some(EnvEntry::Var(ty, access)) = semantic_data.venv.get_data(&var_name) {
+                let (ty, access) = (ty.clone(), *access);
+                body.append(&mut trans_init_object(
+                    var.pos,
+                    &access,
+                    &ty,
+                    Some(args),
+                    semantic_data,
+                ));
+            }
+        } else {
+            body.push(trans_stm(
+                &Stm_::assign_stm(var.pos, var.clone(), init.clone(), AssignType::Normal),
+                semantic_data,
+            ));
+        }
+    }
+    body.push(trans_pop_frame(semantic_data));
+    let mut func_body = trans_prologue(&vec![], semantic_data);
+    func_body.append(&mut semantic_data.static_inits);
+    func_body.append(&mut body);
+    let locals = LazeType_::list_to_wasm_type(&semantic_data.frame.last().unwrap().locals_type);
+    semantic_data.result_modlist.push(Module_::func_mod(
+        func_index,
+        vec![],
+        locals,
+        WasmType::None,
+        WASMStm_::block_stm(func_body),
+        None,
+    ));
+    semantic_data
+        .result_modlist
+        .push(Module_::start_mod(func_index));
+    semantic_data.frame.truncate(1);
+}
@@ -0,0 +1,415 @@
+use crate::{
+    ast::{exp::ASTExpList, ty::Type},
+    util::diagnostic::DiagnosticCode,
+    wasm::{
+        frame::frame::FrameAccess,
+        il::{
+            exp::{Exp, ExpList, Exp_},
+            stm::{StmList, Stm_},
+            util::{BinOper, WasmExpTy, WasmType},
+        },
+    },
+};
+
+use super::{
+    entry_map::EnvEntry,
+    laze_type::{LazeType, LazeTypeData, LazeTypeList, LazeType_},
+    semantic_param::SemanticParam,
+    trans_exp::trans_explist,
+    trans_ty::trans_ty,
+    trans_var::check_args,
+};
+
+// An object of a class is constructed when it is declared or made with 新規.
+// The constructor is the method with the name of the class, and the destructor is named ~<class>.
+// The constructors of the base classes are called before the one of the class,
+// and the destructors are called in the reverse order.
+
+// the constructors or the destructors of the class and its base classes, the base class first
+fn special_methods(
+    ty: &LazeType,
+    prefix: &str,
+    semantic_data: &SemanticParam,
+) -> Vec<(String, i32, LazeTypeList)> {
+    let (mut class_name, members) = match semantic_data.class_entry(ty) {
+        Some(EnvEntry::Class(name, members, _, base_class, _)) => {
+            (Some((name.clone(), base_class.clone())), members)
+        }
+        _ => return vec![],
+    };
+    let mut methods = vec![];
+    while let Some((name, base_class)) = class_name {
+        if let Some(EnvEntry::Method(_, func_num, params, _, _, declaring_class)) =
+            members.get_data(&format!("{}{}", prefix, name))
+        {
+            if *declaring_class == name {
+                methods.push((name.clone(), *func_num, params.clone()));
+            }
+        }
+        class_name =
+            base_class.and_then(|base_name| match semantic_data.tenv.get_data(&base_name) {
+                Some(EnvEntry::Class(_, _, _, base_class, _)) => {
+                    Some((base_name, base_class.clone()))
+                }
+                _ => None,
+            });
+    }
+    methods.reverse();
+    methods
+}
+
+// classes and arrays of them
+pub fn is_object(ty: &LazeType, semantic_data: &SemanticParam) -> bool {
+    match &ty.data {
+        LazeTypeData::Array(element_ty, _) => is_object(element_ty, semantic_data),
+        _ => semantic_data.class_entry(ty).is_some(),
+    }
+}
+
+fn object_address(object: &FrameAccess, semantic_data: &SemanticParam) -> Exp {
+    match object {
+        // the address of an object made with 新規 is kept in a local
+        FrameAccess::InLocal(index) => Exp_::getlocal_exp(WasmType::I32, *index),
+        // an escaped global holds the address of the object
+        FrameAccess::InGlobal(index) => Exp_::getglobal_exp(WasmType::I32, *index),
+        _ => semantic_data.frame_address_exp(object),
+    }
+}
+
+// the members of the class and its base classes that are objects, with their offsets
+fn member_objects(ty: &LazeType, semantic_data: &SemanticParam) -> Vec<(LazeType, i32)> {
+    match semantic_data.class_entry(ty) {
+        Some(EnvEntry::Class(_, members, ..)) => members
+            .entries()
+            .filter_map(|entry| match entry {
+                EnvEntry::Member(_, member_ty, offset, _)
+                    if is_object(member_ty, semantic_data) =>
+                {
+                    Some((member_ty.clone(), *offset))
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// the class, one of its base classes, or one of its members that are objects has a destructor
+fn has_destructor(ty: &LazeType, semantic_data: &SemanticParam) -> bool {
+    match &ty.data {
+        LazeTypeData::Array(element_ty, _) => has_destructor(element_ty, semantic_data),
+        _ => {
+            !special_methods(ty, "~", semantic_data).is_empty()
+                || member_objects(ty, semantic_data)
+                    .iter()
+                    .any(|(member_ty, _)| has_destructor(member_ty, semantic_data))
+        }
+    }
+}
+
+// Sets the vtable pointer of the object, and calls the constructors when args is given.
+pub fn trans_init_object(
+    pos: (usize, usize),
+    object: &FrameAccess,
+    ty: &LazeType,
+    args: Option<&ASTExpList>,
+    semantic_data: &mut SemanticParam,
+) -> StmList {
+    trans_init_object_at(
+        pos,
+        &|semantic_data| object_address(object, semantic_data),
+        ty,
+        args,
+        semantic_data,
+    )
+}
+
+// The address is made for each use, since an expression can be used only once.
+// The elements of an array and the members that are objects are initialized in the same way,
+// and the members before the constructors, which can use them.
+fn trans_init_object_at(
+    pos: (usize, usize),
+    address: &dyn Fn(&SemanticParam) -> Exp,
+    ty: &LazeType,
+    args: Option<&ASTExpList>,
+    semantic_data: &mut SemanticParam,
+) -> StmList {
+    let mut init_stmlist = vec![];
+    if let LazeTypeData::Array(element_ty, len) = &ty.data {
+        if !is_object(element_ty, semantic_data) {
+            return init_stmlist;
+        }
+        for index in 0..*len {
+            let element = |semantic_data: &SemanticParam| {
+                Exp_::add_addr_exp(
+                    address(semantic_data),
+                    Exp_::consti32_exp(index * element_ty.size),
+                )
+            };
+            init_stmlist.append(&mut trans_init_object_at(
+                pos,
+                &element,
+                element_ty,
+                args,
+                semantic_data,
+            ));
+        }
+        return init_stmlist;
+    }
+    // objects know the vtable of their class
+    if let Some(vtable) = semantic_data.class_vtable(ty) {
+        let (pointer_offset, table_start) = (vtable.pointer_offset, vtable.table_start);
+        init_stmlist.push(Stm_::store_stm(
+            Exp_::add_addr_exp(address(semantic_data), Exp_::consti32_exp(pointer_offset)),
+            Exp_::consti32_exp(table_start),
+        ));
+    }
+    let args = match args {
+        Some(args) => args,
+        None => return init_stmlist,
+    };
+    let class_name = match semantic_data.class_entry(ty) {
+        Some(EnvEntry::Class(name, ..)) => name.clone(),
+        _ => {
+            if !args.is_empty() {
+                semantic_data.error(
+                    DiagnosticCode::NotAClass,
+                    pos,
+                    "Only objects of a class can be given arguments.".to_string(),
+                );
+            }
+            return init_stmlist;
+        }
+    };
+    for (member_ty, offset) in member_objects(ty, semantic_data) {
+        let member = |semantic_data: &SemanticParam| {
+            Exp_::add_addr_exp(address(semantic_data), Exp_::consti32_exp(offset))
+        };
+        init_stmlist.append(&mut trans_init_object_at(
+            pos,
+            &member,
+            &member_ty,
+            Some(&vec![]),
+            semantic_data,
+        ));
+    }
+    let constructors = special_methods(ty, "", semantic_data);
+    if !args.is_empty() && !constructors.iter().any(|(name, _, _)| *name == class_name) {
+        semantic_data.error(
+            DiagnosticCode::TypeMismatch,
+            pos,
+            format!(
+                "{:?} does not have a constructor that takes arguments.",
+                class_name
+            ),
+        );
+        return init_stmlist;
+    }
+    for (name, func_num, params) in constructors {
+        let (args_ty, mut args) = if name == class_name {
+            trans_explist(args, semantic_data)
+        } else {
+            (vec![], vec![])
+        };
+        if args_ty.len() + 1 != params.len() {
+            semantic_data.error(
+                DiagnosticCode::TypeMismatch,
+                pos,
+                if name == class_name {
+                    format!(
+                        "The constructor of {:?} takes {} arguments, but {} are given.",
+                        name,
+                        params.len() - 1,
+                        args_ty.len()
+                    )
+                } else {
+                    format!(
+                        "The constructor of the base class {:?} takes arguments, so it cannot be called for {:?}.",
+                        name, class_name
+                    )
+                },
+            );
+            continue;
+        }
+        check_args(&name, &params[1..], &args_ty, pos, semantic_data);
+        args.insert(0, address(semantic_data));
+        init_stmlist.push(Stm_::call_stm(func_num, args, None));
+    }
+    init_stmlist
+}
+
+pub fn trans_destruct(
+    object: &FrameAccess,
+    ty: &LazeType,
+    semantic_data: &SemanticParam,
+) -> StmList {
+    trans_destruct_at(
+        &|semantic_data| object_address(object, semantic_data),
+        ty,
+        semantic_data,
+    )
+}
+
+// The members that are objects are destructed after the destructors of the class.
+fn trans_destruct_at(
+    address: &dyn Fn(&SemanticParam) -> Exp,
+    ty: &LazeType,
+    semantic_data: &SemanticParam,
+) -> StmList {
+    if !is_object(ty, semantic_data) {
+        return vec![];
+    }
+    if let LazeTypeData::Array(element_ty, len) = &ty.data {
+        return (0..*len)
+            .rev()
+            .flat_map(|index| {
+                let element = |semantic_data: &SemanticParam| {
+                    Exp_::add_addr_exp(
+                        address(semantic_data),
+                        Exp_::consti32_exp(index * element_ty.size),
+                    )
+                };
+                trans_destruct_at(&element, element_ty, semantic_data)
+            })
+            .collect();
+    }
+    let mut destruct_stmlist: StmList = special_methods(ty, "~", semantic_data)
+        .into_iter()
+        .rev()
+        .map(|(_, func_num, _)| Stm_::call_stm(func_num, vec![address(semantic_data)], None))
+        .collect();
+    for (member_ty, offset) in member_objects(ty, semantic_data).into_iter().rev() {
+        let member = |semantic_data: &SemanticParam| {
+            Exp_::add_addr_exp(address(semantic_data), Exp_::consti32_exp(offset))
+        };
+        destruct_stmlist.append(&mut trans_destruct_at(&member, &member_ty, semantic_data));
+    }
+    destruct_stmlist
+}
+
+// 新規 <class>(args) allocates the object with メモリ確保, and gives the pointer to it.
+pub fn trans_new_exp(
+    pos: (usize, usize),
+    ty: &Type,
+    args: &ASTExpList,
+    semantic_data: &mut SemanticParam,
+) -> WasmExpTy {
+    let lazetype = trans_ty(ty, semantic_data);
+    let pointer_type = LazeType_::pointer_type(lazetype.clone());
+    let object_local = semantic_data
+        .frame
+        .last_mut()
+        .unwrap()
+        .alloc_local(pointer_type.clone());
+    semantic_data.temp_stmlist.push(Stm_::setlocal_stm(
+        object_local,
+        Exp_::call_exp(
+            WasmType::I32,
+            semantic_data.alloc_func,
+            vec![Exp_::consti64_exp(lazetype.size as i64)],
+            None,
+        ),
+    ));
+    let mut init_stmlist = trans_init_object(
+        pos,
+        &FrameAccess::InLocal(object_local),
+        &lazetype,
+        Some(args),
+        semantic_data,
+    );
+    semantic_data.temp_stmlist.append(&mut init_stmlist);
+    WasmExpTy::new_exp(
+        pointer_type,
+        Exp_::getlocal_exp(WasmType::I32, object_local),
+    )
+}
+
+// The object is destructed before メモリ解放 frees it.
+pub fn trans_free_args(
+    args_ty: &LazeTypeList,
+    mut args: ExpList,
+    semantic_data: &mut SemanticParam,
+) -> ExpList {
+    let pointee = match args_ty.first().map(|ty| &ty.data) {
+        Some(LazeTypeData::Pointer(pointee)) if args.len() == 1 => pointee,
+        _ => return args,
+    };
+    if !has_destructor(pointee, semantic_data) {
+        return args;
+    }
+    let object_local = semantic_data
+        .frame
+        .last_mut()
+        .unwrap()
+        .alloc_local(LazeType_::pointer_type(LazeType_::void_type()));
+    semantic_data
+        .temp_stmlist
+        .push(Stm_::setlocal_stm(object_local, args.pop().unwrap()));
+    let destruct_stmlist =
+        trans_destruct(&FrameAccess::InLocal(object_local), pointee, semantic_data);
+    semantic_data.temp_stmlist.push(Stm_::if_stm(
+        Exp_::binop_exp(
+            WasmType::I32,
+            BinOper::Ne,
+            Exp_::getlocal_exp(WasmType::I32, object_local),
+            Exp_::consti32_exp(0),
+        ),
+        Stm_::block_stm(destruct_stmlist),
+        Stm_::none_stm(),
+    ));
+    vec![Exp_::getlocal_exp(WasmType::I32, object_local)]
+}
+
+pub fn enter_object_scope(semantic_data: &mut SemanticParam) {
+    semantic_data.frame.last_mut().unwrap().objects.push(vec![]);
+}
+
+pub fn add_object(object: FrameAccess, ty: LazeType, semantic_data: &mut SemanticParam) {
+    if let Some(scope) = semantic_data
+        .frame
+        .last_mut()
+        .and_then(|frame| frame.objects.last_mut())
+    {
+        scope.push((object, ty));
+    }
+}
+
+// The objects are destructed in the reverse order of their declarations.
+pub fn exit_object_scope(semantic_data: &mut SemanticParam) -> StmList {
+    let objects = semantic_data
+        .frame
+        .last_mut()
+        .unwrap()
+        .objects
+        .pop()
+        .unwrap_or_default();
+    objects
+        .iter()
+        .rev()
+        .flat_map(|(object, ty)| trans_destruct(object, ty, semantic_data))
+        .collect()
+}
+
+// 抜ける and 次へ destruct the objects of the scopes they leave
+pub fn trans_break_destructors(scope_depth: usize, semantic_data: &SemanticParam) -> StmList {
+    semantic_data.frame.last().unwrap().objects[scope_depth..]
+        .iter()
+        .rev()
+        .flat_map(|scope| scope.iter().rev())
+        .flat_map(|(object, ty)| trans_destruct(object, ty, semantic_data))
+        .collect()
+}
+
+// all the objects of the function are destructed when it returns
+pub fn trans_return_destructors(semantic_data: &SemanticParam) -> StmList {
+    semantic_data
+        .frame
+        .last()
+        .unwrap()
+        .objects
+        .iter()
+        .rev()
+        .flat_map(|scope| scope.iter().rev())
+        .flat_map(|(object, ty)| trans_destruct(object, ty, semantic_data))
+        .collect()
+}
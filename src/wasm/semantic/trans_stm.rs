@@ -1,7 +1,4 @@
-use std::{
-    io::{stderr, Write},
-    mem::replace,
-};
+use std::mem::take;
 
 use crate::{
     ast::{
@@ -16,21 +13,25 @@ use crate::{
         ty::Type_,
         var::{Var, VarData, Var_},
     },
+    util::diagnostic::DiagnosticCode,
     wasm::{
         frame::frame::FrameAccess,
         il::{
-            exp::{ExpData, Exp_},
+            exp::{Exp, ExpData, Exp_},
             stm::{Stm, StmList, Stm_},
+            util::{BinOper, WasmType},
         },
     },
 };
 
 use super::{
     entry_map::EnvEntry,
-    laze_type::{LazeTypeData, LazeType_},
+    laze_type::{LazeType, LazeTypeData, LazeType_},
     semantic_param::SemanticParam,
     trans_dec::trans_dec,
     trans_exp::trans_exp,
+    trans_funcdec::trans_return_stmlist,
+    trans_object::{enter_object_scope, exit_object_scope, trans_break_destructors},
     trans_var::{check_member, trans_right_var, trans_suffix_var_to_addr},
 };
 
@@ -40,7 +41,10 @@ pub fn trans_stm(stm: &ASTStm, semantic_data: &mut SemanticParam) -> Stm {
         ASTStmData::Compound(stmlist) => {
             semantic_data.venv.enter_scope();
             semantic_data.tenv.enter_scope();
-            new_stm = Stm_::block_stm(trans_stmlist(&stmlist, semantic_data));
+            enter_object_scope(semantic_data);
+            let mut block = trans_stmlist(stmlist, semantic_data);
+            block.append(&mut exit_object_scope(semantic_data));
+            new_stm = Stm_::block_stm(block);
             semantic_data.venv.exit_scope();
             semantic_data.tenv.exit_scope();
         }
@@ -50,86 +54,130 @@ pub fn trans_stm(stm: &ASTStm, semantic_data: &mut SemanticParam) -> Stm {
         ASTStmData::IfElse(ifelselist) => {
             new_stm = trans_if_stm(ifelselist, semantic_data, stm.pos);
         }
-        ASTStmData::Exp(exp) => match trans_exp(exp, semantic_data).exp("".to_string()).data {
-            ExpData::CallExp(index, label, args) => {
-                new_stm = Stm_::call_stm(index, args, label);
+        ASTStmData::Exp(exp) => {
+            let value = trans_exp(exp, semantic_data).exp(
+                &mut semantic_data.diagnostics,
+                exp.pos,
+                "The expression did not give a value.",
+            );
+            new_stm = match value.data {
+                ExpData::CallExp(index, label, args) if value.ty == WasmType::None => {
+                    Stm_::call_stm(index, args, label)
+                }
+                ExpData::CallIndirect(index, args, type_index) if value.ty == WasmType::None => {
+                    Stm_::call_indirect_stm(index, args, type_index)
+                }
+                ExpData::None => Stm_::none_stm(),
+                // the results of the other calls are not used,
+                // and the other expressions, such as `p != 0 && f(p)`, run for their side effects
+                data => Stm_::drop_stm(Box::new(Exp_ { ty: value.ty, data })),
+            };
+        }
+        ASTStmData::While(test_exp, while_body) => {
+            let loop_label = semantic_data.new_label();
+            enter_loop(loop_label, semantic_data);
+            let mut loop_body = trans_loop_test(test_exp, false, semantic_data);
+            loop_body.push(trans_stm(while_body, semantic_data));
+            new_stm = exit_loop(loop_label, loop_body, semantic_data);
+        }
+        ASTStmData::Until(test_exp, until_body) => {
+            // the loop is left when the test is true
+            let loop_label = semantic_data.new_label();
+            enter_loop(loop_label, semantic_data);
+            let mut loop_body = trans_loop_test(test_exp, true, semantic_data);
+            loop_body.push(trans_stm(until_body, semantic_data));
+            new_stm = exit_loop(loop_label, loop_body, semantic_data);
+        }
+        ASTStmData::Loop(loop_body) => {
+            if !leaves_loop(loop_body, false) {
+                semantic_data.warning(
+                    DiagnosticCode::EndlessLoop,
+                    stm.pos,
+                    "This loop never ends, because it does not have 抜ける or 終了.".to_string(),
+                );
             }
-            ExpData::CallIndirect(index, args, type_index) => {
-                new_stm = Stm_::call_indirect_stm(index, args, type_index);
+            let loop_label = semantic_data.new_label();
+            enter_loop(loop_label, semantic_data);
+            let loop_body = vec![trans_stm(loop_body, semantic_data)];
+            new_stm = exit_loop(loop_label, loop_body, semantic_data);
+        }
+        ASTStmData::Continue => match semantic_data.frame.last().unwrap().loops.last() {
+            Some(&(_, continue_label, scope_depth)) => {
+                new_stm = trans_branch(continue_label, scope_depth, semantic_data);
             }
-            _ => {
+            None => {
+                semantic_data.error(
+                    DiagnosticCode::NotInLoop,
+                    stm.pos,
+                    "Cannot continue, because this statement is not in a loop.".to_string(),
+                );
                 new_stm = Stm_::none_stm();
             }
         },
-        ASTStmData::While(test_exp, while_body) => {
-            semantic_data.loop_index += 1;
-            new_stm = Stm_::loop_stm(
-                trans_exp(test_exp, semantic_data).exp("".to_string()),
-                trans_stm(while_body, semantic_data),
-                semantic_data.loop_index,
-                false,
-            );
-        }
-        ASTStmData::Continue => {
-            if semantic_data.loop_index > 1 {
-                new_stm = Stm_::break_stm(semantic_data.loop_index);
-            } else {
-                let _ = writeln!(
-                    stderr(),
-                    "Cannot continue, because this statement is not in a loop: {:?}",
-                    stm.pos
-                );
-                new_stm = Stm_::none_stm();
+        ASTStmData::Break => match semantic_data.frame.last().unwrap().loops.last() {
+            Some(&(break_label, _, scope_depth)) => {
+                new_stm = trans_branch(break_label, scope_depth, semantic_data);
             }
-        }
-        ASTStmData::Break => {
-            if semantic_data.loop_index > 1 {
-                new_stm = Stm_::break_stm(semantic_data.loop_index - 1);
-            } else {
-                let _ = writeln!(
-                    stderr(),
-                    "Cannot break out, because this statement is not in a loop: {:?}",
-                    stm.pos
+            None => {
+                semantic_data.error(
+                    DiagnosticCode::NotInLoop,
+                    stm.pos,
+                    "Cannot break out, because this statement is not in a loop.".to_string(),
                 );
                 new_stm = Stm_::none_stm();
             }
-        }
+        },
         ASTStmData::For(init, test_exp, incr, for_body) => {
+            // the variables declared in the initialization are only in the loop
+            semantic_data.venv.enter_scope();
             let mut stm_list = vec![];
             stm_list.push(trans_stm(init, semantic_data));
-            let mut loop_body = vec![];
-            loop_body.push(trans_stm(incr, semantic_data));
-            loop_body.push(trans_stm(for_body, semantic_data));
-
-            stm_list.push(Stm_::loop_stm(
-                trans_exp(test_exp, semantic_data).exp("".to_string()),
-                Stm_::block_stm(loop_body),
-                semantic_data.loop_index,
-                true,
+            let loop_label = semantic_data.new_label();
+            let continue_label = semantic_data.new_label();
+            enter_loop(continue_label, semantic_data);
+            let mut loop_body = trans_loop_test(test_exp, false, semantic_data);
+            // 次へ leaves the block of the body, so the increment runs before the next test
+            loop_body.push(Stm_::labeled_block_stm(
+                continue_label,
+                vec![trans_stm(for_body, semantic_data)],
             ));
+            loop_body.push(trans_stm(incr, semantic_data));
+            stm_list.push(exit_loop(loop_label, loop_body, semantic_data));
+            semantic_data.venv.exit_scope();
             new_stm = Stm_::block_stm(stm_list);
         }
         ASTStmData::Dec(dec) => match &dec.data {
             DecData::Var(_, _, _) => {
                 new_stm = trans_dec(dec, None, semantic_data).stm(
-                    format_args!("Failed to analyze dec semantically: {:?}", dec.pos).to_string(),
+                    &mut semantic_data.diagnostics,
+                    dec.pos,
+                    "The variable declaration did not give a statement.",
                 );
             }
             DecData::Func(..) => {
-                let _ = writeln!(
-                    stderr(),
-                    "Cannot define functions inside function body: {:?}",
-                    dec.pos
+                semantic_data.error(
+                    DiagnosticCode::InvalidDeclaration,
+                    dec.pos,
+                    "Cannot define functions inside function body.".to_string(),
                 );
                 new_stm = Stm_::none_stm();
             }
             _ => {
-                let _ = writeln!(stderr(), "Please declare a variable: {:?}", dec.pos);
+                semantic_data.error(
+                    DiagnosticCode::InvalidDeclaration,
+                    dec.pos,
+                    "Please declare a variable.".to_string(),
+                );
                 new_stm = Stm_::none_stm();
             }
         },
         ASTStmData::Return(value) => {
-            new_stm = Stm_::return_stm(trans_exp(value, semantic_data).exp("".to_string()))
+            let return_exp = trans_exp(value, semantic_data).exp(
+                &mut semantic_data.diagnostics,
+                value.pos,
+                "The expression did not give a value.",
+            );
+            new_stm = Stm_::block_stm(trans_return_stmlist(return_exp, semantic_data));
         }
         ASTStmData::Repeat(limit, repeat_body) => {
             let init = ASTStm_::dec_stm(
@@ -141,17 +189,15 @@ pub fn trans_stm(stm: &ASTStm, semantic_data: &mut SemanticParam) -> Stm {
                     ASTExp_::int_exp(stm.pos, "0".to_string()),
                 ),
             );
+            semantic_data.venv.enter_scope();
             let mut stm_list = vec![];
             stm_list.push(trans_stm(&init, semantic_data));
-            let mut loop_body = vec![];
             let incr = ASTStm_::assign_stm(
                 stm.pos,
                 Var_::simple_var(stm.pos, "カウンタ".to_string()),
                 ASTExp_::int_exp(stm.pos, "1".to_string()),
                 AssignType::Add,
             );
-            loop_body.push(trans_stm(&incr, semantic_data));
-            loop_body.push(trans_stm(repeat_body, semantic_data));
             let test_exp = ASTExp_::binop_exp(
                 stm.pos,
                 vec![Oper::Eq],
@@ -160,20 +206,29 @@ pub fn trans_stm(stm: &ASTStm, semantic_data: &mut SemanticParam) -> Stm {
                     limit.clone(),
                 ],
             );
-            stm_list.push(Stm_::loop_stm(
-                trans_exp(&test_exp, semantic_data).exp("".to_string()),
-                Stm_::block_stm(loop_body),
-                semantic_data.loop_index,
-                true,
-            ));
+            let loop_label = semantic_data.new_label();
+            enter_loop(loop_label, semantic_data);
+            let mut loop_body = trans_loop_test(&test_exp, true, semantic_data);
+            loop_body.push(trans_stm(&incr, semantic_data));
+            loop_body.push(trans_stm(repeat_body, semantic_data));
+            stm_list.push(exit_loop(loop_label, loop_body, semantic_data));
+            semantic_data.venv.exit_scope();
             new_stm = Stm_::block_stm(stm_list);
         }
+        ASTStmData::None => {
+            new_stm = Stm_::none_stm();
+        }
         _ => {
+            semantic_data.error(
+                DiagnosticCode::UnsupportedStatement,
+                stm.pos,
+                "This statement cannot be translated.".to_string(),
+            );
             new_stm = Stm_::none_stm();
         }
     }
-    if semantic_data.temp_stmlist.len() > 0 {
-        let mut block = replace(&mut semantic_data.temp_stmlist, vec![]);
+    if !semantic_data.temp_stmlist.is_empty() {
+        let mut block = take(&mut semantic_data.temp_stmlist);
         block.push(new_stm);
         Stm_::block_stm(block)
     } else {
@@ -181,6 +236,74 @@ pub fn trans_stm(stm: &ASTStm, semantic_data: &mut SemanticParam) -> Stm {
     }
 }
 
+// A loop is a block that 抜ける leaves, around a wasm loop that goes back to the start at the end.
+// continue_label is the label that 次へ branches to, which is the loop itself or a block in it.
+fn enter_loop(continue_label: i32, semantic_data: &mut SemanticParam) {
+    let break_label = semantic_data.new_label();
+    let frame = semantic_data.frame.last_mut().unwrap();
+    let scope_depth = frame.objects.len();
+    frame.loops.push((break_label, continue_label, scope_depth));
+}
+
+fn exit_loop(loop_label: i32, mut loop_body: StmList, semantic_data: &mut SemanticParam) -> Stm {
+    let (break_label, _, _) = semantic_data.frame.last_mut().unwrap().loops.pop().unwrap();
+    loop_body.push(Stm_::break_stm(loop_label));
+    Stm_::labeled_block_stm(break_label, vec![Stm_::loop_stm(loop_label, loop_body)])
+}
+
+// The test is evaluated at the start of every iteration, with the statements it needs.
+fn trans_loop_test(
+    test_exp: &ASTExp,
+    exit_if_true: bool,
+    semantic_data: &mut SemanticParam,
+) -> StmList {
+    let test = trans_exp(test_exp, semantic_data).exp(
+        &mut semantic_data.diagnostics,
+        test_exp.pos,
+        "The expression did not give a value.",
+    );
+    let exit_test = if exit_if_true {
+        test
+    } else {
+        Exp_::binop_exp(WasmType::I32, BinOper::Eq, test, Exp_::consti32_exp(0))
+    };
+    let break_label = semantic_data.frame.last().unwrap().loops.last().unwrap().0;
+    let mut test_stmlist = take(&mut semantic_data.temp_stmlist);
+    test_stmlist.push(Stm_::break_if_stm(break_label, exit_test));
+    test_stmlist
+}
+
+// whether 抜ける or 終了 in the statement can leave the loop it is in
+fn leaves_loop(stm: &ASTStm, in_inner_loop: bool) -> bool {
+    match &stm.data {
+        ASTStmData::Break => !in_inner_loop,
+        ASTStmData::Return(_) => true,
+        ASTStmData::Compound(stmlist) => stmlist.iter().any(|stm| leaves_loop(stm, in_inner_loop)),
+        ASTStmData::IfElse(ifelselist) => ifelselist.iter().any(|ifelse| match &ifelse.data {
+            IfElseData::If(_, body) | IfElseData::ElseIf(_, body) | IfElseData::Else(body) => {
+                leaves_loop(body, in_inner_loop)
+            }
+        }),
+        // 抜ける in these loops leaves only the inner loop
+        ASTStmData::While(_, body)
+        | ASTStmData::Until(_, body)
+        | ASTStmData::For(_, _, _, body)
+        | ASTStmData::Repeat(_, body)
+        | ASTStmData::Loop(body) => leaves_loop(body, true),
+        _ => false,
+    }
+}
+
+// 抜ける and 次へ
+fn trans_branch(label: i32, scope_depth: usize, semantic_data: &SemanticParam) -> Stm {
+    let mut branch_stmlist = trans_break_destructors(scope_depth, semantic_data);
+    if branch_stmlist.is_empty() {
+        return Stm_::break_stm(label);
+    }
+    branch_stmlist.push(Stm_::break_stm(label));
+    Stm_::block_stm(branch_stmlist)
+}
+
 pub fn trans_if_stm(
     ifelselist: &IfElseList,
     semantic_data: &mut SemanticParam,
@@ -188,32 +311,52 @@ pub fn trans_if_stm(
 ) -> Stm {
     let mut ifelse_iter = ifelselist.iter();
     let mut result_stm;
-    if ifelselist.len() > 0 {
+    if !ifelselist.is_empty() {
         if let IfElseData::If(test_exp, if_body) = &ifelselist[0].data {
-            result_stm = Stm_::if_stm(
-                trans_exp(&test_exp, semantic_data).exp("".to_string()),
-                trans_stm(&if_body, semantic_data),
-                Stm_::none_stm(),
+            // the statements that the test needs run before the if statement, not in the body
+            let test = trans_exp(test_exp, semantic_data).exp(
+                &mut semantic_data.diagnostics,
+                test_exp.pos,
+                "The expression did not give a value.",
             );
+            let mut test_stmlist = take(&mut semantic_data.temp_stmlist);
+            result_stm = Stm_::if_stm(test, trans_stm(if_body, semantic_data), Stm_::none_stm());
+            semantic_data.temp_stmlist.append(&mut test_stmlist);
             ifelse_iter.next();
-            while let Some(ifelse) = ifelse_iter.next() {
+            for ifelse in ifelse_iter {
                 match &ifelse.data {
                     IfElseData::ElseIf(test_exp, elseif_body) => {
+                        let test = trans_exp(test_exp, semantic_data).exp(
+                            &mut semantic_data.diagnostics,
+                            test_exp.pos,
+                            "The expression did not give a value.",
+                        );
+                        let mut elseif_stmlist = take(&mut semantic_data.temp_stmlist);
+                        elseif_stmlist.push(Stm_::if_stm(
+                            test,
+                            trans_stm(elseif_body, semantic_data),
+                            Stm_::none_stm(),
+                        ));
                         result_stm.set_if_else_body(
-                            Stm_::if_stm(
-                                trans_exp(&test_exp, semantic_data).exp("".to_string()),
-                                trans_stm(&elseif_body, semantic_data),
-                                Stm_::none_stm(),
-                            ),
+                            Stm_::block_stm(elseif_stmlist),
                             stm_pos,
+                            &mut semantic_data.diagnostics,
                         );
                     }
                     IfElseData::Else(else_body) => {
-                        result_stm.set_if_else_body(trans_stm(&else_body, semantic_data), stm_pos);
+                        let else_stm = trans_stm(else_body, semantic_data);
+                        result_stm.set_if_else_body(
+                            else_stm,
+                            stm_pos,
+                            &mut semantic_data.diagnostics,
+                        );
                     }
                     IfElseData::If(..) => {
-                        let _ =
-                            writeln!(stderr(), "Do not connect if statements: {:?}", ifelse.pos);
+                        semantic_data.error(
+                            DiagnosticCode::InvalidDeclaration,
+                            ifelse.pos,
+                            "Do not connect if statements.".to_string(),
+                        );
                     }
                 }
             }
@@ -226,6 +369,33 @@ pub fn trans_if_stm(
     result_stm
 }
 
+// The value must have the wasm type of the variable, or the module does not validate.
+fn trans_assigned_exp(
+    init: &ASTExp,
+    var_type: &LazeType,
+    semantic_data: &mut SemanticParam,
+) -> Exp {
+    let (ty, exp) = trans_exp(init, semantic_data).ty_exp(
+        &mut semantic_data.diagnostics,
+        init.pos,
+        "The expression did not give a value.",
+    );
+    // the variable or the expression that failed is already reported
+    if var_type.data == LazeTypeData::None
+        || ty.data == LazeTypeData::None
+        || ty.to_wasm_type() == var_type.to_wasm_type()
+    {
+        exp
+    } else {
+        semantic_data.error(
+            DiagnosticCode::TypeMismatch,
+            init.pos,
+            "This expression's type does not match with the variable's type.".to_string(),
+        );
+        Exp_::none_exp()
+    }
+}
+
 pub fn trans_assign_stm(
     var: &Var,
     init: &ASTExp,
@@ -251,17 +421,29 @@ pub fn trans_assign_stm(
     };
     match &var.data {
         VarData::SuffixVar(var, suffixlist) => {
+            let (var_type, addr) = trans_suffix_var_to_addr(var, suffixlist, semantic_data).ty_exp(
+                &mut semantic_data.diagnostics,
+                var.pos,
+                "The variable does not have an address.",
+            );
             new_stm = Stm_::store_stm(
-                trans_suffix_var_to_addr(var, suffixlist, semantic_data)
-                    .exp(format_args!("Var does not have address: {:?}", var.pos).to_string()),
-                trans_exp(&added_init, semantic_data).exp("".to_string()),
+                addr,
+                trans_assigned_exp(&added_init, &var_type, semantic_data),
             );
         }
         VarData::Pointer(var) => {
+            let (pointer_type, addr) = trans_right_var(var, semantic_data).ty_exp(
+                &mut semantic_data.diagnostics,
+                var.pos,
+                "The variable does not have an address.",
+            );
+            let var_type = match pointer_type.data {
+                LazeTypeData::Pointer(pointee) => pointee,
+                _ => LazeType_::none_type(),
+            };
             new_stm = Stm_::store_stm(
-                trans_right_var(var, semantic_data)
-                    .exp(format_args!("Var does not have address: {:?}", var.pos).to_string()),
-                trans_exp(&added_init, semantic_data).exp("".to_string()),
+                addr,
+                trans_assigned_exp(&added_init, &var_type, semantic_data),
             );
         }
         VarData::Simple(name) => {
@@ -269,9 +451,9 @@ pub fn trans_assign_stm(
             let var_type;
             let access;
             {
-                let var_entry = semantic_data.venv.get_data(name);
+                let var_entry = semantic_data.get_var_entry(name);
                 (var_type, access) = if let Some(EnvEntry::Var(ty, access)) = var_entry {
-                    (ty.clone(), access.clone())
+                    (ty, access)
                 } else {
                     (LazeType_::none_type(), FrameAccess::None)
                 };
@@ -281,21 +463,31 @@ pub fn trans_assign_stm(
                     FrameAccess::InLocal(index) => {
                         new_stm = Stm_::setlocal_stm(
                             index,
-                            trans_exp(&added_init, semantic_data).exp("".to_string()),
+                            trans_assigned_exp(&added_init, &var_type, semantic_data),
+                        );
+                    }
+                    // an escaped global holds the address of the value
+                    FrameAccess::InGlobal(index) if var_type.escape => {
+                        new_stm = Stm_::copy_stm(
+                            Exp_::getglobal_exp(WasmType::I32, index),
+                            trans_assigned_exp(&added_init, &var_type, semantic_data),
+                            Exp_::consti32_exp(var_type.size),
                         );
                     }
                     FrameAccess::InGlobal(index) => {
                         new_stm = Stm_::setglobal_stm(
                             index,
-                            trans_exp(&added_init, semantic_data).exp("".to_string()),
+                            trans_assigned_exp(&added_init, &var_type, semantic_data),
                         );
                     }
-                    FrameAccess::InFrame(memory_offset, frame_offset)
-                    | FrameAccess::EscapedParam(_, memory_offset, frame_offset) => {
-                        if var_type.escape == false {
+                    FrameAccess::InFrame(..)
+                    | FrameAccess::EscapedParam(..)
+                    | FrameAccess::InEnv(..)
+                    | FrameAccess::InStatic(..) => {
+                        if !var_type.escape {
                             new_stm = Stm_::store_stm(
-                                Exp_::consti32_exp(memory_offset + frame_offset),
-                                trans_exp(&added_init, semantic_data).exp("".to_string()),
+                                semantic_data.frame_address_exp(&access),
+                                trans_assigned_exp(&added_init, &var_type, semantic_data),
                             );
                         } else {
                             // match added_init.data {
@@ -304,14 +496,18 @@ pub fn trans_assign_stm(
                             //     }
                             // }
                             new_stm = Stm_::copy_stm(
-                                Exp_::consti32_exp(memory_offset + frame_offset),
-                                trans_exp(&added_init, semantic_data).exp("".to_string()),
+                                semantic_data.frame_address_exp(&access),
+                                trans_assigned_exp(&added_init, &var_type, semantic_data),
                                 Exp_::consti32_exp(var_type.size),
                             );
                         }
                     }
                     FrameAccess::None => {
-                        let _ = writeln!(stderr(), "{:?} is not in scope: {:?}", name, var.pos);
+                        semantic_data.error(
+                            DiagnosticCode::UndefinedVariable,
+                            var.pos,
+                            format!("{:?} is not in scope.", name),
+                        );
                         new_stm = Stm_::none_stm();
                     }
                 }
@@ -325,7 +521,11 @@ pub fn trans_assign_stm(
                         semantic_data,
                     );
                 } else {
-                    let _ = writeln!(stderr(), "{:?} is not a variable: {:?}", name, var.pos);
+                    semantic_data.error(
+                        DiagnosticCode::NotAVariable,
+                        var.pos,
+                        format!("{:?} is not a variable.", name),
+                    );
                     new_stm = Stm_::none_stm();
                 }
             }
@@ -0,0 +1,487 @@
+use std::mem::{swap, take};
+
+use crate::{
+    ast::{
+        dec::{Dec, DecData, MemberSpecifier},
+        exp::ASTExpList,
+        field::{FieldData, FieldList},
+        ty::{Type, TypeData, TypeList},
+        var::VarData,
+    },
+    util::diagnostic::DiagnosticCode,
+    wasm::{
+        frame::frame::ClosureEnv,
+        il::{
+            exp::{Exp, Exp_},
+            stm::StmList,
+        },
+    },
+};
+
+use super::{
+    entry_map::{EntryMap, EnvEntry},
+    laze_type::{LazeType, LazeTypeData, LazeTypeList, LazeType_},
+    semantic_param::SemanticParam,
+    trans_exp::{is_user_type, oper_entry_name, trans_explist},
+    trans_funcdec::trans_funcdec,
+    trans_ty::{trans_params, trans_result, trans_tylist, trans_var_ty},
+    trans_var::check_args,
+};
+
+// What is put aside while an instance of a template is translated.
+pub struct TemplateScope {
+    venv: EntryMap,
+    closures: Vec<ClosureEnv>,
+    temp_stmlist: StmList,
+    frame_num: usize,
+}
+
+// An instance is translated where the template was declared, with the type parameters as the given types,
+// so it cannot capture the variables of a function expression.
+pub fn enter_template(
+    type_params: &[String],
+    type_args: &LazeTypeList,
+    mut template_venv: EntryMap,
+    semantic_data: &mut SemanticParam,
+) -> TemplateScope {
+    semantic_data.tenv.enter_scope();
+    for (type_param, type_arg) in type_params.iter().zip(type_args) {
+        semantic_data
+            .tenv
+            .add_data(type_param.clone(), EnvEntry::Poly(type_arg.clone()));
+    }
+    swap(&mut semantic_data.venv, &mut template_venv);
+    TemplateScope {
+        venv: template_venv,
+        closures: take(&mut semantic_data.closures),
+        temp_stmlist: take(&mut semantic_data.temp_stmlist),
+        frame_num: semantic_data.frame.len(),
+    }
+}
+
+pub fn exit_template(scope: TemplateScope, semantic_data: &mut SemanticParam) {
+    semantic_data.venv = scope.venv;
+    semantic_data.closures = scope.closures;
+    semantic_data.temp_stmlist = scope.temp_stmlist;
+    // the frames of the instance must not be taken as the frame of the function that uses it
+    semantic_data.frame.truncate(scope.frame_num);
+    semantic_data.tenv.exit_scope();
+}
+
+// Returns the pairs of a type parameter and the 制約 on it.
+pub fn trans_constraints(
+    constraints: &FieldList,
+    type_params: &[String],
+    semantic_data: &mut SemanticParam,
+) -> Vec<(String, String)> {
+    let mut result = vec![];
+    for constraint in constraints {
+        if let FieldData::Field(var, ty) = &constraint.data {
+            let type_param = match &var.data {
+                VarData::Simple(type_param) if type_params.contains(type_param) => type_param,
+                _ => {
+                    semantic_data.error(
+                        DiagnosticCode::InvalidDeclaration,
+                        var.pos,
+                        "A 制約 can only be put on a type parameter of this template.".to_string(),
+                    );
+                    continue;
+                }
+            };
+            match &ty.data {
+                TypeData::Name(constraint_name)
+                    if matches!(
+                        semantic_data.tenv.get_data(constraint_name),
+                        Some(EnvEntry::Constraint(..))
+                    ) =>
+                {
+                    result.push((type_param.clone(), constraint_name.clone()))
+                }
+                _ => semantic_data.error(
+                    DiagnosticCode::InvalidDeclaration,
+                    ty.pos,
+                    "Could not find this 制約.".to_string(),
+                ),
+            }
+        }
+    }
+    result
+}
+
+// The types given to a template are checked against the 制約 on the type parameters,
+// so that what they lack is reported where the template is used rather than in the instance.
+pub fn check_constraints(
+    pos: (usize, usize),
+    name: &String,
+    type_params: &[String],
+    type_args: &LazeTypeList,
+    constraints: &[(String, String)],
+    template_venv: &EntryMap,
+    semantic_data: &mut SemanticParam,
+) -> bool {
+    let mut satisfied = true;
+    for (type_param, constraint_name) in constraints {
+        let type_arg = match type_params.iter().position(|param| param == type_param) {
+            Some(index) => &type_args[index],
+            None => continue,
+        };
+        let (constraint_param, requirements) = match semantic_data.tenv.get_data(constraint_name) {
+            Some(EnvEntry::Constraint(constraint_param, requirements)) => {
+                (constraint_param.clone(), requirements.clone())
+            }
+            _ => continue,
+        };
+        semantic_data.tenv.enter_scope();
+        semantic_data
+            .tenv
+            .add_data(constraint_param, EnvEntry::Poly(type_arg.clone()));
+        let unmet: Vec<String> = requirements
+            .iter()
+            .filter_map(|requirement| {
+                check_requirement(requirement, type_arg, template_venv, semantic_data).err()
+            })
+            .collect();
+        semantic_data.tenv.exit_scope();
+        for reason in unmet {
+            semantic_data.error(
+                DiagnosticCode::UnsatisfiedConstraint,
+                pos,
+                format!(
+                    "The type given to {:?} of {:?} does not satisfy {:?}: {}",
+                    type_param, name, constraint_name, reason
+                ),
+            );
+            satisfied = false;
+        }
+    }
+    satisfied
+}
+
+// A function is required as a public method of the class,
+// and an operator as a 演算子 or an operator of the language that takes the operands.
+fn check_requirement(
+    requirement: &Dec,
+    type_arg: &LazeType,
+    template_venv: &EntryMap,
+    semantic_data: &mut SemanticParam,
+) -> Result<(), String> {
+    let (name, params, result) = match &requirement.data {
+        DecData::Func(name, params, result, _) | DecData::Oper(name, params, result, _) => {
+            (name, params, result)
+        }
+        _ => return Ok(()),
+    };
+    let params_ty = trans_params(params, semantic_data);
+    let (_, return_ty) = trans_result(requirement.pos, result, semantic_data);
+    if let DecData::Func(..) = requirement.data {
+        let method = match semantic_data.class_entry(type_arg) {
+            Some(EnvEntry::Class(_, members, _, _, _)) => members.get_data(name),
+            _ => None,
+        };
+        match method {
+            Some(EnvEntry::Method(
+                MemberSpecifier::Public,
+                _,
+                method_params,
+                method_return,
+                _,
+                _,
+            )) if same_types(&method_params[1..], &params_ty)
+                && method_return.data == return_ty.data =>
+            {
+                Ok(())
+            }
+            _ => Err(format!(
+                "it needs a public method {:?} with the parameter and return types of the requirement.",
+                name
+            )),
+        }
+    } else {
+        let oper_return = if params_ty.iter().any(is_user_type) {
+            declared_oper_type(name, &params_ty, template_venv, semantic_data)
+        } else {
+            builtin_oper_type(name, &params_ty)
+        };
+        match oper_return {
+            Some(oper_return) if oper_return.data == return_ty.data => Ok(()),
+            _ => Err(format!(
+                "there is no 演算子 {:?} with the parameter and return types of the requirement.",
+                name
+            )),
+        }
+    }
+}
+
+fn same_types(left: &[LazeType], right: &[LazeType]) -> bool {
+    left.len() == right.len() && left.iter().zip(right).all(|(l, r)| l.data == r.data)
+}
+
+// The return type of the 演算子 in the class of the first operand,
+// or of the 演算子 declared before the template.
+fn declared_oper_type(
+    oper_name: &str,
+    operands: &LazeTypeList,
+    template_venv: &EntryMap,
+    semantic_data: &SemanticParam,
+) -> Option<LazeType> {
+    let entry_name = oper_entry_name(oper_name);
+    if let Some(EnvEntry::Class(_, members, _, _, _)) = semantic_data.class_entry(&operands[0]) {
+        if let Some(EnvEntry::Method(_, _, params, return_ty, _, _)) = members.get_data(&entry_name)
+        {
+            if same_types(&params[1..], &operands[1..]) {
+                return Some(return_ty.clone());
+            }
+        }
+    }
+    template_venv
+        .get_overloads(&entry_name)
+        .into_iter()
+        .find_map(|entry| match entry {
+            EnvEntry::Func(_, params, return_ty) if same_types(params, operands) => {
+                Some(return_ty.clone())
+            }
+            _ => None,
+        })
+}
+
+// The type of an operator of the language on the operands.
+fn builtin_oper_type(oper_name: &str, operands: &LazeTypeList) -> Option<LazeType> {
+    let is_number = |ty: &LazeType| {
+        matches!(
+            ty.data,
+            LazeTypeData::Int | LazeTypeData::Short | LazeTypeData::Real | LazeTypeData::Char
+        )
+    };
+    let is_bool = |ty: &LazeType| matches!(ty.data, LazeTypeData::Bool);
+    match (oper_name, operands.as_slice()) {
+        ("Add" | "Sub" | "Mul" | "Div", [left, right])
+            if is_number(left) && left.data == right.data =>
+        {
+            Some(left.clone())
+        }
+        ("Sub", [operand]) if is_number(operand) => Some(operand.clone()),
+        ("Eq" | "Ne", [left, right])
+            if left.data == right.data
+                && (is_number(left)
+                    || is_bool(left)
+                    || matches!(left.data, LazeTypeData::Pointer(_))) =>
+        {
+            Some(LazeType_::bool_type())
+        }
+        ("Lt" | "Le" | "Gt" | "Ge", [left, right])
+            if is_number(left) && left.data == right.data =>
+        {
+            Some(LazeType_::bool_type())
+        }
+        ("And" | "Or", [left, right]) if is_bool(left) && is_bool(right) => {
+            Some(LazeType_::bool_type())
+        }
+        ("Not", [operand]) if is_bool(operand) => Some(LazeType_::bool_type()),
+        _ => None,
+    }
+}
+
+pub fn is_func_template(name: &String, semantic_data: &SemanticParam) -> bool {
+    match semantic_data.tenv.get_data(name) {
+        Some(EnvEntry::Template(original_dec, _, _, _, _)) => {
+            matches!(original_dec.data, DecData::Func(..))
+        }
+        _ => false,
+    }
+}
+
+// A generic function is instantiated for the types given to it,
+// or for the types inferred from the arguments when none are given.
+pub fn trans_template_call(
+    pos: (usize, usize),
+    name: &String,
+    type_args: Option<&TypeList>,
+    explist: &ASTExpList,
+    semantic_data: &mut SemanticParam,
+) -> (LazeType, Exp) {
+    let (args_ty, args) = trans_explist(explist, semantic_data);
+    let (original_dec, template_map, template_venv, type_params, constraints) =
+        match semantic_data.tenv.get_data_clone(name) {
+            Some(EnvEntry::Template(
+                original_dec,
+                template_map,
+                template_venv,
+                type_params,
+                constraints,
+            )) => (
+                original_dec,
+                template_map,
+                template_venv,
+                type_params,
+                constraints,
+            ),
+            _ => return (LazeType_::none_type(), Exp_::none_exp()),
+        };
+    let params = match &original_dec.data {
+        DecData::Func(_, params, _, _) => params,
+        _ => return (LazeType_::none_type(), Exp_::none_exp()),
+    };
+    let type_args = match type_args {
+        Some(type_args) if type_args.len() != type_params.len() => {
+            semantic_data.error(
+                DiagnosticCode::TemplateParams,
+                pos,
+                format!(
+                    "{:?} takes {} type parameters, but {} are given.",
+                    name,
+                    type_params.len(),
+                    type_args.len()
+                ),
+            );
+            return (LazeType_::none_type(), Exp_::none_exp());
+        }
+        Some(type_args) => trans_tylist(type_args.clone(), semantic_data),
+        None => match infer_type_args(params, &type_params, &args_ty, semantic_data) {
+            Ok(type_args) => type_args,
+            Err(message) => {
+                semantic_data.error(DiagnosticCode::TemplateParams, pos, message);
+                return (LazeType_::none_type(), Exp_::none_exp());
+            }
+        },
+    };
+    let (func_index, params_ty, return_ty) = match template_map.get_data(&type_args) {
+        Some(EnvEntry::Func(func_index, params_ty, return_ty)) => {
+            (*func_index, params_ty.clone(), return_ty.clone())
+        }
+        _ => {
+            if !check_constraints(
+                pos,
+                name,
+                &type_params,
+                &type_args,
+                &constraints,
+                &template_venv,
+                semantic_data,
+            ) {
+                return (LazeType_::none_type(), Exp_::none_exp());
+            }
+            trans_func_instance(
+                name,
+                &original_dec,
+                template_venv,
+                &type_params,
+                &type_args,
+                semantic_data,
+            )
+        }
+    };
+    check_args(name, &params_ty, &args_ty, pos, semantic_data);
+    (
+        return_ty.clone(),
+        Exp_::call_exp(return_ty.to_wasm_type(), func_index, args, None),
+    )
+}
+
+fn trans_func_instance(
+    name: &String,
+    original_dec: &Dec,
+    template_venv: EntryMap,
+    type_params: &[String],
+    type_args: &LazeTypeList,
+    semantic_data: &mut SemanticParam,
+) -> (i32, LazeTypeList, LazeType) {
+    let (params, result, func_body) = match &original_dec.data {
+        DecData::Func(_, params, result, func_body) => (params, result, func_body),
+        _ => return (0, vec![], LazeType_::none_type()),
+    };
+    let scope = enter_template(type_params, type_args, template_venv, semantic_data);
+    semantic_data.new_frame(name, None);
+    let params_lazetype = trans_params(params, semantic_data);
+    let (return_var, return_lazetype) = trans_result(original_dec.pos, result, semantic_data);
+    let func_index = semantic_data.func_num;
+    semantic_data.func_num += 1;
+    let func_entry = EnvEntry::Func(func_index, params_lazetype.clone(), return_lazetype.clone());
+    // the instance is entered before its body, so that it can call itself
+    if let Some(EnvEntry::Template(_, template_map, _, _, _)) =
+        semantic_data.tenv.get_mut_data(name)
+    {
+        template_map.add_data(type_args.clone(), func_entry.clone());
+    }
+    semantic_data.venv.add_data(name.clone(), func_entry);
+    let func_mod = trans_funcdec(
+        func_body,
+        params,
+        &params_lazetype,
+        return_var,
+        &return_lazetype,
+        None,
+        func_index,
+        semantic_data,
+    );
+    semantic_data.result_modlist.push(func_mod);
+    exit_template(scope, semantic_data);
+    (func_index, params_lazetype, return_lazetype)
+}
+
+// Each type parameter is given the type of the argument at the place where it appears in the parameter types.
+fn infer_type_args(
+    params: &FieldList,
+    type_params: &[String],
+    args_ty: &LazeTypeList,
+    semantic_data: &mut SemanticParam,
+) -> Result<LazeTypeList, String> {
+    let mut inferred: Vec<Option<LazeType>> = vec![None; type_params.len()];
+    for (field, arg_ty) in params.iter().zip(args_ty) {
+        if let FieldData::Field(var, ty) = &field.data {
+            let (_, param_ty, _) = trans_var_ty(var, ty, semantic_data);
+            if let Err(type_param) = infer_from_type(&param_ty, arg_ty, type_params, &mut inferred)
+            {
+                return Err(format!(
+                    "The arguments give different types to the type parameter {:?}.",
+                    type_param
+                ));
+            }
+        }
+    }
+    inferred
+        .into_iter()
+        .zip(type_params)
+        .map(|(ty, type_param)| {
+            ty.ok_or_else(|| {
+                format!(
+                    "Could not infer the type parameter {:?} from the arguments.",
+                    type_param
+                )
+            })
+        })
+        .collect()
+}
+
+fn infer_from_type(
+    param_ty: &Type,
+    arg_ty: &LazeType,
+    type_params: &[String],
+    inferred: &mut [Option<LazeType>],
+) -> Result<(), String> {
+    match (&param_ty.data, &arg_ty.data) {
+        (TypeData::Name(name), _) => {
+            if let Some(index) = type_params.iter().position(|type_param| type_param == name) {
+                match &inferred[index] {
+                    Some(ty) if ty.data != arg_ty.data => return Err(name.clone()),
+                    Some(_) => {}
+                    None => inferred[index] = Some(arg_ty.clone()),
+                }
+            }
+            Ok(())
+        }
+        (TypeData::Pointer(param_pointee), LazeTypeData::Pointer(arg_pointee))
+        | (TypeData::Array(param_pointee, _), LazeTypeData::Array(arg_pointee, _)) => {
+            infer_from_type(param_pointee, arg_pointee, type_params, inferred)
+        }
+        (
+            TypeData::Template(param_name, param_type_args),
+            LazeTypeData::Template(arg_name, arg_type_args, _),
+        ) if param_name == arg_name => {
+            for (param_type_arg, arg_type_arg) in param_type_args.iter().zip(arg_type_args) {
+                infer_from_type(param_type_arg, arg_type_arg, type_params, inferred)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
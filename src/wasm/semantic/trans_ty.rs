@@ -1,21 +1,21 @@
-use std::{
-    io::{stderr, Write},
-    mem::swap,
-};
-
-use crate::ast::{
-    exp::{ASTExpData, ASTExpList},
-    field::{FieldData, FieldList},
-    suffix::SuffixData,
-    ty::{Type, TypeData, TypeList, Type_},
-    var::{Var, VarData},
+use crate::{
+    ast::{
+        exp::{ASTExpData, ASTExpList},
+        field::{FieldData, FieldList},
+        suffix::SuffixData,
+        ty::{Type, TypeData, TypeList, Type_},
+        var::{Var, VarData},
+    },
+    util::diagnostic::DiagnosticCode,
+    wasm::il::util::WasmType,
 };
 
 use super::{
     entry_map::EnvEntry,
-    laze_type::{LazeType, LazeTypeList, LazeType_},
+    laze_type::{FuncRef, LazeType, LazeTypeList, LazeType_},
     semantic_param::SemanticParam,
     trans_dec::trans_dec,
+    trans_template::{check_constraints, enter_template, exit_template},
 };
 
 pub fn trans_ty(ty: &Type, semantic_data: &mut SemanticParam) -> LazeType {
@@ -26,16 +26,23 @@ pub fn trans_ty(ty: &Type, semantic_data: &mut SemanticParam) -> LazeType {
         TypeData::Char => LazeType_::char_type(),
         TypeData::Real => LazeType_::real_type(),
         TypeData::Short => LazeType_::short_type(),
-        TypeData::Name(name) => LazeType_::class_type(name.clone(), 0),
+        TypeData::Name(name) => match semantic_data.tenv.get_data(name) {
+            Some(EnvEntry::Class(_, _, class_size, _, _)) => {
+                LazeType_::class_type(name.clone(), *class_size)
+            }
+            Some(EnvEntry::Poly(ty)) => ty.clone(),
+            // the class might not be declared yet, as in a pointer to the class in its own members
+            _ => LazeType_::class_type(name.clone(), 0),
+        },
         TypeData::Array(ty, size) => {
             let array_size = match &size.data {
                 ASTExpData::Int(int) => int.parse::<i32>().unwrap(),
                 ASTExpData::Short(int) => int.parse::<i32>().unwrap(),
                 _ => {
-                    let _ = writeln!(
-                        stderr(),
-                        "The size of this array is not a constant: {:?}",
-                        size.pos
+                    semantic_data.error(
+                        DiagnosticCode::NotConstant,
+                        size.pos,
+                        "The size of this array is not a constant.".to_string(),
                     );
                     0
                 }
@@ -50,80 +57,101 @@ pub fn trans_ty(ty: &Type, semantic_data: &mut SemanticParam) -> LazeType {
                     param_types.push(trans_ty(&ty, semantic_data));
                 }
             }
-            LazeType_::func_type(param_types, trans_ty(&result, semantic_data), 0)
+            let result_type = trans_ty(result, semantic_data);
+            // the values of function types are closures,
+            // which take the closure record before the parameters
+            let mut wasm_params = vec![WasmType::I32];
+            wasm_params.append(&mut LazeType_::list_to_wasm_type(&param_types));
+            let type_index = semantic_data.func_type_index(wasm_params, result_type.to_wasm_type());
+            LazeType_::func_type(param_types, result_type, FuncRef::Indirect(type_index))
         }
         TypeData::Template(name, type_params) => {
             let type_params_lazetype = trans_tylist(type_params.clone(), semantic_data);
-            let template_entry = semantic_data.tenv.get_data_clone(name);
-
-            if let Some(entry) = template_entry {
-                if let EnvEntry::Template(
-                    original_dec,
-                    template_map,
-                    mut template_venv,
-                    type_params_str,
-                ) = entry
+            let (original_dec, template_map, template_venv, type_params_str, constraints) =
+                match semantic_data.tenv.get_data_clone(name) {
+                    Some(EnvEntry::Template(
+                        original_dec,
+                        template_map,
+                        template_venv,
+                        type_params_str,
+                        constraints,
+                    )) => (
+                        original_dec,
+                        template_map,
+                        template_venv,
+                        type_params_str,
+                        constraints,
+                    ),
+                    _ => return LazeType_::none_type(),
+                };
+            if template_map.get_data(&type_params_lazetype).is_none() {
+                if type_params.len() < type_params_str.len() {
+                    semantic_data.error(
+                        DiagnosticCode::TemplateParams,
+                        ty.pos,
+                        format!(
+                            "Type parameter is missing: {:?}",
+                            type_params_str[type_params.len()]
+                        ),
+                    );
+                    return LazeType_::none_type();
+                } else if type_params.len() > type_params_str.len() {
+                    semantic_data.error(
+                        DiagnosticCode::TemplateParams,
+                        type_params[type_params_str.len()].pos,
+                        "There are too many type parameters.".to_string(),
+                    );
+                    return LazeType_::none_type();
+                }
+                if !check_constraints(
+                    ty.pos,
+                    name,
+                    &type_params_str,
+                    &type_params_lazetype,
+                    &constraints,
+                    &template_venv,
+                    semantic_data,
+                ) {
+                    return LazeType_::none_type();
+                }
+                // the members can use this instance before its size is known
+                if let Some(EnvEntry::Template(_, template_map, _, _, _)) =
+                    semantic_data.tenv.get_mut_data(name)
                 {
-                    let specific_template = template_map.get_data(&type_params);
-                    if let Some(template) = specific_template {
-                        if let EnvEntry::Class(_, _, class_size) = template {
-                            return LazeType_::template_type(
-                                name.clone(),
-                                type_params_lazetype,
-                                type_params.clone(),
-                                class_size.clone(),
-                            );
-                        }
-                    } else {
-                        if type_params.len() == type_params_str.len() {
-                            for (index, param) in type_params.iter().enumerate() {
-                                let poly_entry = EnvEntry::Poly(trans_ty(param, semantic_data));
-                                semantic_data
-                                    .tenv
-                                    .add_data(type_params_str[index].clone(), poly_entry);
-                            }
-                        } else if type_params.len() < type_params_str.len() {
-                            let _ = writeln!(
-                                stderr(),
-                                "Type parameter is missing: {:?}",
-                                type_params_str[type_params.len()]
-                            );
-                            return LazeType_::none_type();
-                        } else if type_params.len() > type_params_str.len() {
-                            let _ = writeln!(
-                                stderr(),
-                                "There are too many type parameters: {:?}",
-                                type_params[type_params_str.len()]
-                            );
-                            return LazeType_::none_type();
-                        }
-                        swap(&mut semantic_data.venv, &mut template_venv);
-                        let _ = trans_dec(&original_dec, Some(&ty), semantic_data);
-                        swap(&mut semantic_data.venv, &mut template_venv);
-                        if type_params.len() == type_params_str.len() {
-                            for type_str in type_params_str.iter() {
-                                semantic_data.tenv.remove_data(type_str);
-                            }
-                        }
-                        let specific_template = template_map.get_data(&type_params);
-                        if let Some(template) = specific_template {
-                            if let EnvEntry::Class(_, _, class_size) = template {
-                                return LazeType_::template_type(
-                                    name.clone(),
-                                    type_params_lazetype,
-                                    type_params.clone(),
-                                    class_size.clone(),
-                                );
-                            }
-                        }
-                        return LazeType_::none_type();
-                    }
+                    template_map.add_data(type_params_lazetype.clone(), EnvEntry::None);
                 }
+                let scope = enter_template(
+                    &type_params_str,
+                    &type_params_lazetype,
+                    template_venv,
+                    semantic_data,
+                );
+                let _ = trans_dec(&original_dec, Some(ty), semantic_data);
+                exit_template(scope, semantic_data);
             }
-            LazeType_::none_type()
+            let class_size = match semantic_data.tenv.get_data(name) {
+                Some(EnvEntry::Template(_, template_map, _, _, _)) => {
+                    match template_map.get_data(&type_params_lazetype) {
+                        Some(EnvEntry::Class(_, _, class_size, _, _)) => *class_size,
+                        Some(EnvEntry::None) => 0,
+                        _ => return LazeType_::none_type(),
+                    }
+                }
+                _ => return LazeType_::none_type(),
+            };
+            LazeType_::template_type(
+                name.clone(),
+                type_params_lazetype,
+                type_params.clone(),
+                class_size,
+            )
         }
         TypeData::None => {
-            let _ = writeln!(stderr(), "Type is not valid: {:?}", ty.pos);
+            semantic_data.error(
+                DiagnosticCode::InvalidType,
+                ty.pos,
+                "Type is not valid.".to_string(),
+            );
             LazeType_::none_type()
         }
     }
@@ -142,7 +170,7 @@ pub fn trans_params(list: &FieldList, semantic_data: &mut SemanticParam) -> Laze
     for field in list {
         match &field.data {
             FieldData::Field(var, var_ty) => {
-                let (_, new_var_ty, _) = trans_var_ty(var, var_ty);
+                let (_, new_var_ty, _) = trans_var_ty(var, var_ty, semantic_data);
                 let new_var_lazetype = trans_ty(&new_var_ty, semantic_data);
                 result.push(new_var_lazetype);
             }
@@ -158,10 +186,10 @@ pub fn trans_result<'a>(
     semantic_data: &mut SemanticParam,
 ) -> (Option<&'a Var>, LazeType) {
     let return_field = if result_list.len() > 1 {
-        let _ = writeln!(
-            stderr(),
-            "Laze does not support multiple return values: {:?}",
-            pos
+        semantic_data.error(
+            DiagnosticCode::MultipleReturnValues,
+            pos,
+            "Laze does not support multiple return values.".to_string(),
         );
         &result_list[0].data
     } else if result_list.len() == 1 {
@@ -171,14 +199,18 @@ pub fn trans_result<'a>(
     };
     match return_field {
         FieldData::Field(var, ty) => {
-            let (new_var, new_var_ty, _object_explist) = trans_var_ty(var, ty);
+            let (new_var, new_var_ty, _object_explist) = trans_var_ty(var, ty, semantic_data);
             (Some(new_var), trans_ty(&new_var_ty, semantic_data))
         }
         FieldData::None => (None, LazeType_::void_type()),
     }
 }
 
-pub fn trans_var_ty<'a>(var: &'a Var, var_ty: &Type) -> (&'a Var, Type, Option<&'a ASTExpList>) {
+pub fn trans_var_ty<'a>(
+    var: &'a Var,
+    var_ty: &Type,
+    semantic_data: &mut SemanticParam,
+) -> (&'a Var, Type, Option<&'a ASTExpList>) {
     let mut object_explist = None;
     match &var.data {
         VarData::Pointer(pointer_var) => (
@@ -200,19 +232,23 @@ pub fn trans_var_ty<'a>(var: &'a Var, var_ty: &Type) -> (&'a Var, Type, Option<&
                     _ => {}
                 }
             }
-            for suffix in suffixlist {
+            for suffix in suffixlist.iter().skip(1) {
                 match &suffix.data {
                     SuffixData::Subscript(index) => {
-                        let _ = writeln!(
-                            stderr(),
-                            "Warning: Avoid using N-Dimensional arrays: {:?}",
-                            var.pos
+                        semantic_data.warning(
+                            DiagnosticCode::MultiDimensionalArray,
+                            var.pos,
+                            "Avoid using N-Dimensional arrays.".to_string(),
                         );
                         temp_var_ty =
                             Type_::array_type(temp_var_ty.pos, temp_var_ty, index.clone());
                     }
                     SuffixData::Call(_) => {
-                        let _ = writeln!(stderr(), "Unknown declaration: {:?}", var.pos);
+                        semantic_data.error(
+                            DiagnosticCode::InvalidDeclaration,
+                            var.pos,
+                            "Unknown declaration.".to_string(),
+                        );
                     }
                     _ => {}
                 }
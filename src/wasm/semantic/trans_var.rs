@@ -1,26 +1,29 @@
-use std::io::{stderr, Write};
-
 use crate::{
     ast::{
         dec::MemberSpecifier,
-        suffix::{ASTExpSuffixList, ASTExpSuffix_, SuffixData},
+        suffix::{ASTExpSuffix, ASTExpSuffixList, ASTExpSuffix_, SuffixData},
         ty::TypeData,
         var::{Var, VarData, Var_},
     },
+    util::diagnostic::{Diagnostic, DiagnosticCode},
     wasm::{
         frame::frame::FrameType,
         il::{
-            exp::{Exp, Exp_},
-            util::{BinOper, WasmExpTy},
+            exp::{Exp, ExpList, Exp_},
+            util::{BinOper, WasmExpTy, WasmType},
         },
     },
 };
 
 use super::{
-    entry_map::{EntryMap, EnvEntry},
-    laze_type::{LazeType, LazeTypeData, LazeType_},
+    entry_map::{EntryMap, EnvEntry, VTable},
+    laze_type::{FuncRef, LazeType, LazeTypeData, LazeTypeList, LazeType_},
     semantic_param::SemanticParam,
+    trans_closure::{trans_closure_call, trans_func_value},
     trans_exp::{trans_access_to_exp, trans_exp, trans_explist},
+    trans_object::trans_free_args,
+    trans_template::{is_func_template, trans_template_call},
+    trans_ty::trans_ty,
 };
 
 pub fn trans_right_var(var: &Var, semantic_data: &mut SemanticParam) -> WasmExpTy {
@@ -30,16 +33,21 @@ pub fn trans_right_var(var: &Var, semantic_data: &mut SemanticParam) -> WasmExpT
         }
         VarData::Pointer(pointer_var) => trans_right_var(pointer_var, semantic_data),
         VarData::Simple(name) => {
-            let var_entry = semantic_data.venv.get_data(name);
+            let var_entry = semantic_data.get_var_entry(name);
             if let Some(entry) = var_entry {
                 if let EnvEntry::Var(ty, access) = entry {
-                    WasmExpTy::new_exp(ty.clone(), trans_access_to_exp(access, var, name))
+                    let exp = trans_access_to_exp(&access, &ty, var, name, semantic_data);
+                    WasmExpTy::new_exp(ty, exp)
                 } else {
                     let checked_var = check_member(var, semantic_data);
                     if let Some(checked_var_exists) = checked_var {
                         return trans_right_var(&checked_var_exists, semantic_data);
                     } else {
-                        let _ = writeln!(stderr(), "{:?} is not a variable: {:?}", name, var.pos);
+                        semantic_data.error(
+                            DiagnosticCode::NotAVariable,
+                            var.pos,
+                            format!("{:?} is not a variable.", name),
+                        );
                         WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
                     }
                 }
@@ -48,23 +56,58 @@ pub fn trans_right_var(var: &Var, semantic_data: &mut SemanticParam) -> WasmExpT
                 if let Some(checked_var_exists) = checked_var {
                     return trans_right_var(&checked_var_exists, semantic_data);
                 } else {
-                    let _ = writeln!(
-                        stderr(),
-                        "Could not find variable {:?}: {:?}",
-                        name,
-                        var.pos
+                    semantic_data.error(
+                        DiagnosticCode::UndefinedVariable,
+                        var.pos,
+                        format!("Could not find variable {:?}.", name),
                     );
                     WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
                 }
             }
         }
+        VarData::Template(..) => trans_suffix_var_to_addr(var, &vec![], semantic_data),
         VarData::None => {
-            let _ = writeln!(stderr(), "Not a variable: {:?}", var.pos);
+            semantic_data.error(
+                DiagnosticCode::NotAVariable,
+                var.pos,
+                "Not a variable.".to_string(),
+            );
             WasmExpTy::new_exp(LazeType_::none_type(), Exp_::none_exp())
         }
     }
 }
 
+// Returns the value of a simple var or a suffix var.
+// Members and elements are loaded from their address unless they are escaped.
+pub fn trans_var_value(var: &Var, semantic_data: &mut SemanticParam) -> WasmExpTy {
+    match &var.data {
+        VarData::SuffixVar(base_var, suffixlist) => {
+            let result = trans_suffix_var_to_addr(base_var, suffixlist, semantic_data);
+            match suffixlist.last().map(|suffix| &suffix.data) {
+                Some(SuffixData::Call(_)) | None => result,
+                Some(_) if result.ty.escape => result,
+                Some(_) => {
+                    let (ty, exp) = result.ty_exp(
+                        &mut semantic_data.diagnostics,
+                        var.pos,
+                        "The variable does not have an address.",
+                    );
+                    let wasm_ty = ty.to_wasm_type();
+                    WasmExpTy::new_exp(ty, Exp_::load_exp(wasm_ty, exp))
+                }
+            }
+        }
+        VarData::Simple(name) if semantic_data.venv.get_data(name).is_none() => {
+            // a member used in a method without self->
+            match check_member(var, semantic_data) {
+                Some(member_var) => trans_var_value(&member_var, semantic_data),
+                None => trans_suffix_var_to_addr(var, &vec![], semantic_data),
+            }
+        }
+        _ => trans_suffix_var_to_addr(var, &vec![], semantic_data),
+    }
+}
+
 // Returns address of the var
 // because all suffix vars are escaped
 pub fn trans_suffix_var_to_addr(
@@ -72,171 +115,491 @@ pub fn trans_suffix_var_to_addr(
     suffixlist: &ASTExpSuffixList,
     semantic_data: &mut SemanticParam,
 ) -> WasmExpTy {
-    let name = &get_var_name(&var);
+    let name = &get_var_name(var, semantic_data);
 
-    if let Some(var_entry) = semantic_data.venv.get_data(name) {
-        let (mut ty, mut result_exp) = match var_entry {
-            EnvEntry::Var(ty, access) => (ty.clone(), trans_access_to_exp(access, &var, name)),
+    match &var.data {
+        // (*p).method() calls the method of the object that p points to
+        VarData::Pointer(pointer_var) if !suffixlist.is_empty() => {
+            let (ty, exp) = trans_var_value(pointer_var, semantic_data).ty_exp(
+                &mut semantic_data.diagnostics,
+                pointer_var.pos,
+                "The pointer did not give a value.",
+            );
+            let (ty, exp) = match ty.data {
+                LazeTypeData::Pointer(pointee) if pointee.escape => (pointee, exp),
+                LazeTypeData::Pointer(pointee) => {
+                    let wasm_ty = pointee.to_wasm_type();
+                    (pointee, Exp_::load_exp(wasm_ty, exp))
+                }
+                _ => {
+                    semantic_data.error(
+                        DiagnosticCode::NotAPointer,
+                        var.pos,
+                        format!("Cannot dereference {:?} because it is not a pointer.", name),
+                    );
+                    return WasmExpTy::none();
+                }
+            };
+            return trans_suffixlist(var, name, ty, exp, suffixlist, None, semantic_data);
+        }
+        // (a.b).c() is the same as a.b.c()
+        VarData::SuffixVar(base_var, base_suffixlist) => {
+            let mut new_suffixlist = base_suffixlist.clone();
+            new_suffixlist.append(&mut suffixlist.clone());
+            return trans_suffix_var_to_addr(base_var, &new_suffixlist, semantic_data);
+        }
+        _ => {}
+    }
+
+    // a generic function is instantiated by the call after it
+    let type_args = match &var.data {
+        VarData::Template(_, type_args) => Some(type_args),
+        _ => None,
+    };
+    if type_args.is_some()
+        || (semantic_data.venv.get_data(name).is_none() && is_func_template(name, semantic_data))
+    {
+        return match suffixlist.first().map(|suffix| &suffix.data) {
+            Some(SuffixData::Call(explist)) => {
+                let (ty, result_exp) =
+                    trans_template_call(var.pos, name, type_args, explist, semantic_data);
+                trans_suffixlist(
+                    var,
+                    name,
+                    ty,
+                    result_exp,
+                    &suffixlist[1..],
+                    None,
+                    semantic_data,
+                )
+            }
+            _ => {
+                semantic_data.error(
+                    DiagnosticCode::NotAFunction,
+                    var.pos,
+                    format!(
+                        "{:?} is a generic function, so it can only be called.",
+                        name
+                    ),
+                );
+                WasmExpTy::none()
+            }
+        };
+    }
+    if let Some(var_entry) = semantic_data.get_var_entry(name) {
+        // the overloaded function is chosen by the arguments of the call after it
+        let mut overloads: Option<Vec<(i32, LazeTypeList, LazeType)>> = None;
+        if let EnvEntry::Func(..) = var_entry {
+            let funcs = semantic_data.venv.get_overloads(name);
+            if funcs.len() > 1 {
+                overloads = Some(
+                    funcs
+                        .into_iter()
+                        .filter_map(|entry| match entry {
+                            EnvEntry::Func(index, params, return_ty) => {
+                                Some((*index, params.clone(), return_ty.clone()))
+                            }
+                            _ => None,
+                        })
+                        .collect(),
+                );
+            }
+        }
+        let (ty, result_exp) = match var_entry {
+            EnvEntry::Var(ty, access) => {
+                let exp = trans_access_to_exp(&access, &ty, var, name, semantic_data);
+                (ty, exp)
+            }
             // the function was declared with func: <ID> () => () {<body>}
-            // this function returns an address
+            // it is called by the suffix after it, or made into a closure if it is used as a value
             EnvEntry::Func(index, params, return_ty) => (
-                // check params type
-                LazeType_::func_type(params.clone(), return_ty.clone(), -(index + 1)),
-                Exp_::none_exp(), // Exp_::call_exp(
-                                  //     return_ty.to_wasm_type(),
-                                  //     *index,
-                                  //     get_args_from_suffixlist(suffixlist, semantic_data, var.pos),
-                                  //     None,
-                                  // ),
+                LazeType_::func_type(params, return_ty, FuncRef::Direct(index)),
+                Exp_::none_exp(),
             ),
             _ => {
-                let _ = writeln!(
-                    stderr(),
-                    "{} is not a variable nor a function: {:?}",
-                    name,
-                    var.pos
+                semantic_data.error(
+                    DiagnosticCode::NotAVariable,
+                    var.pos,
+                    format!("{} is not a variable nor a function.", name),
                 );
                 (LazeType_::none_type(), Exp_::none_exp())
             }
         };
-        for suffix in suffixlist {
-            match &suffix.data {
-                SuffixData::Subscript(index) => {
-                    match &ty.data {
-                        LazeTypeData::Array(element_ty, _) => {
-                            ty = element_ty.clone();
-                        }
-                        LazeTypeData::Class(_class_name) => {
-                            // operator overloading for subscript
-                        }
-                        LazeTypeData::Template(_class_name, _type_param, _) => {
-                            // operator overloading for subscript
+        trans_suffixlist(
+            var,
+            name,
+            ty,
+            result_exp,
+            suffixlist,
+            overloads,
+            semantic_data,
+        )
+    } else {
+        let checked_var = check_member(var, semantic_data);
+        if let Some(checked_var_exists) = checked_var {
+            // the member is self->member, followed by the suffixes
+            if let VarData::SuffixVar(self_var, member_suffixlist) = &checked_var_exists.data {
+                let mut new_suffixlist = member_suffixlist.clone();
+                new_suffixlist.append(&mut suffixlist.clone());
+                return trans_suffix_var_to_addr(self_var, &new_suffixlist, semantic_data);
+            }
+            return trans_suffix_var_to_addr(&checked_var_exists, suffixlist, semantic_data);
+        } else {
+            semantic_data.error(
+                DiagnosticCode::UndefinedVariable,
+                var.pos,
+                format!("Could not find a variable or function named {:?}.", name),
+            );
+            WasmExpTy::none()
+        }
+    }
+}
+
+// The suffixes are applied from left to right to the variable or the result of a call.
+fn trans_suffixlist(
+    var: &Var,
+    name: &String,
+    mut ty: LazeType,
+    mut result_exp: Exp,
+    suffixlist: &[ASTExpSuffix],
+    mut overloads: Option<Vec<(i32, LazeTypeList, LazeType)>>,
+    semantic_data: &mut SemanticParam,
+) -> WasmExpTy {
+    // whether result_exp is the address of a member or an element, rather than a value
+    let mut is_addr = false;
+    for suffix in suffixlist {
+        let pointer_is_addr = is_addr;
+        is_addr = !matches!(suffix.data, SuffixData::Call(_));
+        match &suffix.data {
+            SuffixData::Subscript(index) => {
+                match &ty.data {
+                    LazeTypeData::Array(element_ty, _) => {
+                        ty = element_ty.clone();
+                    }
+                    // the elements of memory from メモリ確保 are used through a pointer
+                    LazeTypeData::Pointer(element_ty) => {
+                        ty = element_ty.clone();
+                        if pointer_is_addr {
+                            result_exp = Exp_::load_exp(WasmType::I32, result_exp);
                         }
-                        _ => {}
                     }
-                    result_exp = Exp_::add_addr_exp(
-                        result_exp,
-                        Exp_::mul_addr_exp(
-                            Exp_::consti32_exp(ty.size),
-                            trans_exp(index, semantic_data).exp("".to_string()),
-                        ),
-                    )
+                    LazeTypeData::Class(_class_name) => {
+                        // operator overloading for subscript
+                    }
+                    LazeTypeData::Template(_class_name, _type_param, _) => {
+                        // operator overloading for subscript
+                    }
+                    _ => {}
                 }
-                SuffixData::Dot(field) => {
-                    (ty, result_exp) =
-                        trans_dot_var(field, &ty, result_exp, semantic_data, var.pos, name)
+                let mut index_exp = trans_exp(index, semantic_data).exp(
+                    &mut semantic_data.diagnostics,
+                    index.pos,
+                    "The expression did not give a value.",
+                );
+                if index_exp.ty != WasmType::I32 {
+                    index_exp = Exp_::convert_exp(WasmType::I32, index_exp);
+                }
+                result_exp = Exp_::add_addr_exp(
+                    result_exp,
+                    Exp_::mul_addr_exp(Exp_::consti32_exp(ty.size), index_exp),
+                )
+            }
+            SuffixData::Dot(field) => {
+                (ty, result_exp) =
+                    trans_dot_var(field, &ty, result_exp, semantic_data, var.pos, name);
+                // the member that could not be used is already reported,
+                // so the suffixes after it, such as the call of a private method, are not translated
+                if ty.data == LazeTypeData::None {
+                    return WasmExpTy::none();
                 }
-                SuffixData::Call(explist) => {
-                    // class methods will be function variables
-                    if let LazeTypeData::Func(_typelist, return_type, type_index) = ty.data {
-                        // check param type
-                        ty = return_type.clone();
-                        if type_index < 0 {
-                            result_exp = Exp_::call_exp(
-                                ty.to_wasm_type(),
-                                -(type_index + 1),
-                                trans_explist(explist, semantic_data).1,
-                                None,
-                            )
-                        } else {
-                            result_exp = Exp_::call_indirect_exp(
-                                ty.to_wasm_type(),
-                                result_exp,
-                                type_index,
-                                trans_explist(explist, semantic_data).1,
-                            );
+            }
+            SuffixData::Call(explist) => {
+                if let LazeTypeData::Method(typelist, return_type, func_num, vtable) = ty.data {
+                    let (args_ty, args) = trans_explist(explist, semantic_data);
+                    check_args(name, &typelist[1..], &args_ty, var.pos, semantic_data);
+                    ty = return_type;
+                    result_exp = trans_method_call(
+                        result_exp,
+                        func_num,
+                        vtable,
+                        &typelist,
+                        &ty,
+                        args,
+                        semantic_data,
+                    );
+                } else if let LazeTypeData::Func(typelist, mut return_type, mut func_ref) = ty.data
+                {
+                    let (args_ty, args) = trans_explist(explist, semantic_data);
+                    match overloads.take() {
+                        Some(candidates) => {
+                            if let Some((index, _, candidate_return_type)) =
+                                resolve_overload(name, candidates, &args_ty, var.pos, semantic_data)
+                            {
+                                (return_type, func_ref) =
+                                    (candidate_return_type, FuncRef::Direct(index));
+                            }
                         }
-                    } else {
-                        let _ = writeln!(
-                            stderr(),
-                            "Cannot call {:?} because it is not a function: {:?}",
-                            name,
-                            var.pos
-                        );
+                        None => check_args(name, &typelist, &args_ty, var.pos, semantic_data),
                     }
+                    ty = return_type.clone();
+                    result_exp = match func_ref {
+                        FuncRef::Direct(func_num) => {
+                            let args = if func_num == semantic_data.free_func {
+                                trans_free_args(&args_ty, args, semantic_data)
+                            } else {
+                                args
+                            };
+                            Exp_::call_exp(ty.to_wasm_type(), func_num, args, None)
+                        }
+                        FuncRef::Indirect(type_index) => {
+                            trans_closure_call(result_exp, type_index, &ty, args, semantic_data)
+                        }
+                    };
+                } else {
+                    semantic_data.error(
+                        DiagnosticCode::NotAFunction,
+                        var.pos,
+                        format!("Cannot call {:?} because it is not a function.", name),
+                    );
                 }
-                SuffixData::Arrow(field) => match ty.data {
-                    LazeTypeData::Pointer(pointer_ty) => {
-                        ty = pointer_ty;
-                        result_exp = Exp_::load_exp(ty.to_wasm_type(), result_exp);
-                        (ty, result_exp) =
-                            trans_dot_var(field, &ty, result_exp, semantic_data, var.pos, name);
+            }
+            SuffixData::Arrow(field) => match ty.data {
+                LazeTypeData::Pointer(pointer_ty) => {
+                    ty = pointer_ty;
+                    if pointer_is_addr {
+                        result_exp = Exp_::load_exp(WasmType::I32, result_exp);
                     }
-                    _ => {
-                        let _ = writeln!(
-                            stderr(),
-                            "To use the arrow operator, {:?} needs to be a pointer: {:?}",
-                            name,
-                            var.pos
-                        );
+                    (ty, result_exp) =
+                        trans_dot_var(field, &ty, result_exp, semantic_data, var.pos, name);
+                    if ty.data == LazeTypeData::None {
+                        return WasmExpTy::none();
                     }
-                },
-            }
+                }
+                _ => {
+                    semantic_data.error(
+                        DiagnosticCode::NotAPointer,
+                        var.pos,
+                        format!(
+                            "To use the arrow operator, {:?} needs to be a pointer.",
+                            name
+                        ),
+                    );
+                }
+            },
         }
-        WasmExpTy::new_exp(ty, result_exp)
+    }
+    if overloads.is_some() {
+        semantic_data.error(
+            DiagnosticCode::NotAFunction,
+            var.pos,
+            format!("{:?} is overloaded, so it can only be called.", name),
+        );
+    } else if let LazeTypeData::Func(params, return_ty, FuncRef::Direct(func_num)) = &ty.data {
+        return trans_func_value(*func_num, params, return_ty, semantic_data);
+    }
+    WasmExpTy::new_exp(ty, result_exp)
+}
+
+// A function whose parameter types are the argument types is chosen first.
+// Otherwise the function has to be the only one that can take the arguments,
+// where a pointer to a derived class can be passed for a pointer to its base class.
+pub fn resolve_overload(
+    name: &String,
+    candidates: Vec<(i32, LazeTypeList, LazeType)>,
+    args_ty: &LazeTypeList,
+    pos: (usize, usize),
+    semantic_data: &mut SemanticParam,
+) -> Option<(i32, LazeTypeList, LazeType)> {
+    let (exact, others): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .filter(|(_, params, _)| params.len() == args_ty.len())
+        .partition(|(_, params, _)| {
+            params
+                .iter()
+                .zip(args_ty)
+                .all(|(param_ty, arg_ty)| param_ty.data == arg_ty.data)
+        });
+    if exact.len() == 1 {
+        return exact.into_iter().next();
+    }
+    let mut fitting = if exact.is_empty() {
+        others
+            .into_iter()
+            .filter(|(_, params, _)| {
+                params
+                    .iter()
+                    .zip(args_ty)
+                    .all(|(param_ty, arg_ty)| is_derived_pointer(param_ty, arg_ty, semantic_data))
+            })
+            .collect()
     } else {
-        let checked_var = check_member(var, semantic_data);
-        if let Some(checked_var_exists) = checked_var {
-            return trans_suffix_var_to_addr(&checked_var_exists, suffixlist, semantic_data);
-        } else {
-            let _ = writeln!(
-                stderr(),
-                "Could not find a variable or function named {:?}",
-                name
+        exact
+    };
+    match fitting.len() {
+        1 => fitting.pop(),
+        0 => {
+            semantic_data.error(
+                DiagnosticCode::TypeMismatch,
+                pos,
+                format!(
+                    "None of the functions named {:?} can take these arguments.",
+                    name
+                ),
             );
-            WasmExpTy::none()
+            None
+        }
+        candidate_num => {
+            semantic_data.error(
+                DiagnosticCode::TypeMismatch,
+                pos,
+                format!(
+                    "The call to {:?} is ambiguous: {} functions can take these arguments.",
+                    name, candidate_num
+                ),
+            );
+            None
         }
     }
 }
 
+// whether arg_ty is a pointer to a class derived from the class that param_ty points to,
+// or any pointer when param_ty is a pointer to void
+fn is_derived_pointer(
+    param_ty: &LazeType,
+    arg_ty: &LazeType,
+    semantic_data: &SemanticParam,
+) -> bool {
+    match (&param_ty.data, &arg_ty.data) {
+        (LazeTypeData::Pointer(param_pointee), LazeTypeData::Pointer(arg_pointee)) => {
+            match (&param_pointee.data, &arg_pointee.data) {
+                (LazeTypeData::Void, _) => true,
+                (LazeTypeData::Class(param_class), LazeTypeData::Class(arg_class)) => {
+                    semantic_data.is_derived_class(arg_class, param_class)
+                }
+                _ => param_pointee.data == arg_pointee.data,
+            }
+        }
+        _ => param_ty.data == arg_ty.data,
+    }
+}
+
+// The address of the object is passed as self.
+// A virtual method is called through the vtable of the object,
+// so that the method of the derived class is called through a pointer to the base class.
+pub fn trans_method_call(
+    object_exp: Exp,
+    func_num: i32,
+    vtable: Option<(i32, i32)>,
+    params_ty: &LazeTypeList,
+    return_type: &LazeType,
+    mut args: ExpList,
+    semantic_data: &mut SemanticParam,
+) -> Exp {
+    let (pointer_offset, slot) = match vtable {
+        Some(vtable) => vtable,
+        None => {
+            args.insert(0, object_exp);
+            return Exp_::call_exp(return_type.to_wasm_type(), func_num, args, None);
+        }
+    };
+    let object_local = semantic_data
+        .frame
+        .last_mut()
+        .unwrap()
+        .alloc_local(LazeType_::pointer_type(LazeType_::void_type()));
+    args.insert(
+        0,
+        Exp_::teelocal_exp(WasmType::I32, object_local, object_exp),
+    );
+    let type_index = semantic_data.func_type_index(
+        LazeType_::list_to_wasm_type(params_ty),
+        return_type.to_wasm_type(),
+    );
+    Exp_::call_indirect_exp(
+        return_type.to_wasm_type(),
+        Exp_::add_addr_exp(
+            Exp_::load_exp(
+                WasmType::I32,
+                Exp_::add_addr_exp(
+                    Exp_::getlocal_exp(WasmType::I32, object_local),
+                    Exp_::consti32_exp(pointer_offset),
+                ),
+            ),
+            Exp_::consti32_exp(slot),
+        ),
+        type_index,
+        args,
+    )
+}
+
+// The arguments of a call to a function that is not overloaded are checked as an overload would be:
+// their number and types have to fit the parameters.
+pub fn check_args(
+    name: &str,
+    params_ty: &[LazeType],
+    args_ty: &[LazeType],
+    pos: (usize, usize),
+    semantic_data: &mut SemanticParam,
+) {
+    if params_ty.len() != args_ty.len() {
+        semantic_data.error(
+            DiagnosticCode::TypeMismatch,
+            pos,
+            format!(
+                "{:?} takes {} arguments, but {} are given.",
+                name,
+                params_ty.len(),
+                args_ty.len()
+            ),
+        );
+        return;
+    }
+    for (index, (param_ty, arg_ty)) in params_ty.iter().zip(args_ty).enumerate() {
+        // an argument that did not give a value is already reported
+        if arg_ty.data == LazeTypeData::None || is_derived_pointer(param_ty, arg_ty, semantic_data)
+        {
+            continue;
+        }
+        semantic_data.error(
+            DiagnosticCode::TypeMismatch,
+            pos,
+            format!(
+                "Argument {} of {:?} does not have the type of its parameter.",
+                index + 1,
+                name
+            ),
+        );
+    }
+}
+
 // TODO: Make cleaner / fix bugs(haven't found them yet)
 pub fn check_member<'a>(var: &'a Var, semantic_data: &mut SemanticParam) -> Option<Var> {
-    let var_name = get_var_name(var);
+    let var_name = get_var_name(var, semantic_data);
     let frame = semantic_data.current_frame();
     if let Some(frame_exists) = frame {
         if let FrameType::Method(name, parent_class) = &frame_exists.data {
-            let members_map = match &parent_class.data {
-                TypeData::Name(class) => {
-                    if let Some(EnvEntry::Class(_, members_map, _)) =
-                        semantic_data.tenv.get_data(class)
-                    {
-                        members_map
-                    } else {
-                        return None;
-                    }
+            let name = name.clone();
+            let parent_class = parent_class.clone();
+            let class_type = match &parent_class.data {
+                TypeData::Name(_) | TypeData::Template(_, _) => {
+                    trans_ty(&parent_class, semantic_data)
                 }
-                TypeData::Template(template_name, type_param) => {
-                    if let Some(EnvEntry::Template(_, template_map, _, _)) =
-                        semantic_data.tenv.get_data(template_name)
-                    {
-                        if let Some(EnvEntry::Class(_, members_map, _)) =
-                            template_map.get_data(type_param)
-                        {
-                            members_map
-                        } else {
-                            return None;
-                        }
-                    } else {
-                        return None;
-                    }
+                _ => {
+                    return None;
                 }
+            };
+            let members_map = match semantic_data.class_entry(&class_type) {
+                Some(EnvEntry::Class(_, members_map, _, _, _)) => members_map,
                 _ => {
                     return None;
                 }
             };
-            if let Some(member_entry) = members_map.get_data(&var_name) {
-                match member_entry {
-                    EnvEntry::Member(specifier, _, _) | EnvEntry::Method(specifier, _, _, _) => {
-                        if let MemberSpecifier::Private = specifier {
-                            return None;
-                        }
-                    }
-                    _ => {
-                        return None;
-                    }
+            // whether the member can be used here is checked when self->member is translated
+            match members_map.get_data(&var_name) {
+                Some(EnvEntry::Member(..)) | Some(EnvEntry::Method(..)) => {}
+                _ => {
+                    return None;
                 }
-            } else {
-                return None;
             }
             match &var.data {
                 VarData::Simple(name) => Some(Var_::suffix_var(
@@ -291,11 +654,12 @@ pub fn check_member<'a>(var: &'a Var, semantic_data: &mut SemanticParam) -> Opti
                             new_suffix_list,
                         ))
                     }
-                    VarData::None => {
+                    // a generic function is not a member
+                    VarData::Template(..) | VarData::None => {
                         return None;
                     }
                 },
-                VarData::None => {
+                VarData::Template(..) | VarData::None => {
                     return None;
                 }
             }
@@ -307,68 +671,131 @@ pub fn check_member<'a>(var: &'a Var, semantic_data: &mut SemanticParam) -> Opti
     }
 }
 
-pub fn get_var_name(var: &Var) -> String {
+pub fn get_var_name(var: &Var, semantic_data: &mut SemanticParam) -> String {
     match &var.data {
-        VarData::Simple(name) => name.clone(),
-        VarData::Pointer(var) => get_var_name(&var),
-        VarData::SuffixVar(var, _) => get_var_name(&var),
+        VarData::Simple(name) | VarData::Template(name, _) => name.clone(),
+        VarData::Pointer(var) => get_var_name(var, semantic_data),
+        VarData::SuffixVar(var, _) => get_var_name(var, semantic_data),
         VarData::None => {
-            let _ = writeln!(stderr(), "Variable does not exist: {:?}", var.pos);
+            semantic_data.error(
+                DiagnosticCode::UndefinedVariable,
+                var.pos,
+                "Variable does not exist.".to_string(),
+            );
             "".to_string()
         }
     }
 }
 
+// Private members can be used in the methods of the class that declares them,
+// and protected members also in the methods of its derived classes.
+fn check_member_access(
+    specifier: MemberSpecifier,
+    declaring_class: &str,
+    field_name: &String,
+    var_pos: (usize, usize),
+    semantic_data: &SemanticParam,
+) -> Result<(), Diagnostic> {
+    let current_class = semantic_data.current_class();
+    let accessible = match specifier {
+        MemberSpecifier::Public => true,
+        MemberSpecifier::Protected => current_class
+            .is_some_and(|class_name| semantic_data.is_derived_class(class_name, declaring_class)),
+        MemberSpecifier::Private => {
+            current_class.is_some_and(|class_name| class_name == declaring_class)
+        }
+    };
+    if accessible {
+        return Ok(());
+    }
+    let message = match specifier {
+        MemberSpecifier::Protected => format!(
+            "{:?} is a protected member of {:?}, and can only be used in the methods of {:?} and its derived classes.",
+            field_name, declaring_class, declaring_class
+        ),
+        _ => format!(
+            "{:?} is a private member of {:?}, and can only be used in the methods of {:?}.",
+            field_name, declaring_class, declaring_class
+        ),
+    };
+    Err(Diagnostic::error(
+        DiagnosticCode::PrivateMember,
+        var_pos,
+        message,
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
 fn get_member_of_class(
     ty: &LazeType,
     result_exp: Exp,
     members: &EntryMap,
+    vtable: Option<&VTable>,
     field_name: &String,
     class_name: &String,
     var_pos: (usize, usize),
-) -> (LazeType, Exp) {
+    semantic_data: &SemanticParam,
+) -> Result<(LazeType, Exp), Diagnostic> {
     let member_entry = members.get_data(field_name);
     if let Some(member_entry_exists) = member_entry {
         match member_entry_exists {
-            EnvEntry::Member(specifier, member_ty, offset) => {
-                if let MemberSpecifier::Public = specifier {
-                    (
-                        member_ty.clone(),
-                        Exp_::binop_exp(
-                            ty.to_wasm_type(),
-                            BinOper::Add,
-                            result_exp,
-                            Exp_::consti32_exp(*offset),
-                        ),
-                    )
-                } else {
-                    let _ = writeln!(
-                        stderr(),
-                        "Cannot access a member that is not public: {:?}",
-                        var_pos
-                    );
-                    (LazeType_::none_type(), Exp_::none_exp())
-                }
+            EnvEntry::Member(specifier, member_ty, offset, declaring_class) => {
+                check_member_access(
+                    *specifier,
+                    declaring_class,
+                    field_name,
+                    var_pos,
+                    semantic_data,
+                )?;
+                Ok((
+                    member_ty.clone(),
+                    Exp_::binop_exp(
+                        ty.to_wasm_type(),
+                        BinOper::Add,
+                        result_exp,
+                        Exp_::consti32_exp(*offset),
+                    ),
+                ))
             }
-            _ => {
-                let _ = writeln!(
-                    stderr(),
-                    "{} is not a member of {}.",
+            // the method is bound to the object, and called by the call suffix after it
+            EnvEntry::Method(
+                specifier,
+                func_num,
+                params,
+                return_type,
+                vtable_slot,
+                declaring_class,
+            ) => {
+                check_member_access(
+                    *specifier,
+                    declaring_class,
                     field_name,
-                    class_name
-                );
-                (LazeType_::none_type(), Exp_::none_exp())
+                    var_pos,
+                    semantic_data,
+                )?;
+                let vtable = vtable_slot
+                    .zip(vtable)
+                    .map(|(slot, vtable)| (vtable.pointer_offset, slot));
+                Ok((
+                    LazeType_::method_type(params.clone(), return_type.clone(), *func_num, vtable),
+                    result_exp,
+                ))
             }
+            _ => Err(Diagnostic::error(
+                DiagnosticCode::UndefinedMember,
+                var_pos,
+                format!("{} is not a member of {}.", field_name, class_name),
+            )),
         }
     } else {
-        let _ = writeln!(
-            stderr(),
-            "Could not find member {:?} in class {:?}: {:?}",
-            field_name,
-            class_name,
-            var_pos
-        );
-        return (LazeType_::none_type(), Exp_::none_exp());
+        Err(Diagnostic::error(
+            DiagnosticCode::UndefinedMember,
+            var_pos,
+            format!(
+                "Could not find member {:?} in class {:?}.",
+                field_name, class_name
+            ),
+        ))
     }
 }
 
@@ -380,39 +807,71 @@ fn trans_dot_var(
     var_pos: (usize, usize),
     name: &String,
 ) -> (LazeType, Exp) {
-    match &ty.data {
+    let member = match &ty.data {
         LazeTypeData::Class(name) => {
             let class_entry = semantic_data.tenv.get_data(&name);
-            if let Some(EnvEntry::Class(_, members, _)) = class_entry {
-                get_member_of_class(&ty, result_exp, &members, &field, &name, var_pos)
+            if let Some(EnvEntry::Class(_, members, _, _, vtable)) = class_entry {
+                get_member_of_class(
+                    ty,
+                    result_exp,
+                    members,
+                    vtable.as_ref(),
+                    field,
+                    name,
+                    var_pos,
+                    semantic_data,
+                )
             } else {
-                let _ = writeln!(stderr(), "{:?} is not a class: {:?}", name, var_pos);
-                (LazeType_::none_type(), Exp_::none_exp())
+                Err(Diagnostic::error(
+                    DiagnosticCode::NotAClass,
+                    var_pos,
+                    format!("{:?} is not a class.", name),
+                ))
             }
         }
-        LazeTypeData::Template(name, _, type_param) => {
+        LazeTypeData::Template(name, type_param, _) => {
             let template_entry = semantic_data.tenv.get_data(&name);
-            if let Some(EnvEntry::Template(_, specific, _, _)) = template_entry {
+            if let Some(EnvEntry::Template(_, specific, _, _, _)) = template_entry {
                 let class_entry = specific.get_data(type_param);
-                if let Some(EnvEntry::Class(_, members, _)) = class_entry {
-                    get_member_of_class(&ty, result_exp, members, &field, &name, var_pos)
+                if let Some(EnvEntry::Class(_, members, _, _, vtable)) = class_entry {
+                    get_member_of_class(
+                        ty,
+                        result_exp,
+                        members,
+                        vtable.as_ref(),
+                        field,
+                        name,
+                        var_pos,
+                        semantic_data,
+                    )
                 } else {
-                    let _ = writeln!(stderr(), "{:?} is not a class: {:?}", name, var_pos);
-                    (LazeType_::none_type(), Exp_::none_exp())
+                    Err(Diagnostic::error(
+                        DiagnosticCode::NotAClass,
+                        var_pos,
+                        format!("{:?} is not a class.", name),
+                    ))
                 }
             } else {
-                let _ = writeln!(stderr(), "{:?} is not a template: {:?}", name, var_pos);
-                (LazeType_::none_type(), Exp_::none_exp())
+                Err(Diagnostic::error(
+                    DiagnosticCode::NotAClass,
+                    var_pos,
+                    format!("{:?} is not a template.", name),
+                ))
             }
         }
-        _ => {
-            let _ = writeln!(
-                stderr(),
-                "Cannot take field {:?} of {:?} because it is a non-class type: {:?}",
-                field,
-                name,
-                var_pos
-            );
+        _ => Err(Diagnostic::error(
+            DiagnosticCode::NotAClass,
+            var_pos,
+            format!(
+                "Cannot take field {:?} of {:?} because it is a non-class type.",
+                field, name
+            ),
+        )),
+    };
+    match member {
+        Ok(member) => member,
+        Err(diagnostic) => {
+            semantic_data.diagnostics.push(diagnostic);
             (LazeType_::none_type(), Exp_::none_exp())
         }
     }
@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use crate::{
+    laze_parser::parser::LazeParser,
+    util::{
+        diagnostic::{has_error, DiagnosticCode, DiagnosticList},
+        file_opener::open_file,
+        source_map::SourceMap,
+    },
+    wasm::{
+        encode::encode_module::encode_tree, il::module::ModuleList, semantic::trans_ast::trans_ast,
+    },
+};
+
+use super::runner::Instance;
+
+pub struct Compiled {
+    pub module_list: ModuleList,
+    pub mem_size: i32,
+    // from both the parser and the semantic pass
+    pub diagnostics: DiagnosticList,
+}
+
+impl Compiled {
+    pub fn codes(&self) -> Vec<DiagnosticCode> {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect()
+    }
+    // Encodes the module and instantiates it, after checking that it compiled without errors.
+    pub fn instantiate(&self) -> Instance {
+        assert!(
+            !has_error(&self.diagnostics),
+            "The program did not compile: {:?}",
+            self.diagnostics
+        );
+        Instance::new(&encode_tree(&self.module_list, self.mem_size))
+    }
+}
+
+// Compiles a program written with ja.peg, as --compile does.
+pub fn compile_source(file_name: &str, source: &str) -> Compiled {
+    let mut parser = LazeParser::new(Path::new("./parser_files/ja.peg"));
+    let ast = match parser.parse_source(source) {
+        Ok(ast) => ast,
+        Err(diagnostic) => {
+            return Compiled {
+                module_list: vec![],
+                mem_size: 0,
+                diagnostics: vec![diagnostic],
+            }
+        }
+    };
+    let (module_list, mem_size, mut semantic_diagnostics) =
+        trans_ast(ast, SourceMap::new(file_name, source));
+    let mut diagnostics = std::mem::take(&mut parser.diagnostics);
+    diagnostics.append(&mut semantic_diagnostics);
+    Compiled {
+        module_list,
+        mem_size,
+        diagnostics,
+    }
+}
+
+pub fn compile(program_path: &str) -> Compiled {
+    compile_source(program_path, &open_file(Path::new(program_path)))
+}
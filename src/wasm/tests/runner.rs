@@ -0,0 +1,140 @@
+use wasmi::{core::ValType, Engine, ExternType, Func, Linker, Memory, Module, Store, Val};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+impl From<&Val> for Value {
+    fn from(val: &Val) -> Self {
+        match val {
+            Val::I32(value) => Value::I32(*value),
+            Val::I64(value) => Value::I64(*value),
+            Val::F32(value) => Value::F32(value.to_float()),
+            Val::F64(value) => Value::F64(value.to_float()),
+            _ => panic!("The generated code does not use reference values."),
+        }
+    }
+}
+
+impl From<Value> for Val {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::I32(value) => Val::I32(value),
+            Value::I64(value) => Val::I64(value),
+            Value::F32(value) => Val::F32(value.into()),
+            Value::F64(value) => Val::F64(value.into()),
+        }
+    }
+}
+
+// the name and the arguments of each call of an imported function
+type Calls = Vec<(String, Vec<Value>)>;
+
+// A module run by wasmi, which validates it when it is loaded.
+// The imported memory is made by the test, and the imported functions only record their calls.
+pub struct Instance {
+    store: Store<Calls>,
+    instance: wasmi::Instance,
+    memory: Option<Memory>,
+}
+
+// Loads the module, which panics if it is not valid.
+pub fn validate(bytes: &[u8]) -> Module {
+    Module::new(&Engine::default(), bytes)
+        .unwrap_or_else(|error| panic!("The module is not valid: {}", error))
+}
+
+impl Instance {
+    pub fn new(bytes: &[u8]) -> Self {
+        let module = validate(bytes);
+        let mut store = Store::new(module.engine(), vec![]);
+        let mut linker = Linker::<Calls>::new(module.engine());
+        let mut memory = None;
+        for import in module.imports() {
+            let (module_name, name) = (import.module(), import.name());
+            match import.ty() {
+                ExternType::Memory(memory_type) => {
+                    let imported = Memory::new(&mut store, *memory_type).unwrap();
+                    linker.define(module_name, name, imported).unwrap();
+                    memory = Some(imported);
+                }
+                ExternType::Func(func_type) => {
+                    let func_name = name.to_string();
+                    linker
+                        .func_new(
+                            module_name,
+                            name,
+                            func_type.clone(),
+                            move |mut caller, params, results| {
+                                let args = params.iter().map(Value::from).collect();
+                                caller.data_mut().push((func_name.clone(), args));
+                                for result in results.iter_mut() {
+                                    *result = Val::default(result.ty());
+                                }
+                                Ok(())
+                            },
+                        )
+                        .unwrap();
+                }
+                other => panic!("The generated code does not import {:?}.", other),
+            }
+        }
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .unwrap_or_else(|error| panic!("The module could not be instantiated: {}", error));
+        Instance {
+            store,
+            instance,
+            memory,
+        }
+    }
+
+    pub fn call(&mut self, name: &str, args: &[Value]) -> Option<Value> {
+        self.try_call(name, args)
+            .unwrap_or_else(|error| panic!("{:?} trapped: {}", name, error))
+    }
+
+    // the error is the trap, for the tests of the code that traps
+    pub fn try_call(&mut self, name: &str, args: &[Value]) -> Result<Option<Value>, String> {
+        let func: Func = self
+            .instance
+            .get_func(&self.store, name)
+            .unwrap_or_else(|| panic!("{:?} is not exported.", name));
+        let args: Vec<Val> = args.iter().map(|arg| Val::from(*arg)).collect();
+        let mut results: Vec<Val> = func
+            .ty(&self.store)
+            .results()
+            .iter()
+            .map(|ty: &ValType| Val::default(*ty))
+            .collect();
+        func.call(&mut self.store, &args, &mut results)
+            .map_err(|error| error.to_string())?;
+        Ok(results.first().map(Value::from))
+    }
+
+    // the arguments given to the calls of an imported function
+    pub fn calls_of(&self, name: &str) -> Vec<Vec<Value>> {
+        self.store
+            .data()
+            .iter()
+            .filter(|(call_name, _)| call_name == name)
+            .map(|(_, args)| args.clone())
+            .collect()
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        self.memory
+            .map(|memory| memory.data(&self.store))
+            .unwrap_or_default()
+    }
+}
+
+// the arguments of all the calls in order, for an imported function with one parameter
+pub fn outputs(calls: Vec<Vec<Value>>) -> Vec<Value> {
+    calls.into_iter().flatten().collect()
+}